@@ -0,0 +1,88 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use gtk::glib;
+use gtk::prelude::*;
+
+use crate::file_menu::actions;
+use crate::import_util::script_import;
+use crate::isv2_parameter::Isv2Parameter;
+use crate::scenario_node::ScenarioNodeSerde;
+
+const USAGE: &str = "usage: isv2 --import-script <script.(txt|md)> <project.json> [WIDTHxHEIGHT]\n\
+                     \x20 the script is appended to an existing project, and a new project\n\
+                     \x20 is created with the target size WIDTHxHEIGHT";
+
+// parse_size //////////////////////////////////////////////
+/// "1280x720" -> (1280, 720)
+fn parse_size(s: &str) -> Option<(i32, i32)>{
+    let (w, h) = s.split_once(['x', 'X'])?;
+    let w = w.trim().parse::<i32>().ok().filter(|w| (1..=9999).contains(w))?;
+    let h = h.trim().parse::<i32>().ok().filter(|h| (1..=9999).contains(h))?;
+    Some((w, h))
+}
+// import_script ///////////////////////////////////////////
+/// appends the script to the project file, or creates it with the size
+fn import_script(script: &Path, project_file: &Path, size: Option<(i32, i32)>) -> anyhow::Result<()>{
+    let mut project_dir = if let Ok(p) = dunce::canonicalize(project_file) { p } else {
+        std::env::current_dir()?.join(project_file) };
+    let project_file_name = project_dir.file_name()
+        .ok_or(anyhow::anyhow!("{project_file:?} is not a file name"))?
+        .to_str().ok_or(anyhow::anyhow!("{project_file:?} is not a UTF-8 file name"))?.to_string();
+    project_dir.pop();
+
+    let param = Isv2Parameter::new();
+    let root = if project_file.exists() {
+        anyhow::ensure!(size.is_none(), "the target size of the existing project {project_file:?} can not be changed");
+        actions::read_project_file(project_file, &param)?
+    } else {
+        let (w, h) = size.ok_or(anyhow::anyhow!("WIDTHxHEIGHT is needed to create {project_file:?}"))?;
+        param.set_property("target_width",  w);
+        param.set_property("target_height", h);
+        None
+    };
+    param.set_property("project_dir", project_dir.clone());
+    param.set_property("project_file_name", project_file_name);
+
+    let root = script_import::import_script_file(script, root, &project_dir)?;
+    let sn_ser = root.map(ScenarioNodeSerde::from_sn);
+    actions::write_project_file(project_file, &param, sn_ser)
+}
+// run /////////////////////////////////////////////////////
+/// handles command line operations which run without window,
+/// returns None when the arguments are for the gui
+pub fn run(args: &[String]) -> Option<glib::ExitCode>{
+    if args.len() < 2 { return None; }
+    match args[1].as_str() {
+        "--import-script" => {
+            let size = args.get(4).map(|s| parse_size(s));
+            if !(4..=5).contains(&args.len()) || size == Some(None) {
+                println!("{}", USAGE);
+                return Some(glib::ExitCode::FAILURE);
+            }
+            if let Err(e) = import_script(&PathBuf::from(&args[2]), &PathBuf::from(&args[3]), size.flatten()) {
+                println!("(import_script) {:?}", e);
+                return Some(glib::ExitCode::FAILURE);
+            }
+            Some(glib::ExitCode::SUCCESS)
+        },
+        "--help" => {
+            println!("{}", USAGE);
+            Some(glib::ExitCode::SUCCESS)
+        },
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1280x720"), Some((1280, 720)));
+        assert_eq!(parse_size("800X600"), Some((800, 600)));
+        assert_eq!(parse_size("0x600"), None);
+        assert_eq!(parse_size("1280"), None);
+    }
+}
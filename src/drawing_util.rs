@@ -202,3 +202,1031 @@ pub mod util {
         }
     }
 }
+
+pub mod render {
+    //! drawing of scenes and mats without PreviewWindow
+    //!
+    //! the inputs are plain data which can be sent to worker threads, and each
+    //! Renderer has its own pango font map. the preview and all exports draw
+    //! with these functions, so serial and parallel exports write the same bytes.
+
+    use std::collections::HashMap;
+    use std::collections::HashSet;
+    use std::collections::hash_map::DefaultHasher;
+    use std::fs::File;
+    use std::hash::Hasher;
+    use std::ops::Range;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::thread::JoinHandle;
+    use std::time::SystemTime;
+
+    use anyhow::Context;
+    use anyhow::Result;
+    use gtk::cairo;
+    use gtk::cairo::Antialias;
+    use gtk::cairo::FontOptions;
+    use gtk::cairo::Format;
+    use gtk::cairo::ImageSurface;
+    use gtk::cairo::MIME_TYPE_URI;
+    use gtk::cairo::SvgSurface;
+    use gtk::cairo::SvgUnit;
+    use gtk::glib;
+    use gtk::gdk_pixbuf::Colorspace;
+    use gtk::gdk_pixbuf::InterpType;
+    use gtk::gdk_pixbuf::Pixbuf;
+    use gtk::pango;
+    use gtk::pango::FontDescription;
+    use gtk::pango::Layout;
+    use gtk::pango::Style;
+    use gtk::pango::prelude::*;
+    use gtk::prelude::*;
+
+    use crate::export_util::animation;
+    use crate::export_util::contact_sheet::SheetLayout;
+    use crate::export_util::image_format::Content;
+    use crate::export_util::image_format::ImageFormat;
+    use crate::export_util::openraster;
+    use crate::export_util::stamp::Stamp;
+    use crate::export_util::stamp::StampContent;
+    use crate::isv2_parameter::LanguageSetting;
+    use crate::isv2_parameter::ResampleSetting;
+    use crate::scenario_node::LabelType;
+    use crate::scenario_node::Item;
+    use crate::scenario_node::Mat;
+    use crate::scenario_node::ScenarioNode;
+    use super::util;
+
+    // SceneJob ////////////////////////////////////////////
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SceneJob{
+        pub bgcol : (f64, f64, f64),
+        pub bg_en : bool,
+        pub bgimg : Option<PathBuf>,               // in the project dir
+        pub crop  : Option<(i32, i32, i32, i32)>,  // None: whole image
+    }
+    impl SceneJob{
+        /// scene after label resolution
+        pub fn of(scene: &Rc<ScenarioNode>, project_dir: &Path) -> Option<Self>{
+            let scene = ScenarioNode::search_def_label(scene.clone()).unwrap_or(scene.clone());
+            let value = scene.value.borrow();
+            let s = if let Item::Scene(s) = &*value { s } else { return None; };
+            let c: Vec<_> = [s.bgcol.r, s.bgcol.g, s.bgcol.b].iter().map(|c|{ (*c as f64) / 255.0 }).collect();
+            Some(Self{
+                bgcol : (c[0], c[1], c[2]),
+                bg_en : s.bg_en,
+                bgimg : s.bgimg.as_ref().map(|b| project_dir.join(b)),
+                crop  : if s.crop_en { Some((s.crop.pos.x, s.crop.pos.y, s.crop.dim.w, s.crop.dim.h)) } else { None },
+            })
+        }
+    }
+    // Filter //////////////////////////////////////////////
+    /// resampling of images: Fast (bilinear) for the preview, Best (Lanczos3 of cairo) for exports
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum Filter { #[default] Fast, Best }
+
+    // resample ////////////////////////////////////////////
+    pub fn resample(pbuf: &Pixbuf, w: i32, h: i32, filter: Filter) -> Option<Pixbuf>{
+        if (filter == Filter::Fast) || (w < 1) || (h < 1) {
+            return pbuf.scale_simple(w, h, InterpType::Bilinear); }
+        let surface = ImageSurface::create(Format::ARgb32, w, h).ok()?;
+        let cr = cairo::Context::new(&surface).ok()?;
+        cr.scale((w as f64) / (pbuf.width() as f64), (h as f64) / (pbuf.height() as f64));
+        cr.set_source_pixbuf(pbuf, 0.0, 0.0);
+        let source = cr.source();
+        source.set_filter(cairo::Filter::Best);
+        source.set_extend(cairo::Extend::Pad); // no dark fringe at the edges
+        cr.paint().ok()?;
+        drop(cr);
+        let rgba = surface_rgba(surface).ok()?;
+        Some(Pixbuf::from_bytes(&glib::Bytes::from_owned(rgba), Colorspace::Rgb, true, 8, w, h, w * 4))
+    }
+    // scale_crop //////////////////////////////////////////
+    /// crops the background image and scales it to fit in the target size
+    pub fn scale_crop(pbuf     : &Pixbuf,
+                      crop     : Option<(i32, i32, i32, i32)>,
+                      target_w : i32,
+                      target_h : i32,
+                      filter   : Filter) -> Option<Pixbuf>{
+        let (crop_x, crop_y, crop_w, crop_h) = crop.unwrap_or((0, 0, pbuf.width(), pbuf.height()));
+        let crop_pbuf = Pixbuf::new( pbuf.colorspace(), true, pbuf.bits_per_sample(), crop_w, crop_h )?;
+        pbuf.copy_area( crop_x, crop_y, crop_w, crop_h, &crop_pbuf, 0, 0 );
+        let (_, crop_target_w, crop_target_h, _, _) =
+            util::get_scale_offset(crop_w, crop_h, target_w, target_h);
+        let crop_pbuf = resample( &crop_pbuf, crop_target_w, crop_target_h, filter ).unwrap();
+
+        crop_pbuf.copy()
+    }
+    // load_scene //////////////////////////////////////////
+    /// background image and its scaled and cropped buffer
+    pub fn load_scene(job: &SceneJob, target_w: i32, target_h: i32, filter: Filter) -> (Option<Pixbuf>, Option<Pixbuf>){
+        let path = job.bgimg.clone().unwrap_or_default();
+        if let Ok(p) = Pixbuf::from_file( path ) {
+            let scale_pbuf = scale_crop(&p, job.crop, target_w, target_h, filter);
+            (Some(p), scale_pbuf)
+        } else {
+            (None, None)
+        }
+    }
+    // draw_scene //////////////////////////////////////////
+    /// bg_uri: the svg refers to the image instead of embedding it
+    pub fn draw_scene(cr         : &cairo::Context,
+                      job        : &SceneJob,
+                      pbuf       : &Option<Pixbuf>,
+                      scale_pbuf : &Option<Pixbuf>,
+                      target_w   : i32,
+                      target_h   : i32,
+                      bg_uri     : Option<&str>){
+        // fill background
+        let (r, g, b) = job.bgcol;
+        cr.set_source_rgb( r, g, b );
+        cr.rectangle(0.0, 0.0, target_w as f64, target_h as f64);
+        if cr.fill().is_err(){
+            println!("fill background failed");
+            return; }
+
+        // draw image
+        if !job.bg_en { return; }
+        let pbuf = if let Some(p) = pbuf { p } else { return; };
+        let (_, _, crop_w, crop_h) = job.crop.unwrap_or((0, 0, pbuf.width(), pbuf.height()));
+        let (_, crop_target_w, crop_target_h, crop_target_ofst_x, crop_target_ofst_y) =
+            util::get_scale_offset(crop_w, crop_h, target_w, target_h);
+        let scale_crop_pixbuf = {
+            if let Some(ref p) = scale_pbuf { p.clone() }
+            else { return; }};
+
+        if let Some(uri) = bg_uri {
+            // linked image: svg refers to uri instead of embedding the image
+            let surface = {
+                if let Ok(sf) = ImageSurface::create(Format::ARgb32, scale_crop_pixbuf.width(), scale_crop_pixbuf.height()) { sf }
+                else { println!("creating surface for linked image failed!"); return; } };
+            if let Ok(ctx) = cairo::Context::new(&surface) {
+                ctx.set_source_pixbuf(&scale_crop_pixbuf, 0.0, 0.0);
+                if ctx.paint().is_err() { println!("painting linked image failed!"); }
+            }
+            if surface.set_mime_data(MIME_TYPE_URI, uri.as_bytes().to_vec()).is_err() {
+                println!("setting uri of linked image failed!"); }
+            if cr.set_source_surface(&surface, crop_target_ofst_x as f64, crop_target_ofst_y as f64).is_err() {
+                println!("setting linked image as source failed!"); return; }
+        } else {
+            cr.set_source_pixbuf(&scale_crop_pixbuf,
+                                 crop_target_ofst_x as f64, crop_target_ofst_y as f64);
+        }
+        cr.rectangle(crop_target_ofst_x as f64, crop_target_ofst_y as f64,
+                     crop_target_w      as f64, crop_target_h      as f64);
+        if cr.fill().is_err(){
+            println!("draw image failed!");
+        }
+    }
+
+    // MatJob //////////////////////////////////////////////
+    #[derive(Debug, Clone)]
+    pub struct MatJob{
+        pub mat     : Mat,                      // after label resolution
+        pub pos_dim : (f64, f64, f64, f64),     // of the source mat with RefNoRect
+        pub markup  : String,                   // text of the source mat in the language
+        pub limit   : Option<usize>,            // revealed characters, None: all
+        pub name    : String,                   // summary of the source node
+    }
+    /// area of (source, label-resolved) mats -> mats in drawing order.
+    /// reveal: (index of the first mat in area to be revealed, number of characters shown)
+    pub fn mat_jobs(area     : &[(Rc<ScenarioNode>, Option<Rc<ScenarioNode>>)],
+                    text_key : &str,
+                    reveal   : Option<(usize, usize)>) -> Vec<MatJob>{
+        let mut rest = reveal.map(|r| r.1).unwrap_or(0);
+        let mut jobs = Vec::new();
+        for (i, (sn_source, sn_ref)) in area.iter().enumerate() {
+            let sn = if let Some(ref_target) = sn_ref { ref_target } else { sn_source };
+            let mat = match &*sn.value.borrow() {
+                Item::Mat(m) | Item::Pmat(m) => m.clone(),
+                _ => continue,
+            };
+            let pos_sn = if sn_source.get_label_type() == Some(LabelType::RefNoRect) { sn_source } else { sn };
+            let pos_dim = if let Some(t) = pos_sn.get_mat_pos_dim_f64() { t } else { continue; };
+            let markup  = sn_source.get_mat_text_lang(text_key).unwrap_or_default();
+            // characters shown in each mat
+            let limit = match reveal {
+                Some((first, _)) if first <= i => {
+                    let len = animation::visible_len(&markup);
+                    let n = rest.min(len);
+                    rest = rest.saturating_sub(len);
+                    Some(n) },
+                _ => None,
+            };
+            jobs.push(MatJob{ mat, pos_dim, markup, limit, name: sn_source.summary_str() });
+        }
+        jobs.reverse();
+        jobs
+    }
+    // draw_bg_mat_img /////////////////////////////////////
+    fn draw_bg_mat_img(project_dir : &Path,
+                       mat         : &Mat,
+                       img_mat_buf : &mut HashMap<u64, Pixbuf>,
+                       cr          : &cairo::Context,
+                       filter      : Filter,
+                       x: f64, y: f64, w: f64, h: f64
+    ) -> Result<()>{
+        let mut prj_path = project_dir.to_path_buf();
+        if let Some(bgimg_path) = &mat.bgimg {
+            prj_path.push(bgimg_path); }
+
+        if !prj_path.is_file(){ anyhow::bail!("prj_path is not file"); }
+
+        let bg_file = File::open(prj_path.clone())
+            .with_context(||format!("opening {prj_path:?} failed"))?;
+        let m_data = bg_file.metadata()
+            .with_context(||format!("obtaining metadata of {prj_path:?} failed"))?;
+        let mod_time = m_data.modified()
+            .with_context(||format!("getting mod_tile of {prj_path:?} failed"))?;
+        let epoch = mod_time.duration_since(SystemTime::UNIX_EPOCH)
+            .with_context(||format!("getting epoch of {prj_path:?} failed"))?;
+
+        // generate hash of file name with timestamp
+        let prj_path_str = prj_path.clone().into_os_string().into_string().unwrap();
+        let mut hasher = DefaultHasher::new();
+        hasher.write(prj_path_str.as_bytes());
+        hasher.write(&epoch.as_secs().to_ne_bytes());
+        hasher.write(&epoch.as_nanos().to_ne_bytes());
+        let hash_u64 = hasher.finish();
+
+        let bg_buf_sub;
+        let bg_pbuf = if let Some(b) = img_mat_buf.get(&hash_u64){ b } else {
+            let ref pbuf = Pixbuf::from_file( prj_path.clone() )
+                .with_context(||format!("creating pixbuf of {prj_path:?} failed"))?;
+            img_mat_buf.insert(hash_u64, pbuf.clone());
+            bg_buf_sub = pbuf.clone();
+            &bg_buf_sub
+        };
+        // TODO: resize cache mount of img_mat_buf when it becomes too large
+
+        let scale_pbuf = {
+            if let Some(p) = resample(bg_pbuf, w as i32, h as i32, filter) { p }
+            else { println!("resample failed in {}:{}", file!(), line!()); anyhow::bail!(""); }
+        };
+        cr.set_source_pixbuf(&scale_pbuf, x, y);
+        cr.rectangle(x, y, w, h);
+        if cr.fill().is_err(){
+            println!("draw image failed!"); }
+
+        Ok(())
+    }
+    fn rgba(c: &crate::scenario_node::Color, a: u32) -> (f64, f64, f64, f64){
+        ((c.r as f64) / 255.0, (c.g as f64) / 255.0, (c.b as f64) / 255.0, (a as f64) / 255.0)
+    }
+    // draw_mats ///////////////////////////////////////////
+    pub fn draw_mats(pc           : &pango::Context,
+                     cr           : &cairo::Context,
+                     mats         : &[MatJob],
+                     lang_setting : Option<&LanguageSetting>,
+                     project_dir  : &Path,
+                     img_mat_buf  : &mut HashMap<u64, Pixbuf>,
+                     filter       : Filter){
+        for job in mats {
+            if !draw_mat_box(cr, job, project_dir, img_mat_buf, filter) {
+                return; }
+            draw_mat_text(pc, cr, job, lang_setting);
+        }
+    }
+    // draw_mat_box ////////////////////////////////////////
+    /// rectangle or image of the mat, false if filling failed
+    pub fn draw_mat_box(cr          : &cairo::Context,
+                        job         : &MatJob,
+                        project_dir : &Path,
+                        img_mat_buf : &mut HashMap<u64, Pixbuf>,
+                        filter      : Filter) -> bool{
+        let m = &job.mat;
+        let (x, y, w, h) = job.pos_dim;
+        let (r, g, b, a) = rgba(&m.col, m.a);
+
+        if m.bg_en { // image mat
+            if let Err(e) = draw_bg_mat_img(project_dir, m, img_mat_buf, cr, filter, x, y, w, h) {
+                println!("{:?}", e);
+            }
+        } else { // draw rectangle
+            cr.set_line_width(2.0); // TODO parameterize
+            cr.set_source_rgba( r, g, b, a );
+
+            let round = m.r;
+            if round <= 0 {
+                cr.rectangle(x, y, w, h);
+            } else {
+                let round = round as f64;
+                let m_pi = glib_sys::G_PI;
+                cr.move_to(x-round,   y);
+                cr.arc    (x,     y,        round,  1.0*m_pi,  1.5*m_pi);
+                cr.line_to(x+w,   y-round);
+                cr.arc    (x+w,   y,        round,  1.5*m_pi,  2.0*m_pi);
+                cr.line_to(x+w+round, y+h+r);
+                cr.arc    (x+w,   y+h,      round,  0.0*m_pi,  0.5*m_pi);
+                cr.line_to(x,     y+h+round);
+                cr.arc    (x,     y+h,      round,  0.5*m_pi,  1.0*m_pi);
+                cr.close_path();
+            }
+            if cr.fill().is_err() {
+                println!("fill draw_mats failed!");
+                return false; }
+        }
+        true
+    }
+    // mat_text_layout /////////////////////////////////////
+    /// layout of the text of the mat as it is drawn
+    fn mat_text_layout(pc           : &pango::Context,
+                       job          : &MatJob,
+                       lang_setting : Option<&LanguageSetting>) -> Layout{
+        let m = &job.mat;
+        let (_, _, w, h) = job.pos_dim;
+
+        let layout = Layout::new(pc);
+
+        if m.vertical {
+            layout.set_width ((h as i32) * pango::SCALE);     // swap when
+            layout.set_height((w as i32) * 3 * pango::SCALE); // vertical writing
+        } else {
+            layout.set_width ((w as i32) * pango::SCALE);
+            layout.set_height((h as i32) * 3 * pango::SCALE);
+        }
+        // note: Expand the height (width in vertical writing)
+        // to avoid unintentional omission of display.
+        // This is useful when line-spacing is set to 1 or less.
+        // The expanded width (x3) is provisional.
+        if let Some(n) = job.limit {
+            layout.set_markup(&animation::truncate_markup(&job.markup, n));
+        } else {
+            layout.set_markup(&job.markup);
+        }
+
+        // line spacing
+        layout.set_line_spacing(m.line_spacing as f32);
+
+        let mut fopt = FontOptions::new().expect("fopt");
+        fopt.set_antialias(Antialias::Good);
+        pangocairo::context_set_font_options(pc, Some(&fopt));
+
+        let mut font_desc= FontDescription::new();
+        font_desc.set_family( &m.font_family );
+        font_desc.set_style( Style::Normal );
+        font_desc.set_size( m.font_size * pango::SCALE);
+        if let Some(ls) = lang_setting { // per-language override
+            if let Some(family) = &ls.font_family {
+                font_desc.set_family(family); }
+            if let Some(scale) = ls.font_scale {
+                font_desc.set_size( m.font_size * pango::SCALE * (scale as i32) / 100); }
+        }
+        if m.vertical {
+            font_desc.set_gravity(gtk::pango::Gravity::East); }
+
+        let w1 = util::StrumWeight::from_str( &m.font_weight ).expect("weight expression");
+        font_desc.set_weight(w1.0);
+
+        layout.set_font_description(Some(&font_desc));
+        layout
+    }
+    // text_overflows //////////////////////////////////////
+    /// the lines of the text go out of the box of the mat after the padding,
+    /// or a line is longer than the wrapping width
+    pub fn text_overflows(pc: &pango::Context, job: &MatJob, lang_setting: Option<&LanguageSetting>) -> bool{
+        let m = &job.mat;
+        let (_, _, w, h) = job.pos_dim;
+        let (pad_x, pad_y) = (m.text_pos.x as f64, m.text_pos.y as f64);
+        let (text_w, text_h) = mat_text_layout(pc, job, lang_setting).pixel_size();
+        let (text_w, text_h) = (text_w as f64, text_h as f64);
+        if m.vertical { // lines go from right to left
+            (pad_x + text_h > w) || (text_w > h) }
+        else {
+            (pad_y + text_h > h) || (text_w > w) }
+    }
+    // draw_mat_text ///////////////////////////////////////
+    pub fn draw_mat_text(pc           : &pango::Context,
+                         cr           : &cairo::Context,
+                         job          : &MatJob,
+                         lang_setting : Option<&LanguageSetting>){
+        let m = &job.mat;
+        let (mut x, mut y, w, _) = job.pos_dim;
+        let (pad_x, pad_y) = (m.text_pos.x as f64, m.text_pos.y as f64);
+
+        let layout = mat_text_layout(pc, job, lang_setting);
+
+        // text mat
+        if m.vertical {
+            x = x + w - pad_x;
+        }
+        else {
+            x = x + pad_x;
+        }
+        y = y + pad_y;
+
+        cr.set_line_join(cairo::LineJoin::Round);
+
+        cr.save().expect("save context before stroke text decoration");
+        cr.move_to(x, y);
+        if m.vertical {
+            cr.rotate(0.5 * glib_sys::G_PI); }
+        pangocairo::layout_path(cr, &layout);
+        let (r, g, b, a) = rgba(&m.font_col_2, m.font_a_2);
+        cr.set_source_rgba( r, g, b, a );
+        cr.set_line_width(m.font_outl_2);
+        cr.stroke().expect("stroke text");
+        cr.restore().expect("restore context for stroke text decorateion");
+
+        // text foreground
+        cr.save().expect("save context before stroke text");
+        cr.move_to(x, y);
+        if m.vertical {
+            cr.rotate(0.5 * glib_sys::G_PI); }
+        let (r, g, b, a) = rgba(&m.font_col, m.font_a);
+        cr.set_source_rgba( r, g, b, a );
+        pangocairo::show_layout(cr, &layout);
+        cr.restore().expect("restore context for stroke text");
+    }
+
+    // OvimgJob ////////////////////////////////////////////
+    #[derive(Debug, Clone)]
+    pub struct OvimgJob{
+        pub path : PathBuf, // in the project dir
+        pub pos  : (f64, f64),
+        pub a    : f64,
+        pub name : String,  // summary of the node
+    }
+    fn ovimg_jobs(page_node: &Rc<ScenarioNode>, project_dir: &Path) -> Vec<OvimgJob>{
+        collect_ovimgs(page_node).iter().filter_map(|sn|{
+            match &*sn.value.borrow() {
+                Item::Ovimg(o) => Some(OvimgJob{
+                    path : project_dir.join(&o.path),
+                    pos  : (o.pos.x as f64, o.pos.y as f64),
+                    a    : o.a,
+                    name : format!("{}{}", sn.summary_str(), o.path),
+                }),
+                _ => None,
+            }
+        }).collect()
+    }
+    // collect_ovimgs //////////////////////////////////////
+    /// ovimgs of the page node in drawing order
+    pub fn collect_ovimgs(page_node: &Rc<ScenarioNode>) -> Vec<Rc<ScenarioNode>>{
+        let mut ovimgs = Vec::new();
+        if !matches!(*page_node.value.borrow(), Item::Page(_)) {
+            return ovimgs; }
+        let mut p = page_node.child.borrow().clone();
+        while let Some(sn) = p {
+            if matches!(*sn.value.borrow(), Item::Ovimg(_)) {
+                ovimgs.push(sn.clone()); }
+            p = sn.neighbor.borrow().clone();
+        }
+        ovimgs
+    }
+
+    // PageJob /////////////////////////////////////////////
+    #[derive(Debug, Clone)]
+    pub struct PageJob{
+        pub scene  : SceneJob,
+        pub mats   : Vec<MatJob>,   // in drawing order
+        pub ovimgs : Vec<OvimgJob>, // only in Content::Transparent, as the preview does not draw them
+        pub bg_uri : Option<String>,
+        pub stamps : Vec<Stamp>,    // only in exports, drawn over the mats
+    }
+    /// page or pmat node -> PageJob, None if it has no scene
+    pub fn page_job(sn          : &Rc<ScenarioNode>,
+                    project_dir : &Path,
+                    text_key    : &str,
+                    bg_uri      : Option<&str>,
+                    reveal      : Option<usize>) -> Option<PageJob>{
+        let scene_node = ScenarioNode::get_belong_scene(sn)?;
+        let mut area = Vec::new();
+        collect_mats_in_scene(&scene_node, &mut area);
+        let scene_mats = area.len();
+        collect_mats(sn, &mut area);
+        Some(PageJob{
+            scene  : SceneJob::of(&scene_node, project_dir)?,
+            mats   : mat_jobs(&area, text_key, reveal.map(|n| (scene_mats, n))),
+            ovimgs : ovimg_jobs(sn, project_dir),
+            bg_uri : bg_uri.map(|u| u.to_string()),
+            stamps : Vec::new(),
+        })
+    }
+    // collect_mats ////////////////////////////////////////
+    /// mats of the page or pmat node with their label-resolved nodes
+    pub fn collect_mats(page_node : &Rc<ScenarioNode>,
+                        area      : &mut Vec::<(Rc<ScenarioNode>, Option<Rc<ScenarioNode>>)>) {
+        let mut p = page_node.clone();
+        loop {
+            // - it collects mat or pmat
+            // - While collecting mat, pmat will not be mixed
+            //    (guaranteed by tree manipulation constraints)
+            let p1;
+            match *p.value.borrow() {
+                Item::Page(_) => {
+                    p1 = p.child.borrow().clone();
+                }
+                Item::Mat(_) => {
+                    let lbl_ref_node = ScenarioNode::search_def_label(p.clone()); // reference if it has label-ref
+                    area.push( (p.clone(), lbl_ref_node) );
+                    p1 = p.neighbor.borrow().clone();
+                }
+                Item::Pmat(_) => {
+                    let lbl_ref_node = ScenarioNode::search_def_label(p.clone()); // reference if it has label-ref
+                    area.push( (p.clone(), lbl_ref_node) );
+                    p1 = None; /* exit when Pmat */
+                }
+                _ => { p1 = None; }
+            }
+            if p1.is_some() { /* child or neighbor is set if exists */
+                p = p1.unwrap();
+            } else {
+                break;
+            }
+        }
+    }
+    /// mats directly under the scene node
+    pub fn collect_mats_in_scene(scene_node : &Rc<ScenarioNode>,
+                                 area       : &mut Vec::<(Rc<ScenarioNode>, Option<Rc<ScenarioNode>>)>) {
+        let mut p = scene_node.clone();
+        loop {
+            let p1;
+            match *p.value.borrow() {
+                Item::Scene(_) => {
+                    p1 = p.child.borrow().clone();
+                }
+                Item::Mat(_) => {
+                    let lbl_ref_node = ScenarioNode::search_def_label(p.clone()); // reference if it has label-ref
+                    area.push( (p.clone(), lbl_ref_node) );
+                    p1 = p.neighbor.borrow().clone();
+                }
+                Item::Page(_) | Item::Pmat(_) => {
+                    p1 = p.neighbor.borrow().clone();
+                }
+                _ => { p1 = None; }
+            }
+            if p1.is_some() { /* child or neighbor is set if exists */
+                p = p1.unwrap();
+            } else {
+                break;
+            }
+        }
+    }
+
+    // RenderSetting ///////////////////////////////////////
+    /// mats are placed in target size and scaled into output size
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct RenderSetting{
+        pub project_dir  : PathBuf,
+        pub target_w     : i32,
+        pub target_h     : i32,
+        pub out_w        : i32,
+        pub out_h        : i32,
+        pub lang_setting : Option<LanguageSetting>,
+        pub content      : Content,
+        pub filter       : Filter,
+        pub supersample  : u32, // raster pages are drawn N times larger and scaled down, 1: off
+        // of the pango context of the preview, None: the defaults of the font map
+        // (cairo font options are not kept, draw_text replaces them for every mat)
+        pub resolution   : Option<f64>,
+        pub font_desc    : Option<FontDescription>,
+        pub round_glyphs : Option<bool>,
+    }
+    impl RenderSetting{
+        /// output of the whole page in target size
+        pub fn new(project_dir: &Path, target_w: i32, target_h: i32, lang_setting: Option<LanguageSetting>) -> Self{
+            Self{ project_dir: project_dir.to_path_buf(), target_w, target_h, out_w: target_w, out_h: target_h, lang_setting,
+                  content: Content::Full, filter: Filter::Fast, supersample: 1, resolution: None, font_desc: None, round_glyphs: None }
+        }
+        pub fn output(self, out_w: i32, out_h: i32) -> Self{ Self{ out_w, out_h, ..self } }
+        pub fn content(self, content: Content) -> Self{ Self{ content, ..self } }
+        pub fn resample(self, r: &ResampleSetting) -> Self{
+            Self{ filter: r.filter(), supersample: r.supersample.max(1), ..self }
+        }
+        /// texts are laid out with the resolution and default font of pc, e.g. of the preview widget
+        pub fn font(self, pc: &pango::Context) -> Self{
+            Self{ resolution  : Some(pangocairo::context_get_resolution(pc)),
+                  font_desc   : pc.font_description(),
+                  round_glyphs: Some(pc.is_round_glyph_positions()), ..self }
+        }
+    }
+
+    /// the longer side of a cairo image surface
+    const MAX_SURFACE_SIZE: i32 = 32767;
+
+    // Renderer ////////////////////////////////////////////
+    /// state of a rendering thread: font map and decoded images
+    pub struct Renderer{
+        pc           : pango::Context,
+        setting      : RenderSetting,
+        scene        : Option<(SceneJob, (i32, i32), Option<Pixbuf>, Option<Pixbuf>)>, // the last background and its size
+        img_mat_buf  : HashMap<u64, Pixbuf>,
+    }
+    impl Renderer{
+        pub fn new(setting: RenderSetting) -> Self{
+            let pc = pangocairo::FontMap::new().create_context();
+            if let Some(r) = setting.resolution {
+                pangocairo::context_set_resolution(&pc, r); }
+            if let Some(d) = &setting.font_desc {
+                pc.set_font_description(Some(d)); }
+            if let Some(r) = setting.round_glyphs {
+                pc.set_round_glyph_positions(r); }
+            Self{
+                pc,
+                setting,
+                scene        : None,
+                img_mat_buf  : HashMap::new(),
+            }
+        }
+        pub fn project_dir(&self) -> &Path{ &self.setting.project_dir }
+        /// the text of the mat overflows, see text_overflows
+        pub fn overflows(&self, mat: &MatJob) -> bool{
+            text_overflows(&self.pc, mat, self.setting.lang_setting.as_ref())
+        }
+        /// names of the installed font families in lower case
+        pub fn font_families(&self) -> HashSet<String>{
+            self.pc.list_families().iter().map(|f| f.name().to_lowercase()).collect()
+        }
+        /// draws with the given background buffers in output size, or loads them when bufs is None
+        /// the background is left transparent unless the content is Content::Full
+        pub fn draw(&mut self, cr: &cairo::Context, job: &PageJob, bufs: Option<(&Option<Pixbuf>, &Option<Pixbuf>)>){
+            let (out_w, out_h) = (self.setting.out_w, self.setting.out_h);
+            self.draw_in(cr, job, bufs, out_w, out_h);
+        }
+        /// draws the page in out_w x out_h
+        fn draw_in(&mut self, cr: &cairo::Context, job: &PageJob, bufs: Option<(&Option<Pixbuf>, &Option<Pixbuf>)>,
+                   out_w: i32, out_h: i32){
+            let content = self.setting.content;
+            if content == Content::Full {
+                let (pbuf, scale_pbuf) = match bufs {
+                    Some((p, s)) => (p.clone(), s.clone()),
+                    None         => self.scene_bufs(&job.scene, out_w, out_h),
+                };
+                draw_scene(cr, &job.scene, &pbuf, &scale_pbuf, out_w, out_h, job.bg_uri.as_deref());
+            }
+
+            // scale from target to output
+            let s = &self.setting;
+            let (scale, _, _, ofst_x, ofst_y) = util::get_scale_offset(s.target_w, s.target_h, out_w, out_h);
+            cr.save().expect("save context before scaling mats");
+            cr.translate(ofst_x as f64, ofst_y as f64);
+            cr.scale(scale, scale);
+            match content {
+                Content::Full        => {
+                    draw_mats(&self.pc, cr, &job.mats, s.lang_setting.as_ref(), &s.project_dir, &mut self.img_mat_buf, s.filter); },
+                Content::Transparent => {
+                    job.ovimgs.iter().for_each(|o| draw_ovimg(cr, o, o.a));
+                    draw_mats(&self.pc, cr, &job.mats, s.lang_setting.as_ref(), &s.project_dir, &mut self.img_mat_buf, s.filter); },
+                Content::TextOnly    => {
+                    job.mats.iter().for_each(|m| draw_mat_text(&self.pc, cr, m, s.lang_setting.as_ref())); },
+            }
+            cr.restore().expect("restore context after scaling mats");
+            for stamp in &job.stamps {
+                draw_stamp(&self.pc, cr, stamp, &s.project_dir, s.filter, out_w, out_h); }
+        }
+        /// background buffers in w x h, decoded once for consecutive pages of a scene
+        fn scene_bufs(&mut self, scene: &SceneJob, w: i32, h: i32) -> (Option<Pixbuf>, Option<Pixbuf>){
+            if self.scene.as_ref().is_none_or(|(j, size, _, _)| (j != scene) || (*size != (w, h))) {
+                let (p, b) = load_scene(scene, w, h, self.setting.filter);
+                self.scene = Some((scene.clone(), (w, h), p, b));
+            }
+            let (_, _, p, b) = self.scene.as_ref().unwrap();
+            (p.clone(), b.clone())
+        }
+        /// renders the page into an image surface of output size, supersampled if it is set
+        pub fn image(&mut self, job: &PageJob) -> Result<ImageSurface>{
+            let (w, h) = (self.setting.out_w, self.setting.out_h);
+            let n = (self.setting.supersample.max(1) as i32).min(MAX_SURFACE_SIZE / w.max(h).max(1)).max(1);
+            let large = ImageSurface::create(Format::ARgb32, w * n, h * n)?;
+            let cr = cairo::Context::new(&large)?;
+            self.draw_in(&cr, job, None, w * n, h * n);
+            drop(cr);
+            if n == 1 {
+                return Ok(large); }
+
+            // box filter of n x n pixels
+            let surface = ImageSurface::create(Format::ARgb32, w, h)?;
+            let cr = cairo::Context::new(&surface)?;
+            cr.scale(1.0 / (n as f64), 1.0 / (n as f64));
+            cr.set_source_surface(&large, 0.0, 0.0)?;
+            cr.source().set_filter(cairo::Filter::Good);
+            cr.paint()?;
+            drop(cr);
+            Ok(surface)
+        }
+        /// renders the page into a png file
+        pub fn write_png(&mut self, job: &PageJob, path: &Path) -> Result<()>{
+            let surface = self.image(job)?;
+            let mut out_file = File::create(path).with_context(|| format!("can not open: {}", path.display()))?;
+            surface.write_to_png(&mut out_file)?;
+            Ok(())
+        }
+        /// renders the page into a svg file, 1 unit is 1 pixel of png
+        pub fn write_svg(&mut self, job: &PageJob, path: &Path) -> Result<()>{
+            let mut surface = SvgSurface::new(self.setting.out_w as f64, self.setting.out_h as f64, Some(path))
+                .with_context(|| format!("can not open: {}", path.display()))?;
+            surface.set_document_unit(SvgUnit::Px);
+            let cr = cairo::Context::new(&surface)?;
+            self.draw(&cr, job, None);
+            drop(cr);
+            surface.finish();
+            surface.status()?;
+            Ok(())
+        }
+        /// renders the page into an OpenRaster file with a layer for each node
+        pub fn write_ora(&mut self, job: &PageJob, path: &Path) -> Result<()>{
+            let s = self.setting.clone();
+            let mut layers: Vec<(String, f64, ImageSurface)> = Vec::new();
+
+            // background
+            if s.content == Content::Full {
+                let (pbuf, scale_pbuf) = self.scene_bufs(&job.scene, s.out_w, s.out_h);
+                let bg_name = job.scene.bgimg.as_ref().and_then(|b| b.file_name()).map(|f| f.to_string_lossy().to_string());
+                layers.push((openraster::layer_name(&format!("Scene:{}", bg_name.unwrap_or_default()), ""), 1.0,
+                             layer_surface(&s, false, |cr| draw_scene(cr, &job.scene, &pbuf, &scale_pbuf, s.out_w, s.out_h, None))?));
+            }
+            // ovimgs
+            for o in job.ovimgs.iter().filter(|_| s.content == Content::Transparent) {
+                layers.push((openraster::layer_name(&o.name, ""), o.a,
+                             layer_surface(&s, true, |cr| draw_ovimg(cr, o, 1.0))?));
+            }
+            // box and text of mats
+            for m in &job.mats {
+                if s.content != Content::TextOnly {
+                    let img_mat_buf = &mut self.img_mat_buf;
+                    layers.push((openraster::layer_name(&m.name, "box"), 1.0,
+                                 layer_surface(&s, true, |cr|{ draw_mat_box(cr, m, &s.project_dir, img_mat_buf, s.filter); })?));
+                }
+                layers.push((openraster::layer_name(&m.name, "text"), 1.0,
+                             layer_surface(&s, true, |cr| draw_mat_text(&self.pc, cr, m, s.lang_setting.as_ref()))?));
+            }
+            // stamps
+            for (i, stamp) in job.stamps.iter().enumerate() {
+                layers.push((openraster::layer_name(&format!("Stamp{}", i), ""), 1.0,
+                             layer_surface(&s, false, |cr| draw_stamp(&self.pc, cr, stamp, &s.project_dir, s.filter, s.out_w, s.out_h))?));
+            }
+
+            // flattened page and its thumbnail
+            let merged = layer_surface(&s, false, |cr|{
+                for (_, opacity, l) in &layers {
+                    if cr.set_source_surface(l, 0.0, 0.0).and_then(|_| cr.paint_with_alpha(*opacity)).is_err() {
+                        println!("merging layers failed!"); }
+                }
+            })?;
+            let side = s.out_w.max(s.out_h).min(openraster::THUMBNAIL_SIZE);
+            let (thumb_scale, thumb_w, thumb_h, _, _) = util::get_scale_offset(s.out_w, s.out_h, side, side);
+            let thumb = ImageSurface::create(Format::ARgb32, thumb_w.max(1), thumb_h.max(1))?;
+            let cr = cairo::Context::new(&thumb)?;
+            cr.scale(thumb_scale, thumb_scale);
+            cr.set_source_surface(&merged, 0.0, 0.0)?;
+            cr.paint()?;
+            drop(cr);
+
+            let ora_layers = layers.into_iter()
+                .map(|(name, opacity, l)| Ok(openraster::OraLayer{ name, opacity, png: png_bytes(&l)? }))
+                .collect::<Result<Vec<_>>>()?;
+            let data = openraster::ora(s.out_w, s.out_h, &ora_layers, &png_bytes(&merged)?, &png_bytes(&thumb)?);
+            std::fs::write(path, data).with_context(|| format!("can not write: {}", path.display()))
+        }
+        /// renders the page into a file of format
+        pub fn write(&mut self, job: &PageJob, path: &Path, format: ImageFormat) -> Result<()>{
+            let (w, h) = (self.setting.out_w, self.setting.out_h);
+            match format {
+                ImageFormat::Png        => self.write_png(job, path),
+                ImageFormat::Ora        => self.write_ora(job, path),
+                ImageFormat::Svg{ .. }  => self.write_svg(job, path),
+                ImageFormat::Jpeg{ quality } => {
+                    let rgb: Vec<u8> = surface_rgba(self.image(job)?)?.chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect();
+                    let pbuf = Pixbuf::from_bytes(&glib::Bytes::from_owned(rgb), Colorspace::Rgb, false, 8, w, h, w * 3);
+                    pbuf.savev(path, "jpeg", &[("quality", &quality.to_string())])
+                        .with_context(|| format!("can not write: {}", path.display()))
+                },
+                ImageFormat::Webp{ quality } => {
+                    anyhow::ensure!(can_write(format), "gdk-pixbuf can not write WebP: {}", path.display());
+                    let rgba = surface_rgba(self.image(job)?)?;
+                    let pbuf = Pixbuf::from_bytes(&glib::Bytes::from_owned(rgba), Colorspace::Rgb, true, 8, w, h, w * 4);
+                    pbuf.savev(path, "webp", &[("quality", &quality.to_string())])
+                        .with_context(|| format!("can not write: {}", path.display()))
+                },
+            }
+        }
+        /// draws a contact sheet, the output size of the setting is the thumbnail size of layout
+        pub fn draw_sheet(&mut self, cr: &cairo::Context, layout: &SheetLayout, title: &str, cells: &[SheetCell]) -> Result<()>{
+            cr.set_source_rgb(1.0, 1.0, 1.0);
+            cr.paint()?;
+            let l = layout;
+            cr.set_source_rgb(0.0, 0.0, 0.0);
+            self.sheet_text(cr, title, l.margin, l.margin, l.width - 2 * l.margin, l.line_h, true);
+            for c in cells {
+                let (_, x, y) = l.thumb_pos(c.index);
+                let thumb = self.image(&c.job)?;
+                cr.set_source_surface(&thumb, x as f64, y as f64)?;
+                cr.paint()?;
+                cr.set_source_rgb(0.6, 0.6, 0.6);
+                cr.set_line_width(1.0);
+                cr.rectangle(x as f64 - 0.5, y as f64 - 0.5, l.thumb_w as f64 + 1.0, l.thumb_h as f64 + 1.0);
+                cr.stroke()?;
+                cr.set_source_rgb(0.0, 0.0, 0.0);
+                if !c.heading.is_empty() {
+                    self.sheet_text(cr, &c.heading, x, y - l.line_h, l.thumb_w, l.line_h, true); }
+                self.sheet_text(cr, &c.caption, x, y + l.thumb_h, l.thumb_w, l.line_h, false);
+            }
+            Ok(())
+        }
+        /// a line of text ellipsized in w
+        fn sheet_text(&self, cr: &cairo::Context, text: &str, x: i32, y: i32, w: i32, line_h: i32, bold: bool){
+            let layout = Layout::new(&self.pc);
+            let mut font_desc = FontDescription::from_string("Sans");
+            font_desc.set_absolute_size((line_h as f64) * 0.65 * (pango::SCALE as f64));
+            if bold {
+                font_desc.set_weight(pango::Weight::Bold); }
+            layout.set_font_description(Some(&font_desc));
+            layout.set_width(w * pango::SCALE);
+            layout.set_ellipsize(pango::EllipsizeMode::End);
+            layout.set_text(text);
+            let (_, text_h) = layout.pixel_size();
+            cr.move_to(x as f64, (y + (line_h - text_h) / 2) as f64);
+            pangocairo::show_layout(cr, &layout);
+        }
+    }
+    // SheetCell ///////////////////////////////////////////
+    /// a page of a contact sheet
+    pub struct SheetCell{
+        pub index   : usize,  // page number, the position in the sheet is index % per_sheet
+        pub job     : PageJob,
+        pub caption : String,
+        pub heading : String, // groups starting at the page
+    }
+    // draw_ovimg //////////////////////////////////////////
+    fn draw_ovimg(cr: &cairo::Context, job: &OvimgJob, alpha: f64){
+        let img = match Pixbuf::from_file(&job.path) {
+            Ok(p)  => p,
+            Err(e) => { println!("(draw_ovimg) {:?}: {}", job.path, e); return; },
+        };
+        cr.set_source_pixbuf(&img, job.pos.0, job.pos.1);
+        if cr.paint_with_alpha(alpha).is_err() {
+            println!("draw ovimg failed!"); }
+    }
+    // draw_stamp //////////////////////////////////////////
+    /// draws a watermark or a credit in the page of out_w x out_h
+    fn draw_stamp(pc: &pango::Context, cr: &cairo::Context, stamp: &Stamp, project_dir: &Path, filter: Filter,
+                  out_w: i32, out_h: i32){
+        let (out_w, out_h) = (out_w as f64, out_h as f64);
+        let height = out_h * (stamp.size as f64) / 100.0;
+        let margin = out_h * 0.02;
+        cr.save().expect("save context before stamp");
+        cr.push_group();
+        match &stamp.content {
+            StampContent::Text(text) => {
+                let layout = Layout::new(pc);
+                let mut font_desc = FontDescription::from_string("Sans Bold");
+                font_desc.set_absolute_size(height * (pango::SCALE as f64));
+                layout.set_font_description(Some(&font_desc));
+                layout.set_text(text);
+                let (w, h) = layout.pixel_size();
+                let (x, y) = stamp.pos.place(out_w, out_h, w as f64, h as f64, margin);
+                cr.move_to(x, y);
+                pangocairo::layout_path(cr, &layout);
+                cr.set_source_rgb(0.0, 0.0, 0.0);
+                cr.set_line_width((height / 16.0).max(1.0));
+                cr.set_line_join(cairo::LineJoin::Round);
+                if cr.stroke_preserve().is_err() {
+                    println!("stroke stamp failed!"); }
+                cr.set_source_rgb(1.0, 1.0, 1.0);
+                if cr.fill().is_err() {
+                    println!("fill stamp failed!"); }
+            },
+            StampContent::Image(path) => {
+                let path = project_dir.join(path);
+                match Pixbuf::from_file(&path) {
+                    Ok(img) => {
+                        let h = height.round().max(1.0);
+                        let w = ((img.width() as f64) * h / (img.height().max(1) as f64)).round().max(1.0);
+                        if let Some(img) = resample(&img, w as i32, h as i32, filter) {
+                            let (x, y) = stamp.pos.place(out_w, out_h, w, h, margin);
+                            cr.set_source_pixbuf(&img, x.round(), y.round());
+                            if cr.paint().is_err() {
+                                println!("draw stamp failed!"); }
+                        }
+                    },
+                    Err(e) => println!("(draw_stamp) {:?}: {}", path, e),
+                }
+            },
+        }
+        if cr.pop_group_to_source().and_then(|_| cr.paint_with_alpha((stamp.opacity as f64) / 100.0)).is_err() {
+            println!("draw stamp failed!"); }
+        cr.restore().expect("restore context after stamp");
+    }
+    // layer_surface ///////////////////////////////////////
+    /// transparent surface of output size drawn by f, in target coordinates when in_target is set
+    fn layer_surface(s: &RenderSetting, in_target: bool, f: impl FnOnce(&cairo::Context)) -> Result<ImageSurface>{
+        let surface = ImageSurface::create(Format::ARgb32, s.out_w, s.out_h)?;
+        let cr = cairo::Context::new(&surface)?;
+        if in_target {
+            let (scale, _, _, ofst_x, ofst_y) = util::get_scale_offset(s.target_w, s.target_h, s.out_w, s.out_h);
+            cr.translate(ofst_x as f64, ofst_y as f64);
+            cr.scale(scale, scale);
+        }
+        f(&cr);
+        drop(cr);
+        Ok(surface)
+    }
+    fn png_bytes(surface: &ImageSurface) -> Result<Vec<u8>>{
+        let mut buf = Vec::new();
+        surface.write_to_png(&mut buf)?;
+        Ok(buf)
+    }
+    /// gdk-pixbuf can write the format
+    fn has_saver(name: &str) -> bool{
+        Pixbuf::formats().iter().any(|f| f.is_writable() && f.name().is_some_and(|n| n == name))
+    }
+    /// WebP needs the optional saver of gdk-pixbuf (webp-pixbuf-loader)
+    pub fn can_write(format: ImageFormat) -> bool{
+        !matches!(format, ImageFormat::Webp{..}) || has_saver("webp")
+    }
+    // surface_rgba ////////////////////////////////////////
+    /// native endian premultiplied ARGB -> RGBA
+    pub fn surface_rgba(mut surface: ImageSurface) -> Result<Vec<u8>>{
+        surface.flush();
+        let width  = surface.width() as usize;
+        let stride = surface.stride() as usize;
+        let data   = surface.data()?;
+        Ok(data.chunks_exact(stride)
+           .flat_map(|row| row.chunks_exact(4).take(width))
+           .flat_map(|px| {
+               let v = u32::from_ne_bytes([px[0], px[1], px[2], px[3]]);
+               let a = v >> 24;
+               let c = |shift: u32| if a == 0 { 0 } else { (((v >> shift) & 0xff) * 255 + a / 2) / a } as u8;
+               [c(16), c(8), c(0), a as u8] })
+           .collect())
+    }
+
+    // ExportTask //////////////////////////////////////////
+    /// a page to be written into path by a worker
+    #[derive(Debug, Clone)]
+    pub struct ExportTask{
+        pub job    : PageJob,
+        pub path   : PathBuf,
+        pub format : ImageFormat,
+        pub scene  : usize, // serial number of the scene in the export
+    }
+    /// the maximum number of rendering threads
+    pub const MAX_WORKERS: usize = 8;
+    /// pages of a scene in a chunk share the decoded background
+    const CHUNK_PAGES: usize = 8;
+
+    // spawn_workers ///////////////////////////////////////
+    /// renders tasks on worker threads, each with its own Renderer.
+    /// (index of the task, result) is sent to done as each page is written,
+    /// and done is closed when all workers finish. no page is started after cancel is set.
+    /// the handles are to be joined after done is closed.
+    pub fn spawn_workers(tasks   : Vec<ExportTask>,
+                         setting : RenderSetting,
+                         cancel  : Arc<Mutex<bool>>,
+                         done    : async_channel::Sender<(usize, Result<()>)>) -> Vec<JoinHandle<()>>{
+        let scenes: Vec<usize> = tasks.iter().map(|t| t.scene).collect();
+        let chunks = Arc::new(chunks(&scenes, CHUNK_PAGES));
+        let tasks  = Arc::new(tasks);
+        let next   = Arc::new(AtomicUsize::new(0));
+        let n = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+            .min(MAX_WORKERS).min(chunks.len());
+        (0..n).map(|_|{
+            let (chunks, tasks, next, cancel, done, setting) =
+                (chunks.clone(), tasks.clone(), next.clone(), cancel.clone(), done.clone(), setting.clone());
+            std::thread::spawn(move ||{
+                let mut renderer = Renderer::new(setting);
+                while let Some(range) = chunks.get(next.fetch_add(1, Ordering::SeqCst)) {
+                    for i in range.clone() {
+                        if *cancel.lock().unwrap() { return; }
+                        let t = &tasks[i];
+                        let result = renderer.write(&t.job, &t.path, t.format);
+                        if done.send_blocking((i, result)).is_err() { return; } // receiver is gone
+                    }
+                }
+            })
+        }).collect()
+    }
+
+    // chunks //////////////////////////////////////////////
+    /// splits consecutive jobs into chunks of at most max jobs which do not
+    /// cross scenes, so that a worker decodes each background once per chunk
+    pub fn chunks(scenes: &[usize], max: usize) -> Vec<Range<usize>>{
+        let mut out: Vec<Range<usize>> = Vec::new();
+        for (i, s) in scenes.iter().enumerate() {
+            match out.last_mut() {
+                Some(r) if scenes[r.start] == *s && r.len() < max.max(1) => r.end = i + 1,
+                _ => out.push(i..i + 1),
+            }
+        }
+        out
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_chunks() {
+            assert_eq!(chunks(&[0, 0, 0, 1, 1, 2], 2), vec![0..2, 2..3, 3..5, 5..6]);
+            assert_eq!(chunks(&[], 4), vec![]);
+            assert_eq!(chunks(&[3, 3], 0), vec![0..1, 1..2]);
+        }
+    }
+}
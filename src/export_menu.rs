@@ -0,0 +1,816 @@
+pub mod export_actions{
+
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+
+    use gtk::prelude::*;
+    use gtk::glib::clone;
+    use gtk::gio;
+    use gtk::gio::Cancellable;
+    use gtk::gio::ListStore;
+    use gtk::gio::SimpleAction;
+    use gtk::AlertDialog;
+    use gtk::EventControllerKey;
+    use gtk::FileDialog;
+    use gtk::FileFilter;
+    use gtk::Orientation;
+    use gtk::Label;
+    use gtk::Window;
+    use gtk::Box;
+    use gtk::Button;
+    use gtk::Grid;
+    use gtk::Align;
+    use gtk::Entry;
+    use gtk::SingleSelection;
+    use gtk::TreeListRow;
+    use gtk::glib::signal::Propagation;
+
+    use crate::drawing_util::render;
+    use crate::export_util::image_format::Content;
+    use crate::export_util::image_format::ImageFormat;
+    use crate::export_util::naming;
+    use crate::export_util::profile::ExportProfile;
+    use crate::export_util::profile::ExportTarget;
+    use crate::export_util::renpy;
+    use crate::export_util::stamp::Stamp;
+    use crate::export_util::stamp::StampContent;
+    use crate::export_util::stamp::StampPages;
+    use crate::export_util::stamp::StampPos;
+    use crate::export_util::subset::Scope;
+    use crate::export_util::subset::Subset;
+    use crate::isv2_parameter::AnimationSetting;
+    use crate::isv2_parameter::ContactSheetSetting;
+    use crate::isv2_parameter::Isv2Parameter;
+    use crate::isv2_parameter::SubsetSetting;
+    use crate::isv2_parameter::VideoSetting;
+    use crate::pref_menu::pref_actions::valid_export_dir_name;
+    use crate::pref_menu::pref_actions::valid_language;
+    use crate::pref_menu::pref_actions::valid_name_template;
+    use crate::preview_window::PreviewWindow;
+    use crate::scenario_node_object::ScenarioNodeObject;
+
+    pub const ACT_EXPORT_ANIMATION : &str = "export_animation";
+    pub const ACT_EXPORT_VIDEO     : &str = "export_video";
+    pub const ACT_EXPORT_SUBSET    : &str = "export_subset";
+    pub const ACT_EXPORT_PROFILES  : &str = "export_profiles";
+    pub const ACT_EXPORT_HTML      : &str = "export_html";
+    pub const ACT_EXPORT_RENPY     : &str = "export_renpy";
+    pub const ACT_EXPORT_SHEET     : &str = "export_contact_sheet";
+
+    // parse_animation_setting /////////////////////////////
+    /// texts of the entries -> setting. empty last page and typewriter mean "to the last" and "off".
+    pub fn parse_animation_setting(first_page: &str,
+                                   last_page : &str,
+                                   hold_ms   : &str,
+                                   fade_ms   : &str,
+                                   fps       : &str,
+                                   cps       : &str,
+                                   loop_count: &str) -> Option<AnimationSetting> {
+        let first_page = first_page.trim().parse::<usize>().ok()?;
+        let last_page  = match last_page.trim() {
+            "" => None,
+            l  => Some(l.parse::<usize>().ok().filter(|l| first_page <= *l)?),
+        };
+        let hold_ms = hold_ms.trim().parse::<u32>().ok().filter(|h| (1..=600000).contains(h))?;
+        let fade_ms = fade_ms.trim().parse::<u32>().ok().filter(|f| *f <= 60000)?;
+        let fps     = fps.trim().parse::<u32>().ok().filter(|f| (1..=50).contains(f))?;
+        let typewriter_cps = match cps.trim() {
+            "" => None,
+            c  => Some(c.parse::<u32>().ok().filter(|c| (1..=1000).contains(c))?),
+        };
+        let loop_count = loop_count.trim().parse::<u16>().ok()?;
+        Some(AnimationSetting{ first_page, last_page, hold_ms, fade_ms, fps, typewriter_cps, loop_count })
+    }
+
+    // parse_video_setting /////////////////////////////////
+    /// texts of the entries -> setting. empty typewriter means "off".
+    pub fn parse_video_setting(fps        : &str,
+                               duration_ms: &str,
+                               cps        : &str) -> Option<VideoSetting> {
+        let fps         = fps.trim().parse::<u32>().ok().filter(|f| (1..=120).contains(f))?;
+        let duration_ms = duration_ms.trim().parse::<u32>().ok().filter(|d| (1..=600000).contains(d))?;
+        let typewriter_cps = match cps.trim() {
+            "" => None,
+            c  => Some(c.parse::<u32>().ok().filter(|c| (1..=1000).contains(c))?),
+        };
+        Some(VideoSetting{ fps, duration_ms, typewriter_cps })
+    }
+
+    // parse_contact_sheet_setting //////////////////////////
+    /// texts of the entries -> setting
+    pub fn parse_contact_sheet_setting(columns    : &str,
+                                       rows       : &str,
+                                       thumb_width: &str) -> Option<ContactSheetSetting> {
+        let columns     = columns.trim().parse::<u32>().ok().filter(|c| (1..=20).contains(c))?;
+        let rows        = rows.trim().parse::<u32>().ok().filter(|r| (1..=20).contains(r))?;
+        let thumb_width = thumb_width.trim().parse::<i32>().ok().filter(|w| (16..=2000).contains(w))?;
+        Some(ContactSheetSetting{ columns, rows, thumb_width })
+    }
+
+    // parse_subset_setting ////////////////////////////////
+    /// texts of the entries -> setting. pages are "first-last" or a page number,
+    /// and empty pages mean the selected node.
+    pub fn parse_subset_setting(pages    : &str,
+                                numbering: &str) -> Option<SubsetSetting> {
+        let pages = match pages.trim() {
+            "" => None,
+            p  => {
+                let (first, last) = p.split_once(['-', '\u{2013}']).unwrap_or((p, p));
+                let first = first.trim().parse::<usize>().ok()?;
+                let last  = last.trim().parse::<usize>().ok().filter(|l| first <= *l)?;
+                Some((first, last)) },
+        };
+        let local_numbering = match numbering.trim() {
+            "global" => false,
+            "local"  => true,
+            _        => return None,
+        };
+        Some(SubsetSetting{ pages, local_numbering })
+    }
+
+    // parse_profile ///////////////////////////////////////
+    /// "hd, 1920x1080, jpeg:90, hd, {seq}, en" -> profile. the format is png, svg,
+    /// svg-link, jpeg[:quality], webp[:quality] or ora, and the template and the language
+    /// may be omitted (naming::DEFAULT_TEMPLATE and the current language).
+    /// "+transparent" or "+text" after the format leaves out the background or all but texts.
+    /// stamps follow the fields after '|', see parse_stamp.
+    pub fn parse_profile(spec: &str) -> Option<ExportProfile> {
+        let mut parts = spec.split('|');
+        let fields: Vec<&str> = parts.next()?.split(',').map(|f| f.trim()).collect();
+        if fields.len() < 4 || 6 < fields.len() { return None; }
+        let name = fields[0].to_string();
+        if name.is_empty() { return None; }
+        let (w, h) = fields[1].split_once(['x', 'X'])?;
+        let width  = w.trim().parse::<i32>().ok().filter(|w| (1..=9999).contains(w))?;
+        let height = h.trim().parse::<i32>().ok().filter(|h| (1..=9999).contains(h))?;
+        let (format_spec, content) = match fields[2].split_once('+').map(|(f, c)| (f.trim(), c.trim())) {
+            Some((f, "transparent")) => (f, Content::Transparent),
+            Some((f, "text"))        => (f, Content::TextOnly),
+            Some(_)                  => return None,
+            None                     => (fields[2], Content::Full),
+        };
+        let (kind, quality) = match format_spec.split_once(':') {
+            Some((k, q)) => (k.trim(), Some(q.trim().parse::<u8>().ok().filter(|q| (1..=100).contains(q))?)),
+            None         => (format_spec, None),
+        };
+        let format = match (kind, quality) {
+            ("png",           None) => ImageFormat::Png,
+            ("svg",           None) => ImageFormat::Svg{ link_bg: false },
+            ("svg-link",      None) => ImageFormat::Svg{ link_bg: true },
+            ("jpeg" | "jpg",  q)    => ImageFormat::Jpeg{ quality: q.unwrap_or(90) },
+            ("webp",          q)    => ImageFormat::Webp{ quality: q.unwrap_or(90) },
+            ("ora",           None) => ImageFormat::Ora,
+            _                       => return None,
+        };
+        if (content != Content::Full) && !format.has_alpha() { return None; }
+        let export_dir = fields[3].to_string();
+        if !valid_export_dir_name(&export_dir) { return None; }
+        let name_template = fields.get(4).filter(|t| !t.is_empty()).unwrap_or(&naming::DEFAULT_TEMPLATE).to_string();
+        if !valid_name_template(&name_template) { return None; }
+        let language = fields.get(5).unwrap_or(&"").to_string();
+        if !valid_language(&language) { return None; }
+        let stamps = parts.map(parse_stamp).collect::<Option<Vec<_>>>()?;
+        Some(ExportProfile{ name, width, height, format, export_dir, name_template, language, content, stamps })
+    }
+    // format_profile //////////////////////////////////////
+    pub fn format_profile(p: &ExportProfile) -> String {
+        let format = match p.format {
+            ImageFormat::Png                   => String::from("png"),
+            ImageFormat::Svg{ link_bg: false } => String::from("svg"),
+            ImageFormat::Svg{ link_bg: true }  => String::from("svg-link"),
+            ImageFormat::Jpeg{ quality }       => format!("jpeg:{}", quality),
+            ImageFormat::Webp{ quality }       => format!("webp:{}", quality),
+            ImageFormat::Ora                   => String::from("ora"),
+        };
+        let format = match p.content {
+            Content::Full        => format,
+            Content::Transparent => format + "+transparent",
+            Content::TextOnly    => format + "+text",
+        };
+        let mut s = format!("{}, {}x{}, {}, {}, {}", p.name, p.width, p.height, format, p.export_dir, p.name_template);
+        if !p.language.is_empty() {
+            s.push_str(&format!(", {}", p.language)); }
+        for stamp in &p.stamps {
+            s.push_str(&format!(" | {}", format_stamp(stamp))); }
+        s
+    }
+    // parse_stamp /////////////////////////////////////////
+    /// "text:DRAFT @center opacity=30 size=20 pages=all" or
+    /// "image:img/logo.png @bottom-right size=8 pages=last" -> stamp.
+    /// the position is top-left, top, ..., bottom-right, and pages are all, first,
+    /// last, a page number or "first-last". opacity (100) and size (10) are percents.
+    pub fn parse_stamp(spec: &str) -> Option<Stamp> {
+        let (content, options) = spec.trim().rsplit_once(" @")?;
+        let content = match content.trim().split_once(':')? {
+            ("text",  t) if !t.trim().is_empty() => StampContent::Text(t.trim().to_string()),
+            ("image", i) if !i.trim().is_empty() => StampContent::Image(i.trim().to_string()),
+            _                                     => return None,
+        };
+        let mut options = options.split_whitespace();
+        let pos = StampPos::from_name(options.next()?)?;
+        let mut stamp = Stamp{ content, pos, opacity: 100, size: 10, pages: StampPages::All };
+        for option in options {
+            match option.split_once('=')? {
+                ("opacity", o) => stamp.opacity = o.parse::<u32>().ok().filter(|o| (1..=100).contains(o))?,
+                ("size",    z) => stamp.size    = z.parse::<u32>().ok().filter(|z| (1..=100).contains(z))?,
+                ("pages",   p) => stamp.pages   = match p {
+                    "all"   => StampPages::All,
+                    "first" => StampPages::First,
+                    "last"  => StampPages::Last,
+                    range   => {
+                        let (first, last) = range.split_once('-').unwrap_or((range, range));
+                        let first = first.parse::<usize>().ok()?;
+                        let last  = last.parse::<usize>().ok().filter(|l| first <= *l)?;
+                        StampPages::Range(first, last) },
+                },
+                _ => return None,
+            }
+        }
+        Some(stamp)
+    }
+    // format_stamp ////////////////////////////////////////
+    pub fn format_stamp(stamp: &Stamp) -> String {
+        let content = match &stamp.content {
+            StampContent::Text(t)  => format!("text:{}", t),
+            StampContent::Image(i) => format!("image:{}", i),
+        };
+        let pages = match stamp.pages {
+            StampPages::All                   => String::from("all"),
+            StampPages::First                 => String::from("first"),
+            StampPages::Last                  => String::from("last"),
+            StampPages::Range(f, l) if f == l => f.to_string(),
+            StampPages::Range(f, l)           => format!("{}-{}", f, l),
+        };
+        format!("{} @{} opacity={} size={} pages={}", content, stamp.pos.name(), stamp.opacity, stamp.size, pages)
+    }
+    // parse_profiles //////////////////////////////////////
+    /// texts of the entries -> profiles. empty entries are removed, and
+    /// names and directories should be distinct.
+    pub fn parse_profiles(specs: &[String]) -> Option<Vec<ExportProfile>> {
+        let mut profiles: Vec<ExportProfile> = Vec::new();
+        for spec in specs.iter().filter(|s| !s.trim().is_empty()) {
+            let p = parse_profile(spec)?;
+            if profiles.iter().any(|q| q.name == p.name || q.export_dir == p.export_dir) {
+                return None; }
+            profiles.push(p);
+        }
+        Some(profiles)
+    }
+
+    // ExportSettingWin ////////////////////////////////////
+    type SaveFunc = std::boxed::Box<dyn Fn(&[String]) -> bool>;
+    /// rows of entries for an export setting. save gets the texts of the entries and
+    /// returns false if they are invalid, then the window is closed and next is called.
+    struct ExportSettingWin{
+        win             : Window,
+        vbox            : Box,
+        grid            : Grid,
+        entries         : Vec<Entry>,
+        button_box      : Box,
+        ok_button       : Button,
+        cancel_button   : Button,
+        save            : SaveFunc,
+        next            : std::boxed::Box<dyn Fn()>,
+    }
+    impl ExportSettingWin{
+        // key_ctrl ////////////////////////////////////////
+        fn key_ctrl(obj: Rc<Self>) -> EventControllerKey {
+            let kctrl = EventControllerKey::new();
+            kctrl.connect_key_pressed(
+                move|_ctrl, key, _code, _state|{
+                    let mut prop = Propagation::Stop;
+                    match key.name().unwrap().as_str() {
+                        "Escape" => { obj.win.close(); },
+                        "Return" => { obj.apply(); },
+                        _        => { prop = Propagation::Proceed; }
+                    }
+                    prop
+                });
+            kctrl
+        }
+        // apply ///////////////////////////////////////////
+        fn apply(&self) {
+            let texts: Vec<String> = self.entries.iter().map(|e| e.buffer().text().to_string()).collect();
+            if !(self.save)(&texts) {
+                return; }
+            self.win.close();
+            (self.next)();
+        }
+        // build ///////////////////////////////////////////
+        fn build(title : &str,
+                 rows  : Vec<(&str, String)>,
+                 save  : impl Fn(&[String]) -> bool + 'static,
+                 next  : impl Fn() + 'static) -> Rc<Self> {
+            let win           = Window::builder().title( String::from(title) ).modal(true).build();
+            let vbox          = Box::builder().orientation(Orientation::Vertical).build();
+            let grid          = Grid::builder().build();
+            let button_box    = Box::builder().orientation(Orientation::Horizontal).build();
+            let ok_button     = Button::builder().css_classes(vec!["isv2_button"]).build();
+            let cancel_button = Button::builder().css_classes(vec!["isv2_button"]).build();
+
+            let obj = Self{
+                win, vbox, grid,
+                entries: rows.iter().map(|_| Entry::new()).collect(),
+                button_box, ok_button, cancel_button,
+                save: std::boxed::Box::new(save), next: std::boxed::Box::new(next)};
+            let obj = Rc::new(obj);
+
+            for (row, ((label, text), entry)) in rows.into_iter().zip(&obj.entries).enumerate() {
+                let label = Label::builder().label(label).halign(Align::End).build();
+                obj.grid.attach(&label, 0, row as i32, 1, 1);
+                obj.grid.attach(entry, 1, row as i32, 1, 1);
+                entry.buffer().set_text(&text);
+                entry.connect_activate(clone!(@strong obj => move|_e|{ obj.apply(); }));
+            }
+
+            // buttons /////////////////////////////////////
+            obj.button_box.set_halign(Align::End);
+            obj.button_box.set_homogeneous(true);
+
+            obj.ok_button.set_label("export");
+            obj.ok_button.set_hexpand(true);
+            obj.ok_button.connect_clicked(
+                clone!(@strong obj => move|_b|{
+                    obj.apply();
+                }));
+
+            obj.cancel_button.set_label("cancel");
+            obj.cancel_button.set_hexpand(true);
+            obj.cancel_button.connect_clicked(
+                clone!(@strong obj => move|_b|{ obj.win.close(); }));
+
+            obj.button_box.append(&obj.ok_button);
+            obj.button_box.append(&obj.cancel_button);
+
+            obj.vbox.append(&obj.grid);
+            obj.vbox.append(&obj.button_box);
+
+            obj.win.add_controller(Self::key_ctrl(obj.clone()));
+
+            obj.win.set_child(Some(&obj.vbox));
+            obj.win.present();
+
+            obj
+        }
+    }
+    // show_export_error ///////////////////////////////////
+    fn show_export_error(root: &Window, message: &str, e: anyhow::Error) {
+        println!("({}) {:?}", message, e);
+        let dialog = AlertDialog::builder().modal(true).build();
+        dialog.set_message(message);
+        dialog.set_detail(&format!("{:#}", e));
+        dialog.show(Some(root));
+    }
+    // ask_animation_file //////////////////////////////////
+    /// asks the output file and exports the animation
+    fn ask_animation_file(store: ListStore, param: Isv2Parameter, pwin: PreviewWindow, root: Window) {
+        let name = Path::new(&param.property::<String>("project_file_name")).with_extension("gif");
+        let file_dialog = FileDialog::builder().modal(true).initial_name(name.to_string_lossy()).build();
+        let param_path = param.property::<PathBuf>("project_dir");
+        if param_path.exists() && param_path.is_dir() {
+            file_dialog.set_initial_folder(Some(&gio::File::for_path(param_path)));
+        }
+        let model = gio::ListStore::with_type(FileFilter::static_type());
+        for (name, patterns) in [("GIF(gif)", vec!["*.gif"]), ("APNG(png, apng)", vec!["*.png", "*.apng"]), ("WebP(webp)", vec!["*.webp"])] {
+            let file_filter = FileFilter::new();
+            for p in patterns { file_filter.add_pattern(p); }
+            file_filter.set_name(Some(name));
+            model.append(&file_filter);
+        }
+        file_dialog.set_filters(Some(&model));
+
+        file_dialog.save(Some(&root.clone()),
+                         None::<Cancellable>.as_ref(),
+                         move|result|{
+                             let file = if let Ok(f) = result { f } else { return; };
+                             let path = if let Some(p) = file.path() { p } else { return; };
+                             let sn = if let Some(sno) = store.item(0) {
+                                 sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                                 else { println!("(export_animation) store has noitem"); return; };
+                             if let Err(e) = pwin.export_animation(&sn, &param, &path) {
+                                 show_export_error(&root, "Exporting animation failed", e); }
+                         });
+    }
+    // ask_video_dir ///////////////////////////////////////
+    /// asks the output folder and exports the frame sequence
+    fn ask_video_dir(store: ListStore, param: Isv2Parameter, pwin: PreviewWindow, root: Window) {
+        let file_dialog = FileDialog::builder().modal(true).build();
+        let param_path = param.property::<PathBuf>("project_dir");
+        if param_path.exists() && param_path.is_dir() {
+            file_dialog.set_initial_folder(Some(&gio::File::for_path(param_path)));
+        }
+        file_dialog.select_folder(Some(&root.clone()),
+                                  None::<Cancellable>.as_ref(),
+                                  move|result|{
+                                      let file = if let Ok(f) = result { f } else { return; };
+                                      let path = if let Some(p) = file.path() { p } else { return; };
+                                      let sn = if let Some(sno) = store.item(0) {
+                                          sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                                          else { println!("(export_video) store has noitem"); return; };
+                                      if let Err(e) = pwin.export_video(&sn, &param, &path) {
+                                          show_export_error(&root, "Exporting video frames failed", e); }
+                                  });
+    }
+    // ask_html_dir ////////////////////////////////////////
+    /// asks the output folder and exports the pages with the viewer
+    fn ask_html_dir(store: ListStore, param: Isv2Parameter, pwin: PreviewWindow, root: Window, text_on: bool) {
+        let file_dialog = FileDialog::builder().modal(true).build();
+        let param_path = param.property::<PathBuf>("project_dir");
+        if param_path.exists() && param_path.is_dir() {
+            file_dialog.set_initial_folder(Some(&gio::File::for_path(param_path)));
+        }
+        file_dialog.select_folder(Some(&root.clone()),
+                                  None::<Cancellable>.as_ref(),
+                                  move|result|{
+                                      let file = if let Ok(f) = result { f } else { return; };
+                                      let path = if let Some(p) = file.path() { p } else { return; };
+                                      let sn = if let Some(sno) = store.item(0) {
+                                          sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                                          else { println!("(export_html) store has noitem"); return; };
+                                      if let Err(e) = pwin.export_html(&sn, &param, &path, text_on) {
+                                          show_export_error(&root, "Exporting HTML viewer failed", e); }
+                                  });
+    }
+    // ask_contact_sheet_file ///////////////////////////////
+    /// asks the output file and exports the contact sheets
+    fn ask_contact_sheet_file(store: ListStore, param: Isv2Parameter, pwin: PreviewWindow, root: Window) {
+        let name = Path::new(&param.property::<String>("project_file_name")).with_extension("pdf");
+        let file_dialog = FileDialog::builder().modal(true).initial_name(name.to_string_lossy()).build();
+        let param_path = param.property::<PathBuf>("project_dir");
+        if param_path.exists() && param_path.is_dir() {
+            file_dialog.set_initial_folder(Some(&gio::File::for_path(param_path)));
+        }
+        let model = gio::ListStore::with_type(FileFilter::static_type());
+        for (name, pattern) in [("PDF(pdf)", "*.pdf"), ("PNG(png)", "*.png")] {
+            let file_filter = FileFilter::new();
+            file_filter.add_pattern(pattern);
+            file_filter.set_name(Some(name));
+            model.append(&file_filter);
+        }
+        file_dialog.set_filters(Some(&model));
+
+        file_dialog.save(Some(&root.clone()),
+                         None::<Cancellable>.as_ref(),
+                         move|result|{
+                             let file = if let Ok(f) = result { f } else { return; };
+                             let path = if let Some(p) = file.path() { p } else { return; };
+                             let sn = if let Some(sno) = store.item(0) {
+                                 sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                                 else { println!("(export_contact_sheet) store has noitem"); return; };
+                             if let Err(e) = pwin.export_contact_sheet(&sn, &param, &path) {
+                                 show_export_error(&root, "Exporting contact sheet failed", e); }
+                         });
+    }
+    // act_export_animation ////////////////////////////////
+    pub fn act_export_animation(store : ListStore,
+                                param : Isv2Parameter,
+                                pwin  : PreviewWindow,
+                                root  : impl IsA<Window>) -> SimpleAction {
+        let act_export_animation = SimpleAction::new(ACT_EXPORT_ANIMATION, None);
+        act_export_animation.connect_activate(move|_act, _val|{
+            let setting = param.animation();
+            let rows = vec![
+                ("first page",                      setting.first_page.to_string()),
+                ("last page(empty: last)",          setting.last_page.map(|l| l.to_string()).unwrap_or_default()),
+                ("hold time[ms]",                   setting.hold_ms.to_string()),
+                ("crossfade[ms](0: off)",           setting.fade_ms.to_string()),
+                ("frame rate[fps]",                 setting.fps.to_string()),
+                ("typewriter[chars/s](empty: off)", setting.typewriter_cps.map(|c| c.to_string()).unwrap_or_default()),
+                ("loop count(0: infinite)",         setting.loop_count.to_string()),
+            ];
+            let root = root.clone().upcast::<Window>();
+            ExportSettingWin::build("export animation", rows,
+                                    clone!(@strong param => move|t|{
+                                        if let Some(s) = parse_animation_setting(&t[0], &t[1], &t[2], &t[3], &t[4], &t[5], &t[6]) {
+                                            param.set_animation(s);
+                                            true
+                                        } else { false }
+                                    }),
+                                    clone!(@strong store, @strong param, @strong pwin => move||{
+                                        ask_animation_file(store.clone(), param.clone(), pwin.clone(), root.clone());
+                                    }));
+        });
+        act_export_animation
+    }
+    // act_export_video ////////////////////////////////////
+    pub fn act_export_video(store : ListStore,
+                            param : Isv2Parameter,
+                            pwin  : PreviewWindow,
+                            root  : impl IsA<Window>) -> SimpleAction {
+        let act_export_video = SimpleAction::new(ACT_EXPORT_VIDEO, None);
+        act_export_video.connect_activate(move|_act, _val|{
+            let setting = param.video();
+            let rows = vec![
+                ("frame rate[fps]",                 setting.fps.to_string()),
+                ("page duration[ms]",               setting.duration_ms.to_string()),
+                ("typewriter[chars/s](empty: off)", setting.typewriter_cps.map(|c| c.to_string()).unwrap_or_default()),
+            ];
+            let root = root.clone().upcast::<Window>();
+            ExportSettingWin::build("export video frames", rows,
+                                    clone!(@strong param => move|t|{
+                                        if let Some(s) = parse_video_setting(&t[0], &t[1], &t[2]) {
+                                            param.set_video(s);
+                                            true
+                                        } else { false }
+                                    }),
+                                    clone!(@strong store, @strong param, @strong pwin => move||{
+                                        ask_video_dir(store.clone(), param.clone(), pwin.clone(), root.clone());
+                                    }));
+        });
+        act_export_video
+    }
+    // act_export_html /////////////////////////////////////
+    /// exports the pages with a static viewer, the text overlay is chosen before
+    pub fn act_export_html(store : ListStore,
+                           param : Isv2Parameter,
+                           pwin  : PreviewWindow,
+                           root  : impl IsA<Window>) -> SimpleAction {
+        let act_export_html = SimpleAction::new(ACT_EXPORT_HTML, None);
+        act_export_html.connect_activate(move|_act, _val|{
+            let dialog = AlertDialog::builder().modal(true).build();
+            dialog.set_buttons(&["Text overlay off", "Text overlay on", "Cancel"]);
+            dialog.set_message("Export HTML viewer");
+            dialog.set_detail("The text overlay can be toggled with the t key in the viewer.");
+            dialog.set_default_button(0);
+            dialog.set_cancel_button(2);
+            let root = root.clone().upcast::<Window>();
+            dialog.choose(Some(&root.clone()), None::<&Cancellable>,
+                          clone!(@strong store, @strong param, @strong pwin => move|result|{
+                              match result {
+                                  Ok(0) => ask_html_dir(store, param, pwin, root, false),
+                                  Ok(1) => ask_html_dir(store, param, pwin, root, true),
+                                  _     => (),
+                              }
+                          }));
+        });
+        act_export_html
+    }
+    // act_export_renpy ////////////////////////////////////
+    /// writes the Ren'Py script of the current language into the selected game dir
+    pub fn act_export_renpy(store : ListStore,
+                            param : Isv2Parameter,
+                            root  : impl IsA<Window>) -> SimpleAction {
+        let act_export_renpy = SimpleAction::new(ACT_EXPORT_RENPY, None);
+        act_export_renpy.connect_activate(move|_act, _val|{
+            let file_dialog = FileDialog::builder().modal(true).title("Ren'Py game folder").build();
+            let param_path = param.property::<PathBuf>("project_dir");
+            if param_path.exists() && param_path.is_dir() {
+                file_dialog.set_initial_folder(Some(&gio::File::for_path(param_path)));
+            }
+            let root = root.clone().upcast::<Window>();
+            file_dialog.select_folder(Some(&root.clone()),
+                                      None::<Cancellable>.as_ref(),
+                                      clone!(@strong store, @strong param => move|result|{
+                                          let file = if let Ok(f) = result { f } else { return; };
+                                          let path = if let Some(p) = file.path() { p } else { return; };
+                                          let sn = if let Some(sno) = store.item(0) {
+                                              sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                                              else { println!("(export_renpy) store has noitem"); return; };
+                                          let lang = param.property::<String>("language");
+                                          let r = renpy::renpy(&sn,
+                                                               &param.property::<PathBuf>("project_dir"),
+                                                               &param.text_key(&lang),
+                                                               (param.property::<i32>("target_width"),
+                                                                param.property::<i32>("target_height")));
+                                          if let Err(e) = renpy::write_renpy(&path, &r) {
+                                              show_export_error(&root, "Exporting Ren'Py script failed", e);
+                                              return; }
+                                          r.warnings.iter().for_each(|w| println!("(export_renpy) {}", w));
+                                          if !r.warnings.is_empty() {
+                                              let dialog = AlertDialog::builder().modal(true).build();
+                                              dialog.set_message(&format!("Ren'Py script was written with {} warnings", r.warnings.len()));
+                                              let mut detail: Vec<String> = r.warnings.iter().take(20).cloned().collect();
+                                              if r.warnings.len() > 20 {
+                                                  detail.push(format!("and {} more", r.warnings.len() - 20)); }
+                                              dialog.set_detail(&detail.join("\n"));
+                                              dialog.show(Some(&root));
+                                          }
+                                      }));
+        });
+        act_export_renpy
+    }
+    // act_export_contact_sheet ////////////////////////////
+    pub fn act_export_contact_sheet(store : ListStore,
+                                    param : Isv2Parameter,
+                                    pwin  : PreviewWindow,
+                                    root  : impl IsA<Window>) -> SimpleAction {
+        let act_export_sheet = SimpleAction::new(ACT_EXPORT_SHEET, None);
+        act_export_sheet.connect_activate(move|_act, _val|{
+            let setting = param.contact_sheet();
+            let rows = vec![
+                ("columns[1--20]",               setting.columns.to_string()),
+                ("rows[1--20]",                  setting.rows.to_string()),
+                ("thumbnail width[px][16--2000]", setting.thumb_width.to_string()),
+            ];
+            let root = root.clone().upcast::<Window>();
+            ExportSettingWin::build("export contact sheet", rows,
+                                    clone!(@strong param => move|t|{
+                                        if let Some(s) = parse_contact_sheet_setting(&t[0], &t[1], &t[2]) {
+                                            param.set_contact_sheet(s);
+                                            true
+                                        } else { false }
+                                    }),
+                                    clone!(@strong store, @strong param, @strong pwin => move||{
+                                        ask_contact_sheet_file(store.clone(), param.clone(), pwin.clone(), root.clone());
+                                    }));
+        });
+        act_export_sheet
+    }
+
+    // act_export_subset ///////////////////////////////////
+    /// exports the pages under the selected node or in a range of page numbers
+    pub fn act_export_subset(store     : ListStore,
+                             selection : SingleSelection,
+                             param     : Isv2Parameter,
+                             pwin      : PreviewWindow,
+                             root      : impl IsA<Window>) -> SimpleAction {
+        let act_export_subset = SimpleAction::new(ACT_EXPORT_SUBSET, None);
+        act_export_subset.connect_activate(move|_act, _val|{
+            let setting = param.subset();
+            let rows = vec![
+                ("pages(empty: selected node, e.g. 120-140)", setting.pages.map(|(f, l)| format!("{}-{}", f, l)).unwrap_or_default()),
+                ("numbering(global/local)",                   String::from(if setting.local_numbering { "local" } else { "global" })),
+            ];
+            let root = root.clone().upcast::<Window>();
+            ExportSettingWin::build("export images(subset)", rows,
+                                    clone!(@strong param => move|t|{
+                                        if let Some(s) = parse_subset_setting(&t[0], &t[1]) {
+                                            param.set_subset(s);
+                                            true
+                                        } else { false }
+                                    }),
+                                    clone!(@strong store, @strong selection, @strong param, @strong pwin => move||{
+                                        let setting = param.subset();
+                                        let subset = if let Some((first, last)) = setting.pages {
+                                            Subset{ scope: Scope::Pages(first, last), local: setting.local_numbering }
+                                        } else if let Some(row) = selection.selected_item() {
+                                            let row = row.downcast::<TreeListRow>().expect("expect row");
+                                            let sno = row.item().unwrap().downcast::<ScenarioNodeObject>().expect("sno");
+                                            Subset::subtree(&sno.get_node(), setting.local_numbering)
+                                        } else {
+                                            show_export_error(&root, "Exporting images failed", anyhow::anyhow!("no node is selected"));
+                                            return;
+                                        };
+                                        let sn = if let Some(sno) = store.item(0) {
+                                            sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                                            else { println!("(export_subset) store has noitem"); return; };
+                                        pwin.export_images(&sn, &param, &root, ExportTarget::of_param(&param, false, ImageFormat::Png), &subset);
+                                    }));
+        });
+        act_export_subset
+    }
+
+    // act_export_profiles /////////////////////////////////
+    /// edits the export profiles and exports the images of all of them
+    pub fn act_export_profiles(store : ListStore,
+                               param : Isv2Parameter,
+                               pwin  : PreviewWindow,
+                               root  : impl IsA<Window>) -> SimpleAction {
+        let act_export_profiles = SimpleAction::new(ACT_EXPORT_PROFILES, None);
+        act_export_profiles.connect_activate(move|_act, _val|{
+            let profiles = param.profiles();
+            let labels: Vec<String> = (1..=profiles.len()).map(|i| format!("profile {}", i)).collect();
+            let mut rows: Vec<(&str, String)> = labels.iter().map(|l| l.as_str()).zip(profiles.iter().map(format_profile)).collect();
+            rows.push(("new(name, 1920x1080, jpeg:90, dir, {seq}, en | text:DRAFT @center opacity=30)", String::new()));
+            let root = root.clone().upcast::<Window>();
+            ExportSettingWin::build("export profiles", rows,
+                                    clone!(@strong param => move|t|{
+                                        let p = if let Some(p) = parse_profiles(t) { p } else { return false; };
+                                        if let Some(q) = p.iter().find(|q| !render::can_write(q.format)) {
+                                            println!("(export_profiles) {}: {} is not supported by gdk-pixbuf", q.name, q.format.extension());
+                                            return false; }
+                                        param.set_profiles(p);
+                                        true
+                                    }),
+                                    clone!(@strong store, @strong param, @strong pwin => move||{
+                                        let lang = param.property::<String>("language");
+                                        let project_dir = param.property::<PathBuf>("project_dir");
+                                        let targets: Vec<ExportTarget> = param.profiles().iter()
+                                            .map(|p| ExportTarget::of_profile(p, &project_dir, &lang)).collect();
+                                        if targets.is_empty() { return; }
+                                        let sn = if let Some(sno) = store.item(0) {
+                                            sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                                            else { println!("(export_profiles) store has noitem"); return; };
+                                        pwin.export_images(&sn, &param, &root, targets, &Subset::default());
+                                    }));
+        });
+        act_export_profiles
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export_util::image_format::Content;
+    use crate::export_util::image_format::ImageFormat;
+    use crate::export_util::stamp::StampContent;
+    use crate::export_util::stamp::StampPages;
+    use crate::export_util::stamp::StampPos;
+    use crate::export_menu::export_actions::format_profile;
+    use crate::export_menu::export_actions::format_stamp;
+    use crate::export_menu::export_actions::parse_animation_setting;
+    use crate::export_menu::export_actions::parse_contact_sheet_setting;
+    use crate::export_menu::export_actions::parse_profile;
+    use crate::export_menu::export_actions::parse_profiles;
+    use crate::export_menu::export_actions::parse_stamp;
+    use crate::export_menu::export_actions::parse_subset_setting;
+    use crate::export_menu::export_actions::parse_video_setting;
+
+    #[test]
+    fn test_parse_animation_setting() {
+        let s = parse_animation_setting("2", "", "1500", "0", "12", "", "1").unwrap();
+        assert_eq!((s.first_page, s.last_page, s.hold_ms, s.fade_ms, s.fps, s.typewriter_cps, s.loop_count),
+                   (2, None, 1500, 0, 12, None, 1));
+        let s = parse_animation_setting("0", "3", "1000", "500", "10", "20", "0").unwrap();
+        assert_eq!((s.last_page, s.typewriter_cps), (Some(3), Some(20)));
+        assert_eq!(parse_animation_setting("3", "2", "1000", "500", "10", "", "0"), None); // reversed range
+        assert_eq!(parse_animation_setting("0", "", "0", "500", "10", "", "0"), None);    // no hold time
+        assert_eq!(parse_animation_setting("0", "", "1000", "500", "0", "", "0"), None);  // no frame rate
+        assert_eq!(parse_animation_setting("0", "", "1000", "500", "10", "x", "0"), None);
+    }
+
+    #[test]
+    fn test_parse_contact_sheet_setting() {
+        let s = parse_contact_sheet_setting("4", " 6", "240").unwrap();
+        assert_eq!((s.columns, s.rows, s.thumb_width), (4, 6, 240));
+        assert_eq!(parse_contact_sheet_setting("0", "6", "240"), None);  // no column
+        assert_eq!(parse_contact_sheet_setting("4", "6", "8"), None);    // too small thumbnail
+        assert_eq!(parse_contact_sheet_setting("4", "x", "240"), None);
+    }
+
+    #[test]
+    fn test_parse_video_setting() {
+        let s = parse_video_setting("24", "2500", "").unwrap();
+        assert_eq!((s.fps, s.duration_ms, s.typewriter_cps), (24, 2500, None));
+        assert_eq!(parse_video_setting("30", "3000", "15").unwrap().typewriter_cps, Some(15));
+        assert_eq!(parse_video_setting("0", "3000", ""), None);   // no frame rate
+        assert_eq!(parse_video_setting("30", "0", ""), None);     // no duration
+        assert_eq!(parse_video_setting("30", "3000", "x"), None);
+    }
+
+    #[test]
+    fn test_parse_subset_setting() {
+        let s = parse_subset_setting("120-140", "global").unwrap();
+        assert_eq!((s.pages, s.local_numbering), (Some((120, 140)), false));
+        assert_eq!(parse_subset_setting(" 120 \u{2013} 140 ", "local").unwrap().pages, Some((120, 140)));
+        assert_eq!(parse_subset_setting("7", "local").unwrap().pages, Some((7, 7)));
+        assert_eq!(parse_subset_setting("", "local").unwrap().pages, None);  // selected node
+        assert_eq!(parse_subset_setting("140-120", "global"), None);         // reversed range
+        assert_eq!(parse_subset_setting("1-x", "global"), None);
+        assert_eq!(parse_subset_setting("1-2", "both"), None);
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let p = parse_profile("hd, 1920x1080, jpeg:85, hd, {seq}_{page}, en").unwrap();
+        assert_eq!((p.name.as_str(), p.width, p.height, p.format), ("hd", 1920, 1080, ImageFormat::Jpeg{ quality: 85 }));
+        assert_eq!((p.export_dir.as_str(), p.name_template.as_str(), p.language.as_str()), ("hd", "{seq}_{page}", "en"));
+        assert_eq!(parse_profile(&format_profile(&p)), Some(p));
+        let p = parse_profile("print, 1200x1800, webp, print").unwrap();
+        assert_eq!((p.format, p.name_template.as_str(), p.language.as_str()), (ImageFormat::Webp{ quality: 90 }, "{seq}", ""));
+        assert_eq!(parse_profile(&format_profile(&p)), Some(p));
+        assert_eq!(parse_profile("s, 640x360, svg-link, s").unwrap().format, ImageFormat::Svg{ link_bg: true });
+        assert_eq!(parse_profile("s, 640x360, ora, s").unwrap().format, ImageFormat::Ora);
+        let p = parse_profile("t, 640x360, webp:80+text, t").unwrap();
+        assert_eq!((p.format, p.content), (ImageFormat::Webp{ quality: 80 }, Content::TextOnly));
+        assert_eq!(parse_profile(&format_profile(&p)), Some(p));
+        assert_eq!(parse_profile("t, 640x360, png+transparent, t").unwrap().content, Content::Transparent);
+        assert_eq!(parse_profile("t, 640x360, jpeg+text, t"), None); // jpeg has no alpha
+        assert_eq!(parse_profile("t, 640x360, png+bg, t"), None);
+        assert_eq!(parse_profile("s, 640x360, png:50, s"), None);     // png has no quality
+        assert_eq!(parse_profile("s, 640x360, jpeg:0, s"), None);
+        assert_eq!(parse_profile("s, 640, png, s"), None);
+        assert_eq!(parse_profile("s, 640x360, png, a/b"), None);      // not a dir name
+        assert_eq!(parse_profile("s, 640x360, png, s, {page}"), None); // names are not distinct
+
+        let specs = |v: &[&str]| -> Vec<String> { v.iter().map(|s| s.to_string()).collect() };
+        assert_eq!(parse_profiles(&specs(&["a, 1x1, png, a", "", "b, 2x2, png, b"])).unwrap().len(), 2);
+        assert_eq!(parse_profiles(&specs(&["a, 1x1, png, a", "b, 2x2, png, a"])), None); // same dir
+    }
+
+    #[test]
+    fn test_parse_stamp() {
+        let p = parse_profile("review, 1280x720, jpeg, review | text:DRAFT @center opacity=30 size=20 \
+                               | text:(c) 2026 studio, all rights reserved @bottom-right size=3 pages=last").unwrap();
+        assert_eq!(p.stamps.len(), 2);
+        assert_eq!((&p.stamps[0].content, p.stamps[0].pos, p.stamps[0].opacity, p.stamps[0].pages),
+                   (&StampContent::Text("DRAFT".to_string()), StampPos::Center, 30, StampPages::All));
+        assert_eq!(p.stamps[1].content, StampContent::Text("(c) 2026 studio, all rights reserved".to_string()));
+        assert_eq!((p.stamps[1].size, p.stamps[1].pages), (3, StampPages::Last));
+        assert_eq!(parse_profile(&format_profile(&p)), Some(p));
+
+        let s = parse_stamp("image:img/logo.png @top-left pages=2-4").unwrap();
+        assert_eq!((s.content, s.opacity, s.size, s.pages),
+                   (StampContent::Image("img/logo.png".to_string()), 100, 10, StampPages::Range(2, 4)));
+        assert_eq!(parse_stamp("text:mail @ example @top").unwrap().content, StampContent::Text("mail @ example".to_string()));
+        assert_eq!(parse_stamp(&format_stamp(&parse_stamp("text:a @top pages=7").unwrap())).unwrap().pages, StampPages::Range(7, 7));
+        assert_eq!(parse_stamp("text:DRAFT"), None);               // no position
+        assert_eq!(parse_stamp("text: @center"), None);            // no text
+        assert_eq!(parse_stamp("text:DRAFT @middle"), None);
+        assert_eq!(parse_stamp("text:DRAFT @center opacity=0"), None);
+        assert_eq!(parse_stamp("text:DRAFT @center pages=4-2"), None);
+        assert_eq!(parse_stamp("video:a.mp4 @center"), None);
+        assert_eq!(parse_profile("s, 640x360, png, s | text:DRAFT"), None);
+    }
+}
@@ -0,0 +1,304 @@
+//! animated image (GIF / APNG / WebP) of a range of pages
+//!
+//! pages are held for a while, crossfaded to the next page and optionally
+//! revealed like a typewriter. frames are encoded one by one with the gif,
+//! png and webp crates, and WebP frames are muxed into an animation here.
+
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::isv2_parameter::AnimationSetting;
+use crate::scenario_node::ScenarioNode;
+use super::transcript::child_mats;
+use super::transcript::strip_markup;
+
+// visible_len /////////////////////////////////////////
+/// number of characters shown for pango markup
+pub fn visible_len(markup: &str) -> usize{
+    strip_markup(markup).chars().count()
+}
+// truncate_markup /////////////////////////////////////
+/// keeps the first n visible characters of pango markup and closes open tags
+pub fn truncate_markup(markup: &str, n: usize) -> String{
+    let mut out   = String::new();
+    let mut open: Vec<String> = Vec::new();
+    let mut count = 0;
+    let mut rest  = markup;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            let end = if let Some(e) = rest.find('>') { e + 1 } else { break; };
+            let tag = &rest[..end];
+            if tag.starts_with("</") {
+                open.pop(); }
+            else if !tag.ends_with("/>") {
+                let name: String = tag[1..].chars().take_while(|c| c.is_alphanumeric()).collect();
+                open.push(name);
+            }
+            out.push_str(tag);
+            rest = &rest[end..];
+            continue;
+        }
+        if count >= n {
+            break; }
+        let len = if c == '&' {
+            match rest.find(';') { Some(e) if e <= 9 => e + 1, _ => 1 } }
+            else { c.len_utf8() };
+        out.push_str(&rest[..len]);
+        rest = &rest[len..];
+        count += 1;
+    }
+    for name in open.iter().rev() {
+        out += &format!("</{}>", name); }
+    out
+}
+// page_text_len ///////////////////////////////////////
+/// characters revealed by the typewriter: texts of the mats in the page (not in the scene)
+pub fn page_text_len(sn: &Rc<ScenarioNode>, text_key: &str) -> usize{
+    let mats = if sn.is_pmat() { vec![sn.clone()] } else { child_mats(sn) };
+    mats.iter().map(|m| visible_len(&m.get_mat_text_lang(text_key).unwrap_or_default())).sum()
+}
+
+// plan ////////////////////////////////////////////////
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameSpec{
+    pub page     : usize,                      // index in the range
+    pub chars    : Option<usize>,              // revealed characters, None: all
+    pub fade     : Option<(Option<usize>, f64)>, // chars and opacity of the next page
+    pub delay_ms : u32,
+}
+/// frames for pages whose typewriter text lengths are text_lens
+pub fn plan(text_lens: &[usize], s: &AnimationSetting) -> Vec<FrameSpec>{
+    let mut frames: Vec<FrameSpec> = Vec::new();
+    let mut push = |f: FrameSpec| {
+        match frames.last_mut() {
+            Some(l) if (l.page, l.chars, l.fade) == (f.page, f.chars, f.fade) => l.delay_ms += f.delay_ms,
+            _ => frames.push(f),
+        }
+    };
+    let frame_ms = 1000 / s.fps.clamp(1, 100);
+    let cps      = s.typewriter_cps.filter(|c| *c > 0);
+    let start    = |len: usize| if cps.is_some() && len > 0 { Some(0) } else { None };
+    for (page, len) in text_lens.iter().cloned().enumerate() {
+        // typewriter
+        if let Some(cps) = cps {
+            let reveal_ms = (len as u64 * 1000).div_ceil(cps as u64);
+            let mut t = 0;
+            while t < reveal_ms {
+                let chars = (t * cps as u64 / 1000) as usize;
+                push(FrameSpec{ page, chars: Some(chars).filter(|c| *c < len), fade: None, delay_ms: frame_ms });
+                t += frame_ms as u64;
+            }
+        }
+        push(FrameSpec{ page, chars: None, fade: None, delay_ms: s.hold_ms.max(1) });
+        // crossfade
+        if page + 1 < text_lens.len() && s.fade_ms > 0 {
+            let steps = (s.fade_ms / frame_ms).max(1);
+            for i in 1..=steps {
+                let alpha = i as f64 / (steps + 1) as f64;
+                let delay = s.fade_ms * i / steps - s.fade_ms * (i - 1) / steps;
+                push(FrameSpec{ page, chars: None, fade: Some((start(text_lens[page + 1]), alpha)), delay_ms: delay });
+            }
+        }
+    }
+    frames
+}
+
+// AnimFormat //////////////////////////////////////////
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimFormat { Gif, Apng, Webp }
+impl AnimFormat {
+    pub fn from_path(path: &Path) -> Option<Self>{
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "gif"          => Some(Self::Gif),
+            "png" | "apng" => Some(Self::Apng),
+            "webp"         => Some(Self::Webp),
+            _              => None,
+        }
+    }
+}
+
+// AnimWriter //////////////////////////////////////////
+/// quality of the lossy WebP frames
+const WEBP_QUALITY: f32 = 90.0;
+
+enum Encoder<W: Write>{
+    Gif(gif::Encoder<W>),
+    Apng(png::Writer<W>),
+    Webp{ out: W, body: Vec<u8>, alpha: bool }, // ANMF chunks are muxed here
+}
+/// writes the frames into out one by one, frames is the number of the frames
+pub struct AnimWriter<W: Write>{
+    encoder : Encoder<W>,
+    width   : u32,
+    height  : u32,
+    loop_count : u16,
+    time_ms : u64, // elapsed time to round gif delays without drift
+}
+impl<W: Write> AnimWriter<W>{
+    pub fn new(out: W, format: AnimFormat, width: u32, height: u32, loop_count: u16, frames: u32) -> anyhow::Result<Self>{
+        let encoder = match format {
+            AnimFormat::Gif  => {
+                let mut e = gif::Encoder::new(out, width as u16, height as u16, &[])?;
+                e.set_repeat(if loop_count == 0 { gif::Repeat::Infinite } else { gif::Repeat::Finite(loop_count) })?;
+                Encoder::Gif(e)
+            },
+            AnimFormat::Apng => {
+                let mut e = png::Encoder::new(out, width, height);
+                e.set_color(png::ColorType::Rgba);
+                e.set_depth(png::BitDepth::Eight);
+                e.set_animated(frames.max(1), loop_count as u32)?;
+                Encoder::Apng(e.write_header()?)
+            },
+            AnimFormat::Webp => Encoder::Webp{ out, body: Vec::new(), alpha: false },
+        };
+        Ok(Self{ encoder, width, height, loop_count, time_ms: 0 })
+    }
+
+    /// rgba is not premultiplied
+    pub fn add_frame(&mut self, rgba: &[u8], delay_ms: u32) -> anyhow::Result<()>{
+        anyhow::ensure!(rgba.len() == (self.width * self.height * 4) as usize, "frame size mismatch");
+        match &mut self.encoder {
+            Encoder::Gif(e) => {
+                // palette of each frame by NeuQuant
+                let mut pixels = rgba.to_vec();
+                let mut frame = gif::Frame::from_rgba_speed(self.width as u16, self.height as u16, &mut pixels, 10);
+                frame.delay = ((self.time_ms + delay_ms as u64 + 5) / 10 - (self.time_ms + 5) / 10) as u16;
+                self.time_ms += delay_ms as u64;
+                e.write_frame(&frame)?;
+            },
+            Encoder::Apng(e) => {
+                e.set_frame_delay(delay_ms.min(0xffff) as u16, 1000)?;
+                e.write_image_data(rgba)?;
+            },
+            Encoder::Webp{ body, alpha, .. } => {
+                let still = webp::Encoder::from_rgba(rgba, self.width, self.height)
+                    .encode_simple(false, WEBP_QUALITY)
+                    .map_err(|e| anyhow::anyhow!("webp encoding failed: {:?}", e))?;
+                let mut anmf = Vec::new();
+                for v in [0, 0, self.width - 1, self.height - 1, delay_ms.min(0xff_ffff)] {
+                    anmf.extend_from_slice(&u24(v)); }
+                anmf.push(0x02); // no blending, no disposal
+                for (fourcc, data) in riff_chunks(&still[12..])? {
+                    match &fourcc {
+                        b"ALPH"          => { *alpha = true; riff_chunk(&mut anmf, &fourcc, data); },
+                        b"VP8 " | b"VP8L" => riff_chunk(&mut anmf, &fourcc, data),
+                        _                => (), // VP8X of the still image
+                    }
+                }
+                riff_chunk(body, b"ANMF", &anmf);
+            },
+        }
+        Ok(())
+    }
+    pub fn finish(self) -> anyhow::Result<()>{
+        match self.encoder {
+            Encoder::Gif(e)  => { e.into_inner()?; },
+            Encoder::Apng(e) => e.finish()?,
+            Encoder::Webp{ mut out, body, alpha } => {
+                let mut webp = b"WEBP".to_vec();
+                let mut vp8x = vec![if alpha { 0x12 } else { 0x02 }, 0, 0, 0]; // animation and alpha
+                vp8x.extend_from_slice(&u24(self.width - 1));
+                vp8x.extend_from_slice(&u24(self.height - 1));
+                riff_chunk(&mut webp, b"VP8X", &vp8x);
+                let mut anim = vec![0xff, 0xff, 0xff, 0xff];
+                anim.extend_from_slice(&self.loop_count.to_le_bytes());
+                riff_chunk(&mut webp, b"ANIM", &anim);
+                webp.extend_from_slice(&body);
+                let mut riff = Vec::new();
+                riff_chunk(&mut riff, b"RIFF", &webp);
+                out.write_all(&riff)?;
+            },
+        }
+        Ok(())
+    }
+}
+fn riff_chunk(out: &mut Vec<u8>, fourcc: &[u8; 4], data: &[u8]){
+    out.extend_from_slice(fourcc);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 { out.push(0); }
+}
+/// (fourcc, data) of the chunks in a RIFF body
+fn riff_chunks(mut rest: &[u8]) -> anyhow::Result<Vec<([u8; 4], &[u8])>>{
+    let mut chunks = Vec::new();
+    while rest.len() >= 8 {
+        let len = u32::from_le_bytes(rest[4..8].try_into()?) as usize;
+        anyhow::ensure!(rest.len() >= 8 + len, "broken riff chunk");
+        chunks.push((rest[0..4].try_into()?, &rest[8..8 + len]));
+        rest = &rest[(8 + len + len % 2).min(rest.len())..];
+    }
+    Ok(chunks)
+}
+fn u24(v: u32) -> [u8; 3]{
+    let b = v.to_le_bytes();
+    [b[0], b[1], b[2]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_markup() {
+        assert_eq!(truncate_markup("<b>bold</b> text", 2), "<b>bo</b>");
+        assert_eq!(truncate_markup("<span foreground=\"red\">a&amp;b</span>c", 2), "<span foreground=\"red\">a&amp;</span>");
+        assert_eq!(truncate_markup("<b>ab</b>c", 2), "<b>ab</b>");
+        assert_eq!(truncate_markup("abc", 0), "");
+        assert_eq!(visible_len("<i>a&lt;b</i>"), 3);
+    }
+
+    #[test]
+    fn test_plan() {
+        let s = AnimationSetting{ hold_ms: 1000, fade_ms: 200, fps: 10, typewriter_cps: Some(20), ..Default::default() };
+        let f = |page, chars, fade, delay_ms| FrameSpec{ page, chars, fade, delay_ms };
+        assert_eq!(plan(&[3, 0], &s),
+                   vec![f(0, Some(0), None, 100),
+                        f(0, Some(2), None, 100),
+                        f(0, None,    None, 1000),
+                        f(0, None,    Some((None, 1.0 / 3.0)), 100),
+                        f(0, None,    Some((None, 2.0 / 3.0)), 100),
+                        f(1, None,    None, 1000)]);
+        let s = AnimationSetting{ hold_ms: 500, fade_ms: 0, typewriter_cps: Some(5), ..Default::default() };
+        // 1 char per 2 frames
+        assert_eq!(plan(&[2], &s),
+                   vec![f(0, Some(0), None, 200),
+                        f(0, Some(1), None, 200),
+                        f(0, None,    None, 500)]);
+    }
+
+    #[test]
+    fn test_anim_writer() {
+        let rgba: Vec<u8> = (0..2 * 2).flat_map(|i| [i as u8 * 60, 0, 255, 255]).collect();
+        // gif delays are rounded without drift
+        let mut gif = Vec::new();
+        let mut w = AnimWriter::new(&mut gif, AnimFormat::Gif, 2, 2, 0, 3).unwrap();
+        for _ in 0..3 { w.add_frame(&rgba, 33).unwrap(); }
+        w.finish().unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(gif.as_slice()).unwrap();
+        let mut delays = Vec::new();
+        while let Some(f) = decoder.read_next_frame().unwrap() {
+            delays.push(f.delay); }
+        assert_eq!(delays, vec![3, 4, 3]);
+
+        let mut webp = Vec::new();
+        let mut w = AnimWriter::new(&mut webp, AnimFormat::Webp, 2, 2, 1, 1).unwrap();
+        w.add_frame(&rgba, 100).unwrap();
+        assert!(w.add_frame(&rgba[4..], 100).is_err());
+        w.finish().unwrap();
+        assert_eq!(&webp[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(webp[4..8].try_into().unwrap()) as usize, webp.len() - 8);
+        assert_eq!(&webp[8..16], b"WEBPVP8X");
+        let chunks: Vec<[u8; 4]> = riff_chunks(&webp[12..]).unwrap().into_iter().map(|(c, _)| c).collect();
+        assert_eq!(chunks, vec![*b"VP8X", *b"ANIM", *b"ANMF"]);
+
+        let mut apng = Vec::new();
+        let mut w = AnimWriter::new(&mut apng, AnimFormat::Apng, 2, 2, 0, 2).unwrap();
+        w.add_frame(&rgba, 100).unwrap();
+        w.add_frame(&rgba, 250).unwrap();
+        w.finish().unwrap();
+        let reader = png::Decoder::new(apng.as_slice()).read_info().unwrap();
+        let actl = reader.info().animation_control.unwrap();
+        assert_eq!((actl.num_frames, actl.num_plays), (2, 0));
+    }
+}
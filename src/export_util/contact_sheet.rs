@@ -0,0 +1,121 @@
+//! thumbnails of every page in a grid, for reviewing the whole book at a glance
+//!
+//! a sheet has a title line and columns x rows cells. a cell has the groups
+//! starting at the page above the thumbnail and the page number below it.
+//! pages are numbered in the same order as the exported images (0000.png, ..).
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::isv2_parameter::ContactSheetSetting;
+use crate::scenario_node::ScenarioNode;
+use super::pdf::outline;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SheetFormat { Png, Pdf }
+impl SheetFormat {
+    pub fn from_path(path: &Path) -> Option<Self>{
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "pdf" => Some(Self::Pdf),
+            _     => None,
+        }
+    }
+}
+
+// SheetLayout /////////////////////////////////////////
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetLayout{
+    pub columns : usize,
+    pub rows    : usize,
+    pub thumb_w : i32,
+    pub thumb_h : i32,
+    pub margin  : i32,
+    pub line_h  : i32, // height of a heading, a caption and the title
+    pub width   : i32, // size of a sheet
+    pub height  : i32,
+}
+impl SheetLayout{
+    pub fn new(s: &ContactSheetSetting, target_w: i32, target_h: i32) -> Self{
+        let columns = s.columns.max(1) as usize;
+        let rows    = s.rows.max(1) as usize;
+        let thumb_w = s.thumb_width.max(16);
+        let thumb_h = ((thumb_w as i64) * (target_h.max(1) as i64) / (target_w.max(1) as i64)).max(1) as i32;
+        let line_h  = (thumb_w / 14).max(14);
+        let margin  = line_h;
+        Self{
+            columns, rows, thumb_w, thumb_h, margin, line_h,
+            width  : margin + (columns as i32) * (thumb_w + margin),
+            height : margin + 2 * line_h + (rows as i32) * (line_h + thumb_h + line_h + margin),
+        }
+    }
+    pub fn per_sheet(&self) -> usize{ self.columns * self.rows }
+    pub fn sheets(&self, pages: usize) -> usize{ pages.div_ceil(self.per_sheet()) }
+    /// sheet and the top left of the thumbnail of the i-th page
+    pub fn thumb_pos(&self, i: usize) -> (usize, i32, i32){
+        let (sheet, cell) = (i / self.per_sheet(), i % self.per_sheet());
+        let (col, row)    = ((cell % self.columns) as i32, (cell / self.columns) as i32);
+        (sheet,
+         self.margin + col * (self.thumb_w + self.margin),
+         self.margin + 2 * self.line_h + row * (self.line_h + self.thumb_h + self.line_h + self.margin) + self.line_h)
+    }
+}
+
+// headings ////////////////////////////////////////////
+/// groups starting at each page, joined with " / "
+pub fn headings(root: &Rc<ScenarioNode>, pages: usize) -> Vec<String>{
+    let mut h = vec![String::new(); pages];
+    for e in outline(root, false) {
+        if let Some(s) = h.get_mut(e.page - 1) {
+            if !s.is_empty() { s.push_str(" / "); }
+            s.push_str(&e.title);
+        }
+    }
+    h
+}
+// sheet_path //////////////////////////////////////////
+/// png sheets are numbered like name_00.png when there are several
+pub fn sheet_path(path: &Path, sheet: usize, sheets: usize) -> PathBuf{
+    if sheets <= 1 {
+        return path.to_path_buf(); }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext  = path.extension().map(|s| s.to_string_lossy().to_string()).unwrap_or("png".to_string());
+    path.with_file_name(format!("{}_{:02}.{}", stem, sheet, ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import_util::script_import;
+
+    #[test]
+    fn test_layout() {
+        let l = SheetLayout::new(&ContactSheetSetting{ columns: 3, rows: 2, thumb_width: 280 }, 1920, 1080);
+        assert_eq!((l.thumb_w, l.thumb_h, l.line_h, l.margin), (280, 157, 20, 20));
+        assert_eq!((l.width, l.height), (20 + 3 * 300, 20 + 40 + 2 * (20 + 157 + 20 + 20)));
+        assert_eq!(l.sheets(6), 1);
+        assert_eq!(l.sheets(7), 2);
+        assert_eq!(l.thumb_pos(0), (0, 20, 80));
+        assert_eq!(l.thumb_pos(4), (0, 320, 80 + 217));
+        assert_eq!(l.thumb_pos(6), (1, 20, 80));
+
+        assert_eq!(sheet_path(Path::new("d/book.png"), 0, 1), Path::new("d/book.png"));
+        assert_eq!(sheet_path(Path::new("d/book.png"), 1, 3), Path::new("d/book_01.png"));
+    }
+
+    #[test]
+    fn test_headings() {
+        let script = "# part 1\n\
+                      ## part 1-1\n\
+                      @image\n\
+                      a1\n\
+                      \n\
+                      a2\n\
+                      # part 2\n\
+                      @image img/b.png\n\
+                      b1\n";
+        let root = script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+        assert_eq!(headings(&root, 3), vec!["part 1 / part 1-1", "", "part 2"]);
+    }
+}
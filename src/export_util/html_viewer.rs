@@ -0,0 +1,210 @@
+//! static click-through viewer of the rendered pages
+//!
+//! index.html has the css, the script and the page data inline, so that it
+//! runs from the local file system without a server. images are in images/.
+//! keys: right/space/pagedown next, left/backspace/pageup previous,
+//! home/end first/last, t text overlay. clicking the left third goes back.
+
+use std::path::Path;
+use std::rc::Rc;
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::scenario_node::ScenarioNode;
+use crate::translation_util::translation::xml_escape;
+use super::pdf::outline;
+
+pub const IMAGE_DIR: &str = "images";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ViewerPage{
+    pub image : String,      // path relative to index.html
+    pub title : String,
+    pub text  : Vec<String>, // mat texts without markup
+}
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Chapter{
+    title : String,
+    depth : usize,
+    page  : usize, // 0 origin
+}
+
+// image_name //////////////////////////////////////////
+pub fn image_name(i: usize) -> String{
+    format!("{}/{:04}.png", IMAGE_DIR, i)
+}
+// chapters ////////////////////////////////////////////
+/// groups with the depth for indenting the jump list
+fn chapters(root: &Rc<ScenarioNode>) -> Vec<Chapter>{
+    let entries = outline(root, false);
+    let mut depth: Vec<usize> = Vec::new();
+    entries.iter().map(|e| {
+        let d = e.parent.map(|p| depth[p] + 1).unwrap_or(0);
+        depth.push(d);
+        Chapter{ title: e.title.clone(), depth: d, page: e.page - 1 }
+    }).collect()
+}
+// script_json /////////////////////////////////////////
+/// json which can be put in a script element as it is
+fn script_json(v: &impl Serialize) -> String{
+    serde_json::to_string(v).unwrap_or_else(|_| "null".to_string())
+        .replace('<', "\\u003c").replace('>', "\\u003e").replace('&', "\\u0026")
+        .replace('\u{2028}', "\\u2028").replace('\u{2029}', "\\u2029")
+}
+
+const STYLE_CSS: &str = "html, body { margin: 0; height: 100%; background: #202020; color: #e0e0e0; font-family: sans-serif; }\n\
+    #bar { position: fixed; top: 0; left: 0; right: 0; height: 2.2em; display: flex; gap: 0.5em; align-items: center; \
+    padding: 0 0.5em; background: rgba(0, 0, 0, 0.6); z-index: 2; }\n\
+    #bar .grow { flex: 1; overflow: hidden; white-space: nowrap; text-overflow: ellipsis; }\n\
+    #view { position: absolute; top: 2.2em; left: 0; right: 0; bottom: 0; display: flex; align-items: center; \
+    justify-content: center; cursor: pointer; user-select: none; }\n\
+    #view img { max-width: 100%; max-height: 100%; object-fit: contain; }\n\
+    #text { position: fixed; left: 5%; right: 5%; bottom: 1em; padding: 0.5em 1em; background: rgba(0, 0, 0, 0.75); \
+    border-radius: 0.3em; white-space: pre-wrap; display: none; z-index: 2; }\n\
+    #text.on { display: block; }\n\
+    #text p { margin: 0.3em 0; }\n";
+
+const SCRIPT_JS: &str = "var cur = 0;\n\
+    function $(id) { return document.getElementById(id); }\n\
+    function show(i) {\n\
+    \x20 if (PAGES.length == 0) return;\n\
+    \x20 cur = Math.max(0, Math.min(PAGES.length - 1, i));\n\
+    \x20 var p = PAGES[cur];\n\
+    \x20 $('page').src = p.image;\n\
+    \x20 $('page').alt = p.title;\n\
+    \x20 $('title').textContent = p.title;\n\
+    \x20 $('num').textContent = (cur + 1) + ' / ' + PAGES.length;\n\
+    \x20 var t = $('text');\n\
+    \x20 t.textContent = '';\n\
+    \x20 p.text.forEach(function (s) { var e = document.createElement('p'); e.textContent = s; t.appendChild(e); });\n\
+    \x20 var c = -1;\n\
+    \x20 CHAPTERS.forEach(function (ch, k) { if (ch.page <= cur) c = k; });\n\
+    \x20 $('chapter').value = String(c);\n\
+    \x20 if (cur + 1 < PAGES.length) { new Image().src = PAGES[cur + 1].image; }\n\
+    \x20 history.replaceState(null, '', '#' + (cur + 1));\n\
+    }\n\
+    function toggleText() { $('text').classList.toggle('on'); $('toggle').classList.toggle('on'); }\n\
+    window.addEventListener('load', function () {\n\
+    \x20 var sel = $('chapter');\n\
+    \x20 if (CHAPTERS.length == 0) { sel.style.display = 'none'; }\n\
+    \x20 CHAPTERS.forEach(function (ch, k) {\n\
+    \x20   var o = document.createElement('option');\n\
+    \x20   o.value = String(k);\n\
+    \x20   o.textContent = '\\u00a0\\u00a0'.repeat(ch.depth) + ch.title;\n\
+    \x20   sel.appendChild(o);\n\
+    \x20 });\n\
+    \x20 sel.addEventListener('change', function () { var k = Number(sel.value); if (k >= 0) show(CHAPTERS[k].page); });\n\
+    \x20 $('prev').addEventListener('click', function () { show(cur - 1); });\n\
+    \x20 $('next').addEventListener('click', function () { show(cur + 1); });\n\
+    \x20 $('toggle').addEventListener('click', toggleText);\n\
+    \x20 $('view').addEventListener('click', function (e) {\n\
+    \x20   var r = this.getBoundingClientRect();\n\
+    \x20   show(e.clientX - r.left < r.width / 3 ? cur - 1 : cur + 1);\n\
+    \x20 });\n\
+    \x20 document.addEventListener('keydown', function (e) {\n\
+    \x20   if (e.target.tagName == 'SELECT' || e.altKey || e.ctrlKey || e.metaKey) return;\n\
+    \x20   switch (e.key) {\n\
+    \x20   case 'ArrowRight': case 'PageDown': case ' ': show(cur + 1); break;\n\
+    \x20   case 'ArrowLeft': case 'PageUp': case 'Backspace': show(cur - 1); break;\n\
+    \x20   case 'Home': show(0); break;\n\
+    \x20   case 'End': show(PAGES.length - 1); break;\n\
+    \x20   case 't': case 'T': toggleText(); break;\n\
+    \x20   default: return;\n\
+    \x20   }\n\
+    \x20   e.preventDefault();\n\
+    \x20 });\n\
+    \x20 if (TEXT_ON) toggleText();\n\
+    \x20 var h = parseInt(location.hash.substring(1), 10);\n\
+    \x20 show(isNaN(h) ? 0 : h - 1);\n\
+    });\n";
+
+// index_html //////////////////////////////////////////
+/// text_on shows the text overlay when the viewer is opened
+pub fn index_html(title: &str, lang: &str, root: &Rc<ScenarioNode>, pages: &[ViewerPage], text_on: bool) -> String{
+    format!("<!DOCTYPE html>\n\
+             <html lang=\"{lang}\">\n\
+             <head>\n\
+             <meta charset=\"UTF-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <meta name=\"generator\" content=\"{generator}\">\n\
+             <title>{title}</title>\n\
+             <style>\n{css}</style>\n\
+             </head>\n\
+             <body>\n\
+             <div id=\"bar\">\n\
+             <button id=\"prev\" title=\"previous (left)\">&lt;</button>\n\
+             <span id=\"num\"></span>\n\
+             <button id=\"next\" title=\"next (right)\">&gt;</button>\n\
+             <select id=\"chapter\"><option value=\"-1\">{title}</option></select>\n\
+             <span id=\"title\" class=\"grow\"></span>\n\
+             <button id=\"toggle\" title=\"text overlay (t)\">text</button>\n\
+             </div>\n\
+             <div id=\"view\"><img id=\"page\" src=\"\" alt=\"\"></div>\n\
+             <div id=\"text\"></div>\n\
+             <script>\n\
+             var PAGES = {pages};\n\
+             var CHAPTERS = {chapters};\n\
+             var TEXT_ON = {text_on};\n\
+             {js}</script>\n\
+             </body>\n\
+             </html>\n",
+            lang      = xml_escape(if lang.is_empty() { "und" } else { lang }),
+            generator = env!("CARGO_PKG_NAME"),
+            title     = xml_escape(title),
+            css       = STYLE_CSS,
+            pages     = script_json(&pages),
+            chapters  = script_json(&chapters(root)),
+            text_on   = text_on,
+            js        = SCRIPT_JS)
+}
+// write_viewer ////////////////////////////////////////
+/// writes index.html and the page images (png) into dir
+pub fn write_viewer(dir     : &Path,
+                    title   : &str,
+                    lang    : &str,
+                    root    : &Rc<ScenarioNode>,
+                    pages   : &[(ViewerPage, Vec<u8>)],
+                    text_on : bool) -> anyhow::Result<()>{
+    let image_dir = dir.join(IMAGE_DIR);
+    std::fs::create_dir_all(&image_dir).with_context(|| format!("can not create {}", image_dir.display()))?;
+    for (p, png) in pages {
+        let path = dir.join(&p.image);
+        std::fs::write(&path, png).with_context(|| format!("can not write {}", path.display()))?;
+    }
+    let pages: Vec<ViewerPage> = pages.iter().map(|(p, _)| p.clone()).collect();
+    let path = dir.join("index.html");
+    std::fs::write(&path, index_html(title, lang, root, &pages, text_on))
+        .with_context(|| format!("can not write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import_util::script_import;
+
+    #[test]
+    fn test_index_html() {
+        let script = "# part 1\n\
+                      ## part 1-1\n\
+                      @image\n\
+                      a1\n\
+                      # part 2\n\
+                      @image img/b.png\n\
+                      b1\n";
+        let root = script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+        let c = chapters(&root);
+        assert_eq!(c.iter().map(|c| (c.title.as_str(), c.depth, c.page)).collect::<Vec<_>>(),
+                   vec![("part 1", 0, 0), ("part 1-1", 1, 0), ("part 2", 0, 1)]);
+
+        let pages = vec![ViewerPage{ image: image_name(0), title: "a & b".to_string(),
+                                     text: vec!["</script><b>".to_string()] }];
+        let html = index_html("t<t>", "", &root, &pages, false);
+        assert!(html.contains("<title>t&lt;t&gt;</title>"));
+        assert!(html.contains("<html lang=\"und\">"));
+        assert!(html.contains("\"image\":\"images/0000.png\""));
+        assert!(html.contains("\"a \\u0026 b\""));
+        // the text can not close the script element
+        assert_eq!(html.matches("</script>").count(), 1);
+        assert!(html.contains("var TEXT_ON = false;"));
+    }
+}
@@ -0,0 +1,35 @@
+//! file format of the images written by export_images
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ImageFormat {
+    Png,
+    Svg { link_bg: bool }, // link_bg: refers to background images instead of embedding
+    Jpeg{ quality: u8 },   // 1-100
+    Webp{ quality: u8 },   // 1-100, needs the webp saver of gdk-pixbuf
+    Ora,                   // OpenRaster with a layer for each node, see openraster
+}
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str{
+        match self {
+            Self::Png      => "png",
+            Self::Svg{..}  => "svg",
+            Self::Jpeg{..} => "jpg",
+            Self::Webp{..} => "webp",
+            Self::Ora      => "ora",
+        }
+    }
+    /// pages without background can be transparent
+    pub fn has_alpha(&self) -> bool{ !matches!(self, Self::Jpeg{..}) }
+}
+
+// Content /////////////////////////////////////////////
+/// what is drawn on an exported page
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Content {
+    #[default]
+    Full,        // scene background, mats and texts
+    Transparent, // ovimgs, mats and texts on a transparent background
+    TextOnly,    // texts of mats on a transparent background
+}
@@ -0,0 +1,172 @@
+//! manifest of export_images for incremental export
+//!
+//! the manifest in the output dir maps every written file to a hash of the
+//! resolved inputs of its page. files with an unchanged hash are not rendered
+//! again, and files of the previous manifest not produced anymore are removed.
+//! a manifest written for another target (project, language or format) is ignored.
+
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::scenario_node::Item;
+use crate::scenario_node::ScenarioNode;
+
+pub const MANIFEST_NAME: &str = ".isv2_manifest.json";
+
+// StableHasher ////////////////////////////////////////
+/// FNV-1a 64. unlike DefaultHasher the value does not depend on the build.
+#[derive(Debug, Clone)]
+pub struct StableHasher(u64);
+impl Default for StableHasher {
+    fn default() -> Self { Self(0xcbf2_9ce4_8422_2325) }
+}
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = (self.0 ^ *b as u64).wrapping_mul(0x0100_0000_01b3); }
+    }
+    fn finish(&self) -> u64 { self.0 }
+}
+
+// InputHash ///////////////////////////////////////////
+#[derive(Debug, Clone, Default)]
+pub struct InputHash(StableHasher);
+impl InputHash {
+    pub fn value<T: Serialize + ?Sized>(&mut self, v: &T) {
+        self.0.write(serde_json::to_string(v).unwrap_or_default().as_bytes());
+        self.0.write(&[0]);
+    }
+    /// path, size and modification time of the file
+    pub fn file(&mut self, path: &Path) {
+        self.value(&(path.to_string_lossy(), file_stamp(path)));
+    }
+    pub fn finish(&self) -> String { format!("{:016x}", self.0.finish()) }
+}
+/// size and modification time of the file, None if it is not found
+pub fn file_stamp(path: &Path) -> Option<(u64, u128)>{
+    std::fs::metadata(path).ok().map(|m|{
+        let t = m.modified().ok().and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok()).unwrap_or_default();
+        (m.len(), t.as_nanos())
+    })
+}
+// input_hash //////////////////////////////////////////
+/// hash of a page: settings, the scene after label resolution with its image,
+/// and the mats and ovimgs of area (source and label-resolved node) with their images.
+/// an empty area gives the hash of the scene background only.
+pub fn input_hash(project_dir : &Path,
+                  scene       : &Rc<ScenarioNode>,
+                  area        : &[(Rc<ScenarioNode>, Option<Rc<ScenarioNode>>)],
+                  settings    : &impl Serialize) -> String{
+    let mut h = InputHash::default();
+    h.value(env!("CARGO_PKG_VERSION"));
+    h.value(settings);
+    let scene = ScenarioNode::search_def_label(scene.clone()).unwrap_or(scene.clone());
+    h.value(&*scene.value.borrow());
+    if let Some(b) = scene.get_scene_bgimg() {
+        h.file(&project_dir.join(b)); }
+    for (sn_source, sn_ref) in area {
+        h.value(&*sn_source.value.borrow());
+        if let Item::Ovimg(o) = &*sn_source.value.borrow() {
+            h.file(&project_dir.join(&o.path)); }
+        let sn = if let Some(r) = sn_ref { h.value(&*r.value.borrow()); r } else { sn_source };
+        if let (Some(true), Some(b)) = (sn.get_mat_bg_en(), sn.get_mat_bgimg()) {
+            h.file(&project_dir.join(b)); }
+    }
+    h.finish()
+}
+
+// target_key //////////////////////////////////////////
+/// identifies the export target of a manifest: the project file, the language and the file extension
+pub fn target_key(project_file: &Path, lang: &str, extension: &str) -> String{
+    format!("{}:{}:{}", project_file.to_string_lossy(), lang, extension)
+}
+
+// Manifest ////////////////////////////////////////////
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Manifest{
+    #[serde(default)]
+    pub target: String, // see target_key, empty in the manifests of older versions
+    pub files: BTreeMap<String, String>, // file name -> input hash
+}
+impl Manifest{
+    pub fn new(target: &str) -> Self{ Self{ target: target.to_string(), ..Self::default() } }
+    /// missing or broken manifest is empty, so that every page is rendered
+    pub fn load(dir: &Path) -> Self{
+        std::fs::read_to_string(dir.join(MANIFEST_NAME)).ok()
+            .and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+    }
+    /// the manifest of dir if it was written for target
+    pub fn load_for(dir: &Path, target: &str) -> Option<Self>{
+        Some(Self::load(dir)).filter(|m| !target.is_empty() && m.target == target)
+    }
+    pub fn save(&self, dir: &Path) -> anyhow::Result<()>{
+        std::fs::write(dir.join(MANIFEST_NAME), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+    /// the file was written from the same inputs
+    pub fn is_fresh(&self, dir: &Path, name: &str, hash: &str) -> bool{
+        self.files.get(name).is_some_and(|h| h == hash) && dir.join(name).is_file()
+    }
+    /// files of self not in next
+    pub fn stale<'a>(&'a self, next: &Manifest) -> Vec<&'a str>{
+        self.files.keys().filter(|k| !next.files.contains_key(*k)).map(|k| k.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import_util::script_import;
+
+    #[test]
+    fn test_input_hash() {
+        let script = "@image img/a.png\n\
+                      a1\n";
+        let root  = script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+        let mat   = root.child.borrow().clone().unwrap(); // pmat
+        let area  = vec![(mat.clone(), None)];
+        let dir   = Path::new(".");
+        let h0 = input_hash(dir, &root, &area, &(640, 480));
+        assert_eq!(h0, input_hash(dir, &root, &area, &(640, 480)));
+        assert_ne!(h0, input_hash(dir, &root, &area, &(320, 240)));
+        assert_ne!(h0, input_hash(dir, &root, &[], &(640, 480)));
+        mat.set_mat_text("a2");
+        assert_ne!(h0, input_hash(dir, &root, &area, &(640, 480)));
+
+        let mut h = StableHasher::default();
+        h.write(b"a");
+        assert_eq!(h.finish(), 0xaf63dc4c8601ec8c); // FNV-1a 64 of "a"
+    }
+
+    #[test]
+    fn test_manifest() {
+        let dir = std::env::temp_dir().join(format!("isv2_manifest_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target  = target_key(Path::new("/p/a.json"), "en", "png");
+        let mut old = Manifest::new(&target);
+        old.files.insert("0000.png".to_string(), "1".to_string());
+        old.files.insert("0001.png".to_string(), "2".to_string());
+        old.save(&dir).unwrap();
+        std::fs::write(dir.join("0000.png"), b"").unwrap();
+
+        let old = Manifest::load(&dir);
+        assert!(old.is_fresh(&dir, "0000.png", "1"));
+        assert!(!old.is_fresh(&dir, "0000.png", "3"));
+        assert!(!old.is_fresh(&dir, "0001.png", "2")); // removed by hand
+        let mut next = Manifest::default();
+        next.files.insert("0000.png".to_string(), "1".to_string());
+        assert_eq!(old.stale(&next), vec!["0001.png"]);
+        assert_eq!(Manifest::load_for(&dir, &target), Some(old.clone()));
+        assert_eq!(Manifest::load_for(&dir, &target_key(Path::new("/p/a.json"), "ja", "png")), None);
+        assert_eq!(Manifest::load_for(&dir, ""), None);
+
+        std::fs::write(dir.join(MANIFEST_NAME), b"{").unwrap();
+        assert_eq!(Manifest::load(&dir), Manifest::default());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
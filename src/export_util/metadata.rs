@@ -0,0 +1,244 @@
+//! manifest of the exported files for downstream tools
+//!
+//! lists each output file with the node which produced it: ids, page name,
+//! groups, scene, labels, mat texts, speakers and background image.
+//! the format is described by doc/export_manifest.schema.json.
+//! unlike manifest::Manifest this is not read back by isv2, except for
+//! merging the entries of a partial export.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::scenario_node::Item;
+use crate::scenario_node::LabelType;
+use crate::scenario_node::ScenarioNode;
+use super::transcript::child_mats;
+use super::transcript::group_names;
+use super::transcript::strip_markup;
+
+pub const METADATA_NAME : &str = "isv2_export.json";
+pub const SCHEMA        : &str = "isv2-export-manifest";
+pub const VERSION       : u32  = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabelMeta{
+    pub kind : String, // def, ref or ref_no_rect
+    pub name : String,
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneMeta{
+    pub node_id    : i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label      : Option<LabelMeta>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background : Option<String>,
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatMeta{
+    pub node_id : i32,
+    pub text    : String,
+    pub markup  : String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker : Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label   : Option<LabelMeta>,
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageMeta{
+    pub seq      : usize,
+    pub node_id  : i32,
+    pub kind     : String, // page or pmat
+    pub name     : String,
+    pub groups   : Vec<String>,
+    pub scene    : SceneMeta,
+    pub mats     : Vec<MatMeta>,
+    pub overlays : Vec<String>,
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileMeta{
+    pub file         : String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_in_file : Option<usize>, // 1 origin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_frame  : Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_count  : Option<u64>,
+    pub page         : PageMeta,
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportMetadata{
+    pub schema    : String,
+    pub version   : u32,
+    pub generator : String,
+    pub project   : String,
+    pub language  : String,
+    pub width     : i32,
+    pub height    : i32,
+    pub files     : Vec<FileMeta>,
+}
+
+// label_meta //////////////////////////////////////////
+fn label_meta(sn: &Rc<ScenarioNode>) -> Option<LabelMeta>{
+    let kind = match sn.get_label_type()? {
+        LabelType::None      => return None,
+        LabelType::Def       => "def",
+        LabelType::Ref       => "ref",
+        LabelType::RefNoRect => "ref_no_rect",
+    };
+    Some(LabelMeta{ kind: kind.to_string(), name: sn.get_label().unwrap_or_default() })
+}
+fn mat_meta(sn: &Rc<ScenarioNode>, text_key: &str) -> MatMeta{
+    let markup = sn.get_mat_text_lang(text_key).unwrap_or_default();
+    MatMeta{
+        node_id : sn.id.get(),
+        text    : strip_markup(&markup),
+        markup,
+        speaker : sn.get_mat_speaker().filter(|s| !s.is_empty()),
+        label   : label_meta(sn),
+    }
+}
+/// path with '/' as separator
+fn slash_path(p: &Path) -> String{
+    p.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect::<Vec<_>>().join("/")
+}
+// page_meta ///////////////////////////////////////////
+/// metadata of a page or pmat node, seq is the number of the page in the project
+pub fn page_meta(sn: &Rc<ScenarioNode>, seq: usize, text_key: &str) -> PageMeta{
+    let (kind, name) = match &*sn.value.borrow() {
+        Item::Page(p) => ("page", p.name.clone()),
+        Item::Pmat(m) => ("pmat", m.name.clone()),
+        _             => ("", String::new()),
+    };
+    let scene_node = ScenarioNode::get_belong_scene(sn);
+    let scene = match &scene_node {
+        Some(s) => {
+            let resolved = ScenarioNode::search_def_label(s.clone()).unwrap_or(s.clone());
+            SceneMeta{
+                node_id    : s.id.get(),
+                label      : label_meta(s),
+                background : resolved.get_scene_bgimg().map(|b| slash_path(&b)) }
+        },
+        None => SceneMeta{ node_id: -1, label: None, background: None },
+    };
+    let mut mats: Vec<MatMeta> = scene_node.iter().flat_map(child_mats).map(|m| mat_meta(&m, text_key)).collect();
+    if sn.is_pmat() {
+        mats.push(mat_meta(sn, text_key)); }
+    else {
+        mats.extend(child_mats(sn).iter().map(|m| mat_meta(m, text_key))); }
+    let mut overlays = Vec::new();
+    let mut p = if sn.is_page() { sn.child.borrow().clone() } else { None };
+    while let Some(n) = p {
+        if let Item::Ovimg(o) = &*n.value.borrow() {
+            overlays.push(o.path.clone()); }
+        p = n.neighbor.borrow().clone();
+    }
+    PageMeta{
+        seq,
+        node_id : sn.id.get(),
+        kind    : kind.to_string(),
+        name,
+        groups  : ScenarioNode::get_container(sn).map(|c| group_names(&c)).unwrap_or_default(),
+        scene,
+        mats,
+        overlays,
+    }
+}
+
+impl ExportMetadata{
+    pub fn new(project: &str, language: &str, width: i32, height: i32) -> Self{
+        Self{
+            schema    : SCHEMA.to_string(),
+            version   : VERSION,
+            generator : format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            project   : project.to_string(),
+            language  : language.to_string(),
+            width, height,
+            files     : Vec::new(),
+        }
+    }
+    /// adds a file of a page, page_in_file is 1 origin
+    pub fn push(&mut self, file: &str, page_in_file: Option<usize>, page: PageMeta){
+        self.files.push(FileMeta{ file: file.to_string(), page_in_file, first_frame: None, frame_count: None, page });
+    }
+    /// entries of files in old which are not in self, for an export of a part of the pages
+    pub fn merge_kept(&mut self, old: &ExportMetadata){
+        let kept: Vec<FileMeta> = old.files.iter()
+            .filter(|f| !self.files.iter().any(|n| n.file == f.file)).cloned().collect();
+        self.files.extend(kept);
+        self.files.sort_by_key(|f| (f.page.seq, f.page_in_file));
+    }
+    /// None when the file does not exist or is of another version
+    pub fn load(path: &Path) -> Option<Self>{
+        let m: Self = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+        (m.schema == SCHEMA && m.version == VERSION).then_some(m)
+    }
+    pub fn save(&self, path: &Path) -> anyhow::Result<()>{
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("can not write {}", path.display()))
+    }
+}
+// sidecar_path ////////////////////////////////////////
+/// metadata of a single file export, like book.pdf.meta.json
+pub fn sidecar_path(path: &Path) -> PathBuf{
+    let name = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+    path.with_file_name(format!("{}.meta.json", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import_util::script_import;
+
+    #[test]
+    fn test_page_meta() {
+        let script = "# part 1\n\
+                      ## part 1-1\n\
+                      @image\n\
+                      <b>a1</b>\n\
+                      --\n\
+                      a2\n";
+        let root = script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+        let mut vec = vec![root.clone()];
+        let page = std::iter::from_fn(|| ScenarioNode::traverse(&mut vec)).find(|n| n.is_page()).unwrap();
+        let mat = page.child.borrow().clone().unwrap();
+        mat.set_mat_speaker(Some("Eileen".to_string()));
+        mat.set_label_type(LabelType::Def);
+        mat.set_label(Some("l1".to_string()));
+        ScenarioNode::get_belong_scene(&page).unwrap().set_scene_bgimg(Some(PathBuf::from("img").join("a.png")));
+
+        let m = page_meta(&page, 3, "");
+        assert_eq!((m.seq, m.kind.as_str()), (3, "page"));
+        assert_eq!(m.groups, vec!["part 1", "part 1-1"]);
+        assert_eq!(m.scene.background.as_deref(), Some("img/a.png"));
+        assert_eq!(m.mats.len(), 2);
+        assert_eq!((m.mats[0].text.as_str(), m.mats[0].markup.as_str()), ("a1", "<b>a1</b>"));
+        assert_eq!(m.mats[0].speaker.as_deref(), Some("Eileen"));
+        assert_eq!(m.mats[0].label, Some(LabelMeta{ kind: "def".to_string(), name: "l1".to_string() }));
+        assert_eq!(m.mats[1].speaker, None);
+
+        // fields required by the schema
+        let schema: serde_json::Value =
+            serde_json::from_str(include_str!("../../doc/export_manifest.schema.json")).unwrap();
+        let mut meta = ExportMetadata::new("book", "", 640, 360);
+        meta.push("0003.png", None, m);
+        let json = serde_json::to_value(&meta).unwrap();
+        let required = |v: &serde_json::Value| -> Vec<String> {
+            v["required"].as_array().unwrap().iter().map(|r| r.as_str().unwrap().to_string()).collect() };
+        for (obj, def) in [(&json, &schema),
+                           (&json["files"][0], &schema["$defs"]["file"]),
+                           (&json["files"][0]["page"], &schema["$defs"]["page"]),
+                           (&json["files"][0]["page"]["scene"], &schema["$defs"]["scene"]),
+                           (&json["files"][0]["page"]["mats"][0], &schema["$defs"]["mat"])] {
+            for r in required(def) {
+                assert!(obj.get(&r).is_some(), "{} is missing", r); }
+            for k in obj.as_object().unwrap().keys() {
+                assert!(def["properties"].get(k).is_some(), "{} is not in the schema", k); }
+        }
+        assert_eq!(json["schema"], schema["properties"]["schema"]["const"]);
+
+        assert_eq!(sidecar_path(Path::new("d/book.pdf")), Path::new("d/book.pdf.meta.json"));
+    }
+}
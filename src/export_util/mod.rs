@@ -0,0 +1,17 @@
+pub mod image_format;
+pub mod naming;
+pub mod transcript;
+pub mod pdf;
+pub mod package;
+pub mod html_viewer;
+pub mod renpy;
+pub mod contact_sheet;
+pub mod animation;
+pub mod video;
+pub mod manifest;
+pub mod metadata;
+pub mod subset;
+pub mod profile;
+pub mod stamp;
+pub mod preflight;
+pub mod openraster;
@@ -0,0 +1,157 @@
+//! file names of exported images from a template like "{seq}_{scene}_{page}"
+//!
+//! {seq} is the sequence number (4 digits, or N digits with {seq:N}), {group} the
+//! nearest group, {scene} the label of the scene (or its image name), {page} the
+//! name of the page or pmat and {id} the node id, which survives inserting pages.
+
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::scenario_node::ScenarioNode;
+use super::transcript::group_names;
+
+pub const DEFAULT_TEMPLATE: &str = "{seq}";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Part{
+    Text(String),
+    Seq(usize), // digits
+    Group,
+    Scene,
+    Page,
+    Id,
+}
+// parse ///////////////////////////////////////////////
+/// None for unbalanced braces or unknown placeholders
+pub fn parse(template: &str) -> Option<Vec<Part>>{
+    let mut parts = Vec::new();
+    let mut rest  = template;
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('{') {
+            let end = r.find('}')?;
+            parts.push(match &r[..end] {
+                "seq"   => Part::Seq(4),
+                "group" => Part::Group,
+                "scene" => Part::Scene,
+                "page"  => Part::Page,
+                "id"    => Part::Id,
+                p       => Part::Seq(p.strip_prefix("seq:")?.parse::<usize>().ok().filter(|n| (1..=9).contains(n))?),
+            });
+            rest = &r[end + 1..];
+        } else {
+            let end = rest.find(['{', '}']).unwrap_or(rest.len());
+            if end == 0 {
+                return None; } // '}' without '{'
+            parts.push(Part::Text(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+    }
+    Some(parts)
+}
+
+// NameContext /////////////////////////////////////////
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NameContext{
+    pub seq   : usize,
+    pub group : String,
+    pub scene : String,
+    pub page  : String,
+    pub id    : i32,
+}
+impl NameContext{
+    /// context of a page or pmat node
+    pub fn of(sn: &Rc<ScenarioNode>, seq: usize) -> Self{
+        let scene = ScenarioNode::get_belong_scene(sn).map(|s|{
+            s.get_label().filter(|l| !l.is_empty()).unwrap_or_else(||{
+                s.get_scene_bgimg().and_then(|b| b.file_stem().map(|f| f.to_string_lossy().to_string())).unwrap_or_default() })
+        }).unwrap_or_default();
+        let group = ScenarioNode::get_container(sn)
+            .and_then(|c| group_names(&c).pop()).unwrap_or_default();
+        let page = sn.get_page_name().unwrap_or_default();
+        Self{ seq, group, scene, page, id: sn.id.get() }
+    }
+}
+// sanitize ////////////////////////////////////////////
+/// replaces characters which can not be used in file names
+fn sanitize(s: &str) -> String{
+    let s: String = s.chars().take(64)
+        .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+        .collect();
+    s.trim().to_string()
+}
+// render //////////////////////////////////////////////
+/// file name without extension. an invalid template falls back to the default.
+pub fn render(template: &str, ctx: &NameContext) -> String{
+    let parts = parse(template).unwrap_or_else(|| parse(DEFAULT_TEMPLATE).unwrap());
+    let name: String = parts.iter().map(|p|{
+        match p {
+            Part::Text(t) => sanitize(t),
+            Part::Seq(n)  => format!("{:0n$}", ctx.seq, n = n),
+            Part::Group   => sanitize(&ctx.group),
+            Part::Scene   => sanitize(&ctx.scene),
+            Part::Page    => sanitize(&ctx.page),
+            Part::Id      => ctx.id.to_string(),
+        }
+    }).collect();
+    let name = name.trim_start_matches('.').to_string();
+    if name.is_empty() { format!("{:04}", ctx.seq) } else { name }
+}
+// UniqueNames /////////////////////////////////////////
+/// appends _2, _3, .. to names already used
+#[derive(Debug, Default)]
+pub struct UniqueNames(HashSet<String>);
+impl UniqueNames{
+    pub fn unique(&mut self, name: String) -> String{
+        let mut n = 1;
+        let mut candidate = name.clone();
+        while self.0.contains(&candidate.to_lowercase()) {
+            n += 1;
+            candidate = format!("{}_{}", name, n);
+        }
+        self.0.insert(candidate.to_lowercase());
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use crate::import_util::script_import;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("p{seq:3}-{id}"), Some(vec![Part::Text("p".to_string()), Part::Seq(3), Part::Text("-".to_string()), Part::Id]));
+        assert_eq!(parse("{seq"), None);
+        assert_eq!(parse("seq}"), None);
+        assert_eq!(parse("{name}"), None);
+        assert_eq!(parse("{seq:0}"), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let script = "# part 1\n\
+                      @image img/a.png\n\
+                      a1\n\
+                      \n\
+                      a2\n";
+        let root = script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+        let scene = root.child.borrow().clone().unwrap();
+        let page  = scene.child.borrow().clone().unwrap();
+        page.id.set(42);
+        let ctx = NameContext::of(&page, 7);
+        assert_eq!((ctx.group.as_str(), ctx.scene.as_str(), ctx.id), ("part 1", "a", 42));
+        assert_eq!(render("{seq}_{group}_{scene}_{id}", &ctx), "0007_part 1_a_42");
+        assert_eq!(render("{seq:2}", &ctx), "07");
+
+        let ctx = NameContext{ seq: 3, page: "a/b: c".to_string(), ..Default::default() };
+        assert_eq!(render("{page}", &ctx), "a_b_ c");
+        assert_eq!(render("{seq", &ctx), "0003");  // invalid template
+        assert_eq!(render("{group}", &ctx), "0003"); // empty name
+
+        let mut names = UniqueNames::default();
+        assert_eq!(names.unique("a".to_string()), "a");
+        assert_eq!(names.unique("A".to_string()), "A_2");
+        assert_eq!(names.unique("a".to_string()), "a_3");
+    }
+}
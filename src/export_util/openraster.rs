@@ -0,0 +1,79 @@
+//! layered page for painting applications (Krita, GIMP, MyPaint)
+//!
+//! an OpenRaster file is a zip of stack.xml and a png for each layer.
+//! the layers of a page are the scene background, each ovimg (transparent
+//! content only, as in the flat export), and the box and the text of each
+//! mat, named after the node as in the scenario tree.
+
+use crate::translation_util::translation::xml_escape;
+use super::package::ZipWriter;
+use super::transcript::strip_markup;
+
+pub const MIME_TYPE: &str = "image/openraster";
+/// the longer side of Thumbnails/thumbnail.png
+pub const THUMBNAIL_SIZE: i32 = 256;
+
+// OraLayer ////////////////////////////////////////////
+pub struct OraLayer{
+    pub name    : String,
+    pub png     : Vec<u8>, // page size
+    pub opacity : f64,
+}
+
+// stack_xml ///////////////////////////////////////////
+/// layers are given from the bottom, stack.xml lists them from the top
+pub fn stack_xml(w: i32, h: i32, layers: &[OraLayer]) -> String{
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml += &format!("<image version=\"0.0.5\" w=\"{}\" h=\"{}\">\n  <stack>\n", w, h);
+    for (i, l) in layers.iter().enumerate().rev() {
+        xml += &format!("    <layer name=\"{}\" src=\"data/{:03}.png\" x=\"0\" y=\"0\" opacity=\"{:.3}\" visibility=\"visible\"/>\n",
+                        xml_escape(&l.name), i, l.opacity.clamp(0.0, 1.0));
+    }
+    xml += "  </stack>\n</image>\n";
+    xml
+}
+// ora /////////////////////////////////////////////////
+/// merged: the flattened page, thumbnail: the page within THUMBNAIL_SIZE
+pub fn ora(w: i32, h: i32, layers: &[OraLayer], merged: &[u8], thumbnail: &[u8]) -> Vec<u8>{
+    let mut zip = ZipWriter::new();
+    zip.add("mimetype", MIME_TYPE.as_bytes()); // must be the first entry
+    zip.add("stack.xml", stack_xml(w, h, layers).as_bytes());
+    for (i, l) in layers.iter().enumerate() {
+        zip.add(&format!("data/{:03}.png", i), &l.png); }
+    zip.add("Thumbnails/thumbnail.png", thumbnail);
+    zip.add("mergedimage.png", merged);
+    zip.finish()
+}
+// layer_name //////////////////////////////////////////
+/// one line of the node summary without markup, not too long for the layer panel
+pub fn layer_name(summary: &str, part: &str) -> String{
+    let mut name: String = strip_markup(summary).lines().next().unwrap_or_default().chars().take(40).collect();
+    if !part.is_empty() {
+        name += &format!(" ({})", part); }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ora() {
+        let layers = vec![
+            OraLayer{ name: layer_name("Scene:bg.png", ""),        png: vec![1], opacity: 1.0 },
+            OraLayer{ name: layer_name("Mat:<b>\"hi\"</b>", "text"), png: vec![2], opacity: 0.5 },
+        ];
+        let xml = stack_xml(640, 480, &layers);
+        assert!(xml.contains("<image version=\"0.0.5\" w=\"640\" h=\"480\">"));
+        // the top layer comes first
+        let (top, bottom) = (xml.find("data/001.png").unwrap(), xml.find("data/000.png").unwrap());
+        assert!(top < bottom);
+        assert!(xml.contains("name=\"Mat:&quot;hi&quot; (text)\""));
+        assert!(xml.contains("opacity=\"0.500\""));
+        assert_eq!(layer_name(&"x".repeat(50), "box").len(), 46);
+
+        let data = ora(640, 480, &layers, &[3], &[4]);
+        assert_eq!(&data[30..38], b"mimetype");
+        assert_eq!(&data[38..54], MIME_TYPE.as_bytes());
+    }
+}
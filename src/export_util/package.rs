@@ -0,0 +1,369 @@
+//! packs rendered pages into a comic archive (CBZ) or a fixed-layout EPUB 3
+//!
+//! both are zip files written with the stored method.
+//! mat texts of every page are put into the EPUB as hidden accessible text.
+
+use std::path::Path;
+use std::rc::Rc;
+use std::time::SystemTime;
+use anyhow::Context;
+use std::time::UNIX_EPOCH;
+
+use crate::scenario_node::ScenarioNode;
+use crate::translation_util::translation::xml_escape;
+use super::pdf::outline;
+use super::pdf::OutlineEntry;
+use super::transcript::child_mats;
+use super::transcript::strip_markup;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackageFormat { Cbz, Epub }
+impl PackageFormat {
+    pub fn from_path(path: &Path) -> Option<Self>{
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "cbz"  => Some(Self::Cbz),
+            "epub" => Some(Self::Epub),
+            _      => None,
+        }
+    }
+}
+
+pub struct PackageInfo{
+    pub title    : String,
+    pub lang     : String,
+    pub width    : i32,
+    pub height   : i32,
+    pub modified : String, // dcterms:modified of EPUB
+}
+pub struct PackagePage{
+    pub png  : Vec<u8>,
+    pub text : Vec<String>, // mat texts without markup
+}
+
+// crc32 ///////////////////////////////////////////////
+pub(super) fn crc32(data: &[u8]) -> u32{
+    let mut crc = 0xffff_ffffu32;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+// ZipWriter ///////////////////////////////////////////
+/// stored (not compressed) zip. time stamps are fixed to keep the output stable.
+pub(super) struct ZipWriter{
+    buf     : Vec<u8>,
+    central : Vec<u8>,
+    count   : u16,
+}
+impl ZipWriter{
+    const DOS_TIME : u16 = 0;
+    const DOS_DATE : u16 = (1 << 5) | 1; // 1980-01-01
+    pub(super) fn new() -> Self{ Self{ buf: Vec::new(), central: Vec::new(), count: 0 } }
+    pub(super) fn add(&mut self, name: &str, data: &[u8]){
+        let crc    = crc32(data);
+        let offset = self.buf.len() as u32;
+        let name_b = name.as_bytes();
+        // local file header
+        self.buf.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        for v in [20u16, 0x0800, 0, Self::DOS_TIME, Self::DOS_DATE] {
+            self.buf.extend_from_slice(&v.to_le_bytes()); }
+        for v in [crc, data.len() as u32, data.len() as u32] {
+            self.buf.extend_from_slice(&v.to_le_bytes()); }
+        for v in [name_b.len() as u16, 0] {
+            self.buf.extend_from_slice(&v.to_le_bytes()); }
+        self.buf.extend_from_slice(name_b);
+        self.buf.extend_from_slice(data);
+        // central directory header
+        self.central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        for v in [20u16, 20, 0x0800, 0, Self::DOS_TIME, Self::DOS_DATE] {
+            self.central.extend_from_slice(&v.to_le_bytes()); }
+        for v in [crc, data.len() as u32, data.len() as u32] {
+            self.central.extend_from_slice(&v.to_le_bytes()); }
+        for v in [name_b.len() as u16, 0, 0, 0, 0] {
+            self.central.extend_from_slice(&v.to_le_bytes()); }
+        for v in [0u32, offset] {
+            self.central.extend_from_slice(&v.to_le_bytes()); }
+        self.central.extend_from_slice(name_b);
+        self.count += 1;
+    }
+    pub(super) fn finish(mut self) -> Vec<u8>{
+        let offset = self.buf.len() as u32;
+        let size   = self.central.len() as u32;
+        self.buf.append(&mut self.central);
+        self.buf.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        for v in [0u16, 0, self.count, self.count] {
+            self.buf.extend_from_slice(&v.to_le_bytes()); }
+        for v in [size, offset] {
+            self.buf.extend_from_slice(&v.to_le_bytes()); }
+        self.buf.extend_from_slice(&0u16.to_le_bytes());
+        self.buf
+    }
+}
+
+// page_text ///////////////////////////////////////////
+/// texts shown on a page or pmat node, scene mats first
+pub fn page_text(sn: &Rc<ScenarioNode>, text_key: &str) -> Vec<String>{
+    let mut mats = Vec::new();
+    if let Some(scene) = ScenarioNode::get_belong_scene(sn) {
+        mats.append(&mut child_mats(&scene)); }
+    if sn.is_pmat() {
+        mats.push(sn.clone()); }
+    else {
+        mats.append(&mut child_mats(sn)); }
+    mats.iter()
+        .filter_map(|m| {
+            let text = strip_markup(&m.get_mat_text_lang(text_key)?);
+            if text.trim().is_empty() { return None; }
+            Some(match m.get_mat_speaker() {
+                Some(s) => format!("{s}: {text}"),
+                None    => text,
+            })
+        })
+        .collect()
+}
+// iso8601 /////////////////////////////////////////////
+/// UTC date time like 2024-01-31T12:34:56Z
+pub fn iso8601(t: SystemTime) -> String{
+    let secs = t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) as i64;
+    let (days, rem) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    // civil from days
+    let z   = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp  = (5 * doy + 2) / 153;
+    let d   = doy - (153 * mp + 2) / 5 + 1;
+    let m   = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y   = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", y, m, d, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+fn lang_or_und(lang: &str) -> &str{
+    if lang.is_empty() { "und" } else { lang }
+}
+
+// cbz /////////////////////////////////////////////////
+pub fn comic_info(info: &PackageInfo, pages: &[PackagePage], groups: &[OutlineEntry]) -> String{
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+                                <ComicInfo xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" \
+                                xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\n");
+    xml += &format!("  <Title>{}</Title>\n", xml_escape(&info.title));
+    xml += &format!("  <PageCount>{}</PageCount>\n", pages.len());
+    if !info.lang.is_empty() {
+        xml += &format!("  <LanguageISO>{}</LanguageISO>\n", xml_escape(&info.lang)); }
+    xml += "  <Pages>\n";
+    for (i, _) in pages.iter().enumerate() {
+        let bookmark: Vec<&str> = groups.iter().filter(|e| e.page == i + 1).map(|e| e.title.as_str()).collect();
+        if bookmark.is_empty() {
+            xml += &format!("    <Page Image=\"{}\" ImageWidth=\"{}\" ImageHeight=\"{}\"/>\n",
+                            i, info.width, info.height);
+        } else {
+            xml += &format!("    <Page Image=\"{}\" ImageWidth=\"{}\" ImageHeight=\"{}\" Bookmark=\"{}\"/>\n",
+                            i, info.width, info.height, xml_escape(&bookmark.join(" / ")));
+        }
+    }
+    xml += "  </Pages>\n</ComicInfo>\n";
+    xml
+}
+pub fn cbz(info: &PackageInfo, root: &Rc<ScenarioNode>, pages: &[PackagePage]) -> Vec<u8>{
+    let mut zip = ZipWriter::new();
+    for (i, p) in pages.iter().enumerate() {
+        zip.add(&format!("{:04}.png", i), &p.png); }
+    zip.add("ComicInfo.xml", comic_info(info, pages, &outline(root, false)).as_bytes());
+    zip.finish()
+}
+
+// epub ////////////////////////////////////////////////
+const CONTAINER_XML: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+    <container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n\
+    \x20 <rootfiles>\n\
+    \x20   <rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>\n\
+    \x20 </rootfiles>\n\
+    </container>\n";
+// text is visually hidden but left for screen readers
+const STYLE_CSS: &str = "html, body { margin: 0; padding: 0; }\n\
+    .page { position: relative; }\n\
+    .page img { display: block; width: 100%; height: 100%; }\n\
+    .text { position: absolute; width: 1px; height: 1px; overflow: hidden; clip: rect(0 0 0 0); white-space: nowrap; }\n";
+
+fn epub_page(info: &PackageInfo, i: usize, page: &PackagePage) -> String{
+    let mut x = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                         <!DOCTYPE html>\n\
+                         <html xmlns=\"http://www.w3.org/1999/xhtml\" xml:lang=\"{lang}\" lang=\"{lang}\">\n\
+                         <head>\n\
+                         <meta charset=\"UTF-8\"/>\n\
+                         <meta name=\"viewport\" content=\"width={w}, height={h}\"/>\n\
+                         <title>{title} {i:04}</title>\n\
+                         <link rel=\"stylesheet\" type=\"text/css\" href=\"../style.css\"/>\n\
+                         </head>\n\
+                         <body>\n\
+                         <div class=\"page\" style=\"width: {w}px; height: {h}px;\">\n\
+                         <img src=\"../images/{i:04}.png\" alt=\"\"/>\n",
+                        lang  = xml_escape(lang_or_und(&info.lang)),
+                        w     = info.width,
+                        h     = info.height,
+                        title = xml_escape(&info.title));
+    if !page.text.is_empty() {
+        x += "<div class=\"text\">\n";
+        for t in &page.text {
+            x += &format!("<p>{}</p>\n", xml_escape(t).replace('\n', "<br/>")); }
+        x += "</div>\n";
+    }
+    x += "</div>\n</body>\n</html>\n";
+    x
+}
+pub fn nav(info: &PackageInfo, groups: &[OutlineEntry]) -> String{
+    let mut x = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                         <!DOCTYPE html>\n\
+                         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" \
+                         xml:lang=\"{lang}\" lang=\"{lang}\">\n\
+                         <head><meta charset=\"UTF-8\"/><title>{title}</title></head>\n\
+                         <body>\n\
+                         <nav epub:type=\"toc\" id=\"toc\">\n\
+                         <h1>{title}</h1>\n",
+                        lang  = xml_escape(lang_or_und(&info.lang)),
+                        title = xml_escape(&info.title));
+    let link = |e: &OutlineEntry| format!("<a href=\"pages/{:04}.xhtml\">{}</a>", e.page - 1, xml_escape(&e.title));
+    if groups.is_empty() {
+        x += &format!("<ol>\n<li>{}</li>\n</ol>\n",
+                      link(&OutlineEntry{ parent: None, title: info.title.clone(), page: 1 }));
+    } else {
+        // nested lists, entries are in tree order so a first child follows its parent
+        let mut depth = Vec::new();
+        for (i, e) in groups.iter().enumerate() {
+            let d = e.parent.map(|p| depth[p] + 1).unwrap_or(0);
+            if i == 0 {
+                x += "<ol>\n"; }
+            else if d > depth[i - 1] {
+                x += "\n<ol>\n"; }
+            else {
+                x += "</li>\n";
+                x += &"</ol>\n</li>\n".repeat(depth[i - 1] - d);
+            }
+            x += &format!("<li>{}", link(e));
+            depth.push(d);
+        }
+        x += "</li>\n";
+        x += &"</ol>\n</li>\n".repeat(*depth.last().unwrap());
+        x += "</ol>\n";
+    }
+    x += "</nav>\n</body>\n</html>\n";
+    x
+}
+pub fn opf(info: &PackageInfo, pages: &[PackagePage]) -> String{
+    let mut x = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"uid\" \
+                         xml:lang=\"{lang}\" prefix=\"rendition: http://www.idpf.org/vocab/rendition/#\">\n\
+                         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+                         <dc:identifier id=\"uid\">urn:isv2:{uid:08x}</dc:identifier>\n\
+                         <dc:title>{title}</dc:title>\n\
+                         <dc:language>{lang}</dc:language>\n\
+                         <meta property=\"dcterms:modified\">{modified}</meta>\n\
+                         <meta property=\"rendition:layout\">pre-paginated</meta>\n\
+                         <meta property=\"rendition:orientation\">auto</meta>\n\
+                         <meta property=\"rendition:spread\">none</meta>\n\
+                         </metadata>\n\
+                         <manifest>\n\
+                         <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+                         <item id=\"css\" href=\"style.css\" media-type=\"text/css\"/>\n",
+                        lang     = xml_escape(lang_or_und(&info.lang)),
+                        uid      = crc32(format!("{}/{}", info.title, info.lang).as_bytes()),
+                        title    = xml_escape(&info.title),
+                        modified = xml_escape(&info.modified));
+    for i in 0..pages.len() {
+        let cover = if i == 0 { " properties=\"cover-image\"" } else { "" };
+        x += &format!("<item id=\"img{i:04}\" href=\"images/{i:04}.png\" media-type=\"image/png\"{cover}/>\n\
+                       <item id=\"p{i:04}\" href=\"pages/{i:04}.xhtml\" media-type=\"application/xhtml+xml\"/>\n");
+    }
+    x += "</manifest>\n<spine>\n";
+    for i in 0..pages.len() {
+        x += &format!("<itemref idref=\"p{i:04}\"/>\n"); }
+    x += "</spine>\n</package>\n";
+    x
+}
+pub fn epub(info: &PackageInfo, root: &Rc<ScenarioNode>, pages: &[PackagePage]) -> Vec<u8>{
+    let mut zip = ZipWriter::new();
+    zip.add("mimetype", b"application/epub+zip"); // must be the first entry
+    zip.add("META-INF/container.xml", CONTAINER_XML.as_bytes());
+    zip.add("OEBPS/content.opf", opf(info, pages).as_bytes());
+    zip.add("OEBPS/nav.xhtml", nav(info, &outline(root, false)).as_bytes());
+    zip.add("OEBPS/style.css", STYLE_CSS.as_bytes());
+    for (i, p) in pages.iter().enumerate() {
+        zip.add(&format!("OEBPS/images/{:04}.png", i), &p.png);
+        zip.add(&format!("OEBPS/pages/{:04}.xhtml", i), epub_page(info, i, p).as_bytes());
+    }
+    zip.finish()
+}
+// write_package ///////////////////////////////////////
+pub fn write_package(path: &Path, info: &PackageInfo, root: &Rc<ScenarioNode>, pages: &[PackagePage]) -> anyhow::Result<()>{
+    let data = match PackageFormat::from_path(path) {
+        Some(PackageFormat::Cbz)  => cbz(info, root, pages),
+        Some(PackageFormat::Epub) => epub(info, root, pages),
+        None => anyhow::bail!("unknown package format: {}", path.display()),
+    };
+    std::fs::write(path, data).with_context(|| format!("can not write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import_util::script_import;
+
+    fn info() -> PackageInfo{
+        PackageInfo{ title: "t & t".to_string(), lang: "ja".to_string(), width: 640, height: 360,
+                     modified: "2024-01-31T00:00:00Z".to_string() }
+    }
+
+    #[test]
+    fn test_crc32_and_date() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(iso8601(UNIX_EPOCH + std::time::Duration::from_secs(1_706_745_600)), "2024-02-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_epub() {
+        let script = "# part 1\n\
+                      ## part 1-1\n\
+                      @image\n\
+                      a1\n\
+                      # part 2\n\
+                      @image img/b.png\n\
+                      b1\n";
+        let root = script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+        let nav = nav(&info(), &outline(&root, false));
+        assert!(nav.contains("<ol>\n<li><a href=\"pages/0000.xhtml\">part 1</a>\n\
+                              <ol>\n<li><a href=\"pages/0000.xhtml\">part 1-1</a></li>\n</ol>\n</li>\n\
+                              <li><a href=\"pages/0001.xhtml\">part 2</a></li>\n</ol>\n"), "{}", nav);
+
+        let pages = vec![PackagePage{ png: vec![1, 2, 3], text: vec!["a<1>".to_string()] }];
+        let data = epub(&info(), &root, &pages);
+        // mimetype is the first stored entry
+        assert_eq!(&data[0..4], b"PK\x03\x04");
+        assert_eq!(&data[30..38], b"mimetype");
+        assert_eq!(&data[38..58], b"application/epub+zip");
+        assert_eq!(&data[data.len() - 22..data.len() - 18], b"PK\x05\x06");
+        assert!(epub_page(&info(), 0, &pages[0]).contains("<div class=\"text\">\n<p>a&lt;1&gt;</p>\n</div>"));
+        assert!(opf(&info(), &pages).contains("<dc:title>t &amp; t</dc:title>"));
+    }
+
+    #[test]
+    fn test_page_text_and_comic_info() {
+        let script = "@image img/a.png\n\
+                      <b>hello</b>\n\
+                      --\n\
+                      world\n";
+        let root = script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+        let mut vec = vec![root.clone()];
+        let page = std::iter::from_fn(|| ScenarioNode::traverse(&mut vec))
+            .find(|n| n.is_page() || n.is_pmat()).unwrap();
+        assert_eq!(page_text(&page, ""), vec!["hello", "world"]);
+
+        let pages = vec![PackagePage{ png: vec![], text: vec![] }];
+        let xml = comic_info(&info(), &pages, &[OutlineEntry{ parent: None, title: "g".to_string(), page: 1 }]);
+        assert!(xml.contains("<PageCount>1</PageCount>"));
+        assert!(xml.contains("<Page Image=\"0\" ImageWidth=\"640\" ImageHeight=\"360\" Bookmark=\"g\"/>"));
+    }
+}
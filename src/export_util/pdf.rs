@@ -0,0 +1,96 @@
+//! outline of the exported pdf
+//!
+//! groups and scenes become outline entries pointing at their first page.
+//! entries without any page are omitted.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::scenario_node::ScenarioNode;
+use crate::scenario_node::Item;
+use super::transcript::scene_name;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineEntry{
+    pub parent : Option<usize>, // index of the parent entry
+    pub title  : String,
+    pub page   : usize,         // 1 origin
+}
+
+// outline /////////////////////////////////////////////
+/// entries in tree order, parents always precede their children.
+/// scenes are omitted unless with_scenes is set.
+pub fn outline(root: &Rc<ScenarioNode>, with_scenes: bool) -> Vec<OutlineEntry>{
+    let mut entries = Vec::new();
+    let mut added: HashMap<*const ScenarioNode, usize> = HashMap::new();
+    let mut page = 0;
+    let mut vec = vec![root.clone()];
+    while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+        if !matches!(&*sn.value.borrow(), Item::Page(_) | Item::Pmat(_)) {
+            continue; }
+        page += 1;
+        // scene and groups containing the page, from the top level
+        let mut chain = Vec::new();
+        let mut p = ScenarioNode::get_belong_scene(&sn);
+        while let Some(n) = p {
+            let title = match &*n.value.borrow() {
+                Item::Scene(_) if with_scenes => Some(scene_name(&n)),
+                Item::Group(_) => n.get_group_name(),
+                _              => None,
+            };
+            if let Some(t) = title.filter(|t| !t.trim().is_empty()) {
+                chain.push((n.clone(), t)); }
+            p = ScenarioNode::get_container(&n);
+        }
+        let mut parent = None;
+        for (n, title) in chain.into_iter().rev() {
+            let idx = *added.entry(Rc::as_ptr(&n)).or_insert_with(|| {
+                entries.push(OutlineEntry{ parent, title, page });
+                entries.len() - 1 });
+            parent = Some(idx);
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use crate::import_util::script_import;
+
+    #[test]
+    fn test_outline() {
+        let script = "# part 1\n\
+                      @image img/a.png\n\
+                      a1\n\
+                      \n\
+                      a2\n\
+                      @image img/b.png\n\
+                      b1\n\
+                      # part 2\n\
+                      @image img/c.png\n\
+                      c1\n";
+        let root = script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+        let e = |parent, title: &str, page| OutlineEntry{ parent, title: title.to_string(), page };
+        assert_eq!(outline(&root, false),
+                   vec![e(None, "part 1", 1),
+                        e(None, "part 2", 4)]);
+        assert_eq!(outline(&root, true),
+                   vec![e(None,    "part 1", 1),
+                        e(Some(0), "a.png",  1),
+                        e(Some(0), "b.png",  3),
+                        e(None,    "part 2", 4),
+                        e(Some(3), "c.png",  4)]);
+
+        // unnamed group
+        root.neighbor.borrow().as_ref().unwrap().set_group_name("");
+        assert_eq!(outline(&root, false),
+                   vec![e(None, "part 1", 1)]);
+        assert_eq!(outline(&root, true),
+                   vec![e(None,    "part 1", 1),
+                        e(Some(0), "a.png",  1),
+                        e(Some(0), "b.png",  3),
+                        e(None,    "c.png",  4)]);
+    }
+}
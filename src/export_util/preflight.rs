@@ -0,0 +1,218 @@
+//! checks of every page before export_images
+//!
+//! the export goes on when an image can not be loaded, a label is not defined
+//! or markup is broken, and only prints it. preflight lists these problems with
+//! their nodes before the export starts. text overflow needs a pango context
+//! and is checked by render::Renderer::overflowing_mats.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::rc::Rc;
+
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::pango;
+
+use crate::scenario_node::Item;
+use crate::scenario_node::LabelType;
+use crate::scenario_node::ScenarioNode;
+use super::transcript::child_mats;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity{ Error, Warning }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind{
+    NoScene,
+    MissingImage,
+    UnresolvedLabel,
+    TextOverflow,
+    BadMarkup,
+    EmptyPage,
+    MissingFont,
+    UnsupportedFormat,
+}
+#[derive(Debug, Clone)]
+pub struct Issue{
+    pub severity : Severity,
+    pub kind     : IssueKind,
+    pub page     : usize, // the first page at or after the node
+    pub node     : Rc<ScenarioNode>,
+    pub message  : String,
+}
+impl Issue{
+    fn new(severity: Severity, kind: IssueKind, page: usize, node: &Rc<ScenarioNode>, message: String) -> Self{
+        Self{ severity, kind, page, node: node.clone(), message }
+    }
+    /// a row of the report
+    pub fn line(&self) -> String{
+        let severity = match self.severity { Severity::Error => "error", Severity::Warning => "warning" };
+        format!("{:04} {:<7} {}: {}", self.page, severity, self.node.summary_str().trim(), self.message)
+    }
+}
+
+// PreflightLang ///////////////////////////////////////
+/// a language of the export targets
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreflightLang{
+    pub lang        : String,
+    pub text_key    : String,
+    pub font_family : Option<String>, // replaces the font family of mats
+}
+impl PreflightLang{
+    /// " (en)" appended to the messages, empty for the original language
+    fn suffix(&self) -> String{
+        if self.lang.is_empty() { String::new() } else { format!(" ({})", self.lang) }
+    }
+}
+
+/// generic families which fontconfig always resolves
+const GENERIC_FAMILIES: [&str; 7] = ["sans", "sans-serif", "serif", "monospace", "cursive", "fantasy", "system-ui"];
+
+// font_available //////////////////////////////////////
+/// one of the comma separated families is installed. installed is in lower case.
+pub fn font_available(family: &str, installed: &HashSet<String>) -> bool{
+    family.split(',').map(|f| f.trim().to_lowercase()).filter(|f| !f.is_empty())
+        .any(|f| GENERIC_FAMILIES.contains(&f.as_str()) || installed.contains(&f))
+}
+// image_problem ///////////////////////////////////////
+/// None if the image can be read
+pub fn image_problem(project_dir: &Path, path: &Path) -> Option<String>{
+    let abs = project_dir.join(path);
+    if !abs.is_file() {
+        Some(format!("{} is not found", path.display())) }
+    else if Pixbuf::file_info(&abs).is_none() {
+        Some(format!("{} can not be read", path.display())) }
+    else {
+        None }
+}
+fn unresolved(sn: &Rc<ScenarioNode>) -> bool{
+    matches!(sn.get_label_type(), Some(LabelType::Ref | LabelType::RefNoRect)) &&
+        ScenarioNode::search_def_label(sn.clone()).is_none()
+}
+
+// check ///////////////////////////////////////////////
+/// issues of the tree under root except text overflow, in tree order.
+/// installed is the font families in lower case.
+pub fn check(root        : &Rc<ScenarioNode>,
+             project_dir : &Path,
+             langs       : &[PreflightLang],
+             installed   : &HashSet<String>) -> Vec<Issue>{
+    let mut issues        = Vec::new();
+    let mut missing_fonts = HashSet::new(); // reported once for each family
+    let mut page          = 0;
+    let mut vec           = vec![root.clone()];
+    while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+        if unresolved(&sn) {
+            issues.push(Issue::new(Severity::Warning, IssueKind::UnresolvedLabel, page, &sn,
+                                   format!("label {} is not defined", sn.get_label().unwrap_or_default()))); }
+        match &*sn.value.borrow() {
+            Item::Scene(s) => {
+                // a scene referring to a label uses the image of the defining scene
+                let is_ref = matches!(s.lbl_type, LabelType::Ref | LabelType::RefNoRect);
+                if let (false, Some(b)) = (is_ref, &s.bgimg) {
+                    if let Some(m) = image_problem(project_dir, b) {
+                        issues.push(Issue::new(Severity::Error, IssueKind::MissingImage, page, &sn, m)); }
+                }
+            },
+            Item::Mat(_) | Item::Pmat(_) => {
+                let resolved = ScenarioNode::search_def_label(sn.clone()).unwrap_or(sn.clone());
+                if let (Some(true), Some(b)) = (resolved.get_mat_bg_en(), resolved.get_mat_bgimg()) {
+                    if let Some(m) = image_problem(project_dir, &b) {
+                        issues.push(Issue::new(Severity::Error, IssueKind::MissingImage, page, &sn, m)); }
+                }
+                for l in langs {
+                    let markup = sn.get_mat_text_lang(&l.text_key).unwrap_or_default();
+                    if let Err(e) = pango::parse_markup(&markup, '\0') {
+                        issues.push(Issue::new(Severity::Error, IssueKind::BadMarkup, page, &sn,
+                                               format!("{}{}", e.message(), l.suffix()))); }
+                    let family = l.font_family.clone().or(resolved.get_mat_font_family()).unwrap_or_default();
+                    if !family.is_empty() && !font_available(&family, installed) && missing_fonts.insert(family.clone()) {
+                        issues.push(Issue::new(Severity::Warning, IssueKind::MissingFont, page, &sn,
+                                               format!("font {} is not installed{}", family, l.suffix()))); }
+                }
+            },
+            Item::Ovimg(o) => {
+                if let Some(m) = image_problem(project_dir, Path::new(&o.path)) {
+                    issues.push(Issue::new(Severity::Error, IssueKind::MissingImage, page, &sn, m)); }
+            },
+            Item::Page(_) => (),
+            Item::Group(_) => (),
+        }
+        if sn.is_page() || sn.is_pmat() {
+            if ScenarioNode::get_belong_scene(&sn).is_none() {
+                issues.push(Issue::new(Severity::Error, IssueKind::NoScene, page, &sn,
+                                       "the page is not in a scene".to_string())); }
+            for l in langs.iter().filter(|l| is_empty_page(&sn, &l.text_key)) {
+                issues.push(Issue::new(Severity::Warning, IssueKind::EmptyPage, page, &sn,
+                                       format!("the page has no text or image{}", l.suffix()))); }
+            page += 1;
+        }
+    }
+    issues
+}
+/// no text, image mat nor ovimg in the page
+fn is_empty_page(sn: &Rc<ScenarioNode>, text_key: &str) -> bool{
+    let mats = if sn.is_pmat() { vec![sn.clone()] } else { child_mats(sn) };
+    let has_ovimg = {
+        let mut p = if sn.is_page() { sn.child.borrow().clone() } else { None };
+        let mut found = false;
+        while let Some(n) = p {
+            found |= matches!(&*n.value.borrow(), Item::Ovimg(_));
+            p = n.neighbor.borrow().clone();
+        }
+        found
+    };
+    !has_ovimg && mats.iter().all(|m|{
+        let resolved = ScenarioNode::search_def_label(m.clone()).unwrap_or(m.clone());
+        resolved.get_mat_bg_en() != Some(true) && m.get_mat_text_lang(text_key).unwrap_or_default().trim().is_empty()
+    })
+}
+// overflow_issue //////////////////////////////////////
+pub fn overflow_issue(node: &Rc<ScenarioNode>, page: usize, lang: &PreflightLang) -> Issue{
+    Issue::new(Severity::Warning, IssueKind::TextOverflow, page, node,
+               format!("the text overflows the mat{}", lang.suffix()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import_util::script_import;
+
+    #[test]
+    fn test_check() {
+        let script = "@image img/missing.png\n\
+                      <b>broken</i>\n\
+                      \n\
+                      empty\n\
+                      \n\
+                      fine\n";
+        let root = script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+        let mut vec = vec![root.clone()];
+        let mats: Vec<_> = std::iter::from_fn(|| ScenarioNode::traverse(&mut vec)).filter(|n| n.is_pmat()).collect();
+        assert_eq!(mats.len(), 3);
+        mats[1].set_mat_text("");
+        mats[2].set_label_type(LabelType::Ref);
+        mats[2].set_label(Some("nowhere".to_string()));
+
+        let langs = [PreflightLang{ lang: String::new(), text_key: String::new(), font_family: Some("No Such Font, Sans".to_string()) }];
+        let issues = check(&root, Path::new("."), &langs, &HashSet::new());
+        let kinds: Vec<_> = issues.iter().map(|i| (i.kind, i.page)).collect();
+        assert!(kinds.contains(&(IssueKind::MissingImage, 0)), "{:?}", kinds);
+        assert!(kinds.contains(&(IssueKind::BadMarkup, 0)), "{:?}", kinds);
+        assert!(kinds.contains(&(IssueKind::EmptyPage, 1)), "{:?}", kinds);
+        assert!(kinds.contains(&(IssueKind::UnresolvedLabel, 2)), "{:?}", kinds);
+        assert!(!kinds.iter().any(|k| k.0 == IssueKind::MissingFont), "{:?}", kinds); // Sans is generic
+        assert!(issues[0].line().starts_with("0000 error"));
+
+        let langs = [PreflightLang{ lang: "en".to_string(), text_key: String::new(), font_family: Some("No Such Font".to_string()) }];
+        let fonts: Vec<_> = check(&root, Path::new("."), &langs, &HashSet::new()).into_iter()
+            .filter(|i| i.kind == IssueKind::MissingFont).collect();
+        assert_eq!(fonts.len(), 1); // once for each family
+        assert!(fonts[0].message.ends_with("(en)"));
+
+        let installed: HashSet<String> = ["noto sans cjk jp".to_string()].into();
+        assert!(font_available("Noto Sans CJK JP", &installed));
+        assert!(font_available("Nothing, serif", &installed));
+        assert!(!font_available("Nothing", &installed));
+    }
+}
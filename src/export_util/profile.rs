@@ -0,0 +1,100 @@
+//! named export settings stored in the project
+//!
+//! a profile has its own resolution, format, directory, naming template and
+//! language. the mats are placed in the target size of the project and scaled
+//! into the resolution of the profile, centered when the aspect ratio differs.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use gtk::prelude::*;
+
+use crate::isv2_parameter::Isv2Parameter;
+use super::image_format::Content;
+use super::image_format::ImageFormat;
+use super::stamp::Stamp;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportProfile{
+    pub name          : String,
+    pub width         : i32,
+    pub height        : i32,
+    pub format        : ImageFormat,
+    pub export_dir    : String, // in the project dir
+    pub name_template : String, // see naming
+    pub language      : String, // "" means the current language
+    #[serde(default)]
+    pub content       : Content,
+    #[serde(default)]
+    pub stamps        : Vec<Stamp>, // watermarks and credits
+}
+
+// ExportTarget ////////////////////////////////////////
+/// one output of export_images: a language in a directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportTarget{
+    pub lang          : String,
+    pub out_dir       : PathBuf,
+    pub width         : i32,
+    pub height        : i32,
+    pub format        : ImageFormat,
+    pub name_template : String,
+    pub content       : Content,
+    pub stamps        : Vec<Stamp>,
+}
+impl ExportTarget{
+    /// the current language into export_dir, or all languages of the project
+    /// into export_dir/<language> when all_languages is set
+    pub fn of_param(param: &Isv2Parameter, all_languages: bool, format: ImageFormat) -> Vec<Self>{
+        let mut dir = param.property::<PathBuf>("project_dir");
+        dir.push( param.property::<String>("export_dir") );
+        let codes: Vec<String> = param.languages().into_iter().map(|l| l.code).collect();
+        let langs: Vec<(String, PathBuf)> = {
+            if all_languages && !codes.is_empty() {
+                codes.into_iter().map(|c| (c.clone(), dir.join(c))).collect() }
+            else {
+                vec![(param.property::<String>("language"), dir)] }
+        };
+        langs.into_iter().map(|(lang, out_dir)| Self{
+            lang, out_dir, format,
+            width         : param.property::<i32>("target_width"),
+            height        : param.property::<i32>("target_height"),
+            name_template : param.property::<String>("name_template"),
+            content       : Content::Full,
+            stamps        : Vec::new(),
+        }).collect()
+    }
+    pub fn of_profile(profile: &ExportProfile, project_dir: &Path, current_lang: &str) -> Self{
+        Self{
+            lang          : if profile.language.is_empty() { current_lang.to_string() } else { profile.language.clone() },
+            out_dir       : project_dir.join(&profile.export_dir),
+            width         : profile.width,
+            height        : profile.height,
+            format        : profile.format,
+            name_template : profile.name_template.clone(),
+            content       : profile.content,
+            stamps        : profile.stamps.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target() {
+        let mut p = ExportProfile{
+            name: "hd".to_string(), width: 1280, height: 720, format: ImageFormat::Jpeg{ quality: 80 },
+            export_dir: "hd".to_string(), name_template: "{seq}".to_string(), language: String::new(),
+            content: Content::Full, stamps: Vec::new() };
+        let t = ExportTarget::of_profile(&p, Path::new("/prj"), "ja");
+        assert_eq!((t.lang.as_str(), t.out_dir.as_path(), t.width, t.height), ("ja", Path::new("/prj/hd"), 1280, 720));
+        p.language = "en".to_string();
+        assert_eq!(ExportTarget::of_profile(&p, Path::new("/prj"), "ja").lang, "en");
+        let json = serde_json::to_string(&p).unwrap();
+        assert_eq!(serde_json::from_str::<ExportProfile>(&json).unwrap(), p);
+    }
+}
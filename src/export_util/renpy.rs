@@ -0,0 +1,394 @@
+//! Ren'Py script of the tree
+//!
+//!   group            -> label
+//!   scene            -> image bg_NNNN and `scene` (label when it defines one)
+//!   page             -> ovimgs by `show`, mat texts by say statements
+//!   pmat             -> say statement
+//!   speaker of mat   -> Character
+//!
+//! background images are copied into images/isv2/ of the game directory and
+//! fitted to the screen with Transform(fit=...) of Ren'Py 7.5 or later.
+//! constructs Ren'Py can not show in the same way are reported as warnings.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use anyhow::Context;
+
+use crate::scenario_node::Item;
+use crate::scenario_node::LabelType;
+use crate::scenario_node::ScenarioNode;
+use crate::scenario_node::Transition;
+use super::transcript::child_mats;
+use super::transcript::strip_markup;
+
+pub const ASSET_DIR: &str = "images/isv2";
+
+pub struct RenpyExport{
+    pub script     : String,                 // script.rpy
+    pub characters : String,                 // characters.rpy
+    pub images     : String,                 // images.rpy
+    pub assets     : Vec<(PathBuf, String)>, // source -> path relative to the game dir
+    pub warnings   : Vec<String>,
+}
+
+// ident ///////////////////////////////////////////////
+/// python identifier from a name, unique in used
+fn ident(name: &str, prefix: &str, used: &mut HashSet<String>) -> String{
+    let mut s = String::new();
+    for c in name.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() { s.push(c); }
+        else if !s.ends_with('_') { s.push('_'); }
+    }
+    let s = s.trim_matches('_');
+    let base = if s.is_empty() || s.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("{}{}", prefix, s) } else { s.to_string() };
+    let mut id = base.clone();
+    let mut n  = 2;
+    while used.contains(&id) {
+        id = format!("{}_{}", base, n);
+        n += 1;
+    }
+    used.insert(id.clone());
+    id
+}
+// escape //////////////////////////////////////////////
+/// content of a string literal of Ren'Py, text tags and interpolation are escaped
+fn escape(s: &str) -> String{
+    let mut q = String::new();
+    for c in s.chars() {
+        match c {
+            '\\' => q.push_str("\\\\"),
+            '"'  => q.push_str("\\\""),
+            '\n' => q.push_str("\\n"),
+            '{'  => q.push_str("{{"),
+            '['  => q.push_str("[["),
+            _    => q.push(c),
+        }
+    }
+    q
+}
+fn quote(s: &str) -> String{ format!("\"{}\"", escape(s)) }
+// attrs ///////////////////////////////////////////////
+/// attributes of a tag like `span foreground="red" size='large'`
+fn attrs(tag: &str) -> Vec<(String, String)>{
+    let mut v = Vec::new();
+    let mut rest = tag.split_once(char::is_whitespace).map(|(_, r)| r).unwrap_or("").trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim().to_string();
+        let r = rest[eq + 1..].trim_start();
+        let (value, next) = match r.chars().next() {
+            Some(q) if q == '"' || q == '\'' => {
+                let end = r[1..].find(q).map(|e| e + 1).unwrap_or(r.len());
+                (&r[1..end], r.get(end + 1..).unwrap_or("")) },
+            _ => {
+                let end = r.find(char::is_whitespace).unwrap_or(r.len());
+                (&r[..end], &r[end..]) },
+        };
+        v.push((key, value.to_string()));
+        rest = next.trim_start();
+    }
+    v
+}
+// text_tags ///////////////////////////////////////////
+/// pango markup -> text with Ren'Py text tags. markup without
+/// a counterpart is dropped and returned as the second value.
+pub fn text_tags(markup: &str) -> (String, Vec<String>){
+    let mut out     = String::new();
+    let mut dropped = Vec::new();
+    let mut stack: Vec<Vec<&str>> = Vec::new(); // closing tags of the open elements
+    let mut rest = markup;
+    while let Some(lt) = rest.find('<') {
+        out.push_str(&escape(&strip_markup(&rest[..lt])));
+        let gt = if let Some(g) = rest[lt..].find('>') { lt + g } else { rest = &rest[lt..]; break; };
+        let tag = rest[lt + 1..gt].trim();
+        rest = &rest[gt + 1..];
+        if tag.starts_with('/') {
+            for c in stack.pop().unwrap_or_default().iter().rev() {
+                out.push_str(&format!("{{/{}}}", c)); }
+            continue;
+        }
+        let name = tag.split_whitespace().next().unwrap_or("").trim_end_matches('/');
+        let mut close = Vec::new();
+        let mut open  = |t: &str, c: &'static str, out: &mut String| { out.push_str(&format!("{{{}}}", t)); close.push(c); };
+        match name {
+            "b" | "i" | "u" | "s" => {
+                let c = match name { "b" => "b", "i" => "i", "u" => "u", _ => "s" };
+                open(c, c, &mut out); },
+            "big"   => open("size=+4", "size", &mut out),
+            "small" => open("size=-4", "size", &mut out),
+            "span"  => {
+                for (k, v) in attrs(tag) {
+                    match (k.as_str(), v.as_str()) {
+                        ("foreground" | "fgcolor" | "color", c) if c.starts_with('#') => open(&format!("color={}", c), "color", &mut out),
+                        ("weight" | "font_weight", "bold" | "heavy" | "ultrabold")      => open("b", "b", &mut out),
+                        ("style" | "font_style", "italic" | "oblique")                  => open("i", "i", &mut out),
+                        ("underline", u) if u != "none"                                 => open("u", "u", &mut out),
+                        ("strikethrough", "true")                                       => open("s", "s", &mut out),
+                        _ => dropped.push(format!("{}=\"{}\"", k, v)),
+                    }
+                }
+            },
+            _ => dropped.push(format!("<{}>", name)),
+        }
+        stack.push(close);
+    }
+    out.push_str(&escape(&strip_markup(rest)));
+    while let Some(close) = stack.pop() {
+        for c in close.iter().rev() {
+            out.push_str(&format!("{{/{}}}", c)); }
+    }
+    (out, dropped)
+}
+
+// Builder /////////////////////////////////////////////
+struct Builder<'a>{
+    project_dir : &'a Path,
+    text_key    : &'a str,
+    size        : (i32, i32),
+    script      : String,
+    images      : String,
+    chars       : Vec<(String, String)>,       // (variable, speaker)
+    char_vars   : HashMap<String, String>,      // speaker -> variable
+    bg_names    : HashMap<String, String>,      // image expression -> image name
+    asset_names : HashMap<PathBuf, String>,     // source -> path in the game dir
+    assets      : Vec<(PathBuf, String)>,
+    used        : HashSet<String>,              // labels, characters and images
+    warnings    : Vec<String>,
+    in_label    : bool,                         // the current label has a statement
+}
+impl Builder<'_>{
+    fn warn(&mut self, sn: &Rc<ScenarioNode>, message: &str){
+        self.warnings.push(format!("{}: {}", sn.summary_str().trim(), message)); }
+    fn statement(&mut self, s: &str){
+        self.script += &format!("    {}\n", s);
+        self.in_label = true;
+    }
+    fn label(&mut self, name: &str, comment: &str){
+        if !self.in_label {
+            self.statement("pass"); }
+        let id = ident(name, "l_", &mut self.used);
+        self.script += &format!("\n# {}\nlabel {}:\n", comment.replace('\n', " "), id);
+        self.in_label = false;
+    }
+    /// copies the file into the game dir, None when it does not exist
+    fn asset(&mut self, sn: &Rc<ScenarioNode>, path: &Path) -> Option<String>{
+        let src = self.project_dir.join(path);
+        if !src.is_file() {
+            self.warn(sn, &format!("{} is not found", src.display()));
+            return None; }
+        if let Some(a) = self.asset_names.get(&src) {
+            return Some(a.clone()); }
+        let stem = src.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let ext  = src.extension().map(|s| s.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let name = format!("{}/{}.{}", ASSET_DIR, ident(&stem, "img_", &mut self.used), ext);
+        self.asset_names.insert(src.clone(), name.clone());
+        self.assets.push((src, name.clone()));
+        Some(name)
+    }
+    fn scene(&mut self, sn: &Rc<ScenarioNode>){
+        if sn.get_label_type() == Some(LabelType::Def) {
+            if let Some(l) = sn.get_label() {
+                self.label(&l, &format!("label {}", l)); }
+        }
+        let def = ScenarioNode::search_def_label(sn.clone());
+        if matches!(sn.get_label_type(), Some(LabelType::Ref | LabelType::RefNoRect)) && def.is_none() {
+            self.warn(sn, "the label is not defined, the scene is exported as it is"); }
+        let scene = def.unwrap_or(sn.clone());
+        let s = if let Item::Scene(s) = &*scene.value.borrow() { s.clone() } else { return; };
+
+        let (w, h) = self.size;
+        let solid  = format!("Solid(\"#{:02x}{:02x}{:02x}\")", s.bgcol.r.min(255), s.bgcol.g.min(255), s.bgcol.b.min(255));
+        let img    = if s.bg_en { s.bgimg.as_ref().and_then(|b| self.asset(sn, b)) } else { None };
+        let expr = match img {
+            Some(path) => {
+                let crop = if s.crop_en {
+                    format!(", crop=({}, {}, {}, {})", s.crop.pos.x, s.crop.pos.y, s.crop.dim.w, s.crop.dim.h) }
+                    else { String::new() };
+                format!("Fixed({}, Transform(\"{}\"{}, fit=\"contain\", xysize=({}, {}), align=(0.5, 0.5)), xysize=({}, {}))",
+                        solid, path, crop, w, h, w, h) },
+            None => solid,
+        };
+        let name = match self.bg_names.get(&expr) {
+            Some(n) => n.clone(),
+            None    => {
+                let n = format!("bg_{:04}", self.bg_names.len());
+                self.used.insert(n.clone());
+                self.images += &format!("image {} = {}\n", n, expr);
+                self.bg_names.insert(expr, n.clone());
+                n },
+        };
+        self.statement(&format!("scene {}", name));
+
+        // mats of the scene are on every page in isv2
+        let mats = child_mats(sn);
+        if !mats.is_empty() {
+            self.warn(sn, "mats directly under the scene are said once at the beginning of the scene"); }
+        for m in mats {
+            self.say(&m); }
+    }
+    fn speaker(&mut self, speaker: &str) -> String{
+        if let Some(v) = self.char_vars.get(speaker) {
+            return v.clone(); }
+        let v = ident(&format!("c_{}", speaker), "c_", &mut self.used);
+        self.chars.push((v.clone(), speaker.to_string()));
+        self.char_vars.insert(speaker.to_string(), v.clone());
+        v
+    }
+    fn say(&mut self, sn: &Rc<ScenarioNode>){
+        if matches!(sn.get_label_type(), Some(LabelType::Ref | LabelType::RefNoRect)) &&
+            ScenarioNode::search_def_label(sn.clone()).is_none() {
+            self.warn(sn, "the label is not defined"); }
+        if sn.get_mat_bg_en() == Some(true) {
+            self.warn(sn, "the image of the mat is not exported"); }
+        if sn.get_mat_vertical() == Some(true) {
+            self.warn(sn, "vertical text is exported as horizontal text"); }
+        let markup = sn.get_mat_text_lang(self.text_key).unwrap_or_default();
+        if strip_markup(&markup).trim().is_empty() {
+            return; }
+        let (text, dropped) = text_tags(markup.trim());
+        if !dropped.is_empty() {
+            self.warn(sn, &format!("unsupported markup is dropped: {}", dropped.join(" "))); }
+        let who = sn.get_mat_speaker().filter(|s| !s.trim().is_empty()).map(|s| self.speaker(s.trim()));
+        match who {
+            Some(w) => self.statement(&format!("{} \"{}\"", w, text)),
+            None    => self.statement(&format!("\"{}\"", text)),
+        }
+    }
+    fn transition(&mut self, sn: &Rc<ScenarioNode>){
+        match sn.get_timing().unwrap_or_default().transition {
+            Transition::Cut       => (),
+            Transition::Crossfade => self.statement("with dissolve"),
+            Transition::FadeBlack => self.statement("with fade"),
+        }
+    }
+    fn page(&mut self, sn: &Rc<ScenarioNode>){
+        let mut shown = Vec::new();
+        let mut p = sn.child.borrow().clone();
+        while let Some(n) = p {
+            if let Item::Ovimg(o) = &*n.value.borrow() {
+                if let Some(path) = self.asset(&n, Path::new(&o.path)) {
+                    let tag = format!("ov_{}", shown.len());
+                    self.statement(&format!("show expression \"{}\" as {} at Transform(xpos={}, ypos={}, alpha={})",
+                                            path, tag, o.pos.x, o.pos.y, o.a));
+                    shown.push(tag);
+                }
+            }
+            p = n.neighbor.borrow().clone();
+        }
+        self.transition(sn);
+        let before = self.script.len();
+        for m in child_mats(sn) {
+            self.say(&m); }
+        if self.script.len() == before {
+            self.statement("pause"); }
+        for tag in shown {
+            self.statement(&format!("hide {}", tag)); }
+    }
+}
+
+// renpy ///////////////////////////////////////////////
+/// script of the tree from root in the language of text_key, for a game of size
+pub fn renpy(root: &Rc<ScenarioNode>, project_dir: &Path, text_key: &str, size: (i32, i32)) -> RenpyExport{
+    let mut b = Builder{
+        project_dir, text_key, size,
+        script      : String::new(),
+        images      : String::new(),
+        chars       : Vec::new(),
+        char_vars   : HashMap::new(),
+        bg_names    : HashMap::new(),
+        asset_names : HashMap::new(),
+        assets      : Vec::new(),
+        used        : ["start", "narrator", "extend", "centered", "config", "renpy", "store", "gui", "style"]
+                          .iter().map(|s| s.to_string()).collect(),
+        warnings    : Vec::new(),
+        in_label    : false,
+    };
+    b.script += "label start:\n";
+    let mut vec = vec![root.clone()];
+    while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+        let item = sn.value.borrow().clone();
+        match item {
+            Item::Group(_) => {
+                if let Some(name) = sn.get_group_name() {
+                    b.label(&name, &name); }
+            },
+            Item::Scene(_) => b.scene(&sn),
+            Item::Page(_)  => b.page(&sn),
+            Item::Pmat(_)  => { b.transition(&sn); b.say(&sn); },
+            _ => (),
+        }
+    }
+    b.statement("return");
+
+    let header = format!("# generated by {} for a {}x{} screen\n\n", env!("CARGO_PKG_NAME"), size.0, size.1);
+    let characters = b.chars.iter()
+        .map(|(v, s)| format!("define {} = Character({})\n", v, quote(s)))
+        .collect::<String>();
+    RenpyExport{
+        script     : format!("{}{}", header, b.script),
+        characters : format!("{}{}", header, characters),
+        images     : format!("{}{}", header, b.images),
+        assets     : b.assets,
+        warnings   : b.warnings,
+    }
+}
+// write_renpy /////////////////////////////////////////
+/// writes script.rpy, characters.rpy and images.rpy into the game dir and copies the images
+pub fn write_renpy(game_dir: &Path, r: &RenpyExport) -> anyhow::Result<()>{
+    for (name, text) in [("script.rpy", &r.script), ("characters.rpy", &r.characters), ("images.rpy", &r.images)] {
+        let path = game_dir.join(name);
+        std::fs::write(&path, text).with_context(|| format!("can not write {}", path.display()))?;
+    }
+    for (src, name) in &r.assets {
+        let dst = game_dir.join(name);
+        if let Some(d) = dst.parent() {
+            std::fs::create_dir_all(d).with_context(|| format!("can not create {}", d.display()))?; }
+        std::fs::copy(src, &dst).with_context(|| format!("can not copy {} to {}", src.display(), dst.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import_util::script_import;
+
+    #[test]
+    fn test_text_tags() {
+        assert_eq!(text_tags("<b>a</b> & \"b\" {c} [d]"),
+                   ("{b}a{/b} & \\\"b\\\" {{c} [[d]".to_string(), vec![]));
+        assert_eq!(text_tags("<span foreground=\"#ff0000\" size=\"large\">x</span>&lt;"),
+                   ("{color=#ff0000}x{/color}<".to_string(), vec!["size=\"large\"".to_string()]));
+        assert_eq!(text_tags("<tt>y</tt>\nz"), ("y\\nz".to_string(), vec!["<tt>".to_string()]));
+    }
+
+    #[test]
+    fn test_renpy() {
+        let script = "# part 1\n\
+                      @image\n\
+                      hello\n\
+                      \n\
+                      a\n\
+                      --\n\
+                      b\n\
+                      # part 1\n\
+                      @image img/missing.png\n\
+                      bye\n";
+        let root = script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+        let mut vec = vec![root.clone()];
+        let pmat = std::iter::from_fn(|| ScenarioNode::traverse(&mut vec)).find(|n| n.is_pmat()).unwrap();
+        pmat.set_mat_speaker(Some("Eileen".to_string()));
+
+        let r = renpy(&root, Path::new("."), "", (1280, 720));
+        assert!(r.script.contains("label start:\n    pass\n\n# part 1\nlabel part_1:\n    scene bg_0000\n    c_eileen \"hello\"\n    \"a\"\n    \"b\"\n"),
+                "{}", r.script);
+        assert!(r.script.contains("label part_1_2:\n    scene bg_0000\n    \"bye\"\n    return\n"), "{}", r.script);
+        assert!(r.characters.contains("define c_eileen = Character(\"Eileen\")\n"));
+        assert!(r.images.contains("image bg_0000 = Solid(\"#808080\")\n"));
+        assert!(r.assets.is_empty());
+        assert_eq!(r.warnings.len(), 1, "{:?}", r.warnings);
+    }
+}
@@ -0,0 +1,99 @@
+//! watermarks and credit lines of an export profile
+//!
+//! a stamp is a text or an image drawn over the mats of the exported pages,
+//! placed in the output size of the profile. it is never drawn in the preview.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StampContent{
+    Text(String),
+    Image(String), // in the project dir
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StampPos{
+    TopLeft, Top, TopRight,
+    Left, #[default] Center, Right,
+    BottomLeft, Bottom, BottomRight,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StampPages{
+    #[default]
+    All,
+    First,
+    Last,
+    Range(usize, usize), // first and last page number
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stamp{
+    pub content : StampContent,
+    pub pos     : StampPos,
+    pub opacity : u32, // percent
+    pub size    : u32, // height of the text or the image in percent of the page height
+    pub pages   : StampPages,
+}
+
+const POS_NAMES: [(StampPos, &str); 9] = [
+    (StampPos::TopLeft,    "top-left"),    (StampPos::Top,    "top"),    (StampPos::TopRight,    "top-right"),
+    (StampPos::Left,       "left"),        (StampPos::Center, "center"), (StampPos::Right,       "right"),
+    (StampPos::BottomLeft, "bottom-left"), (StampPos::Bottom, "bottom"), (StampPos::BottomRight, "bottom-right")];
+
+impl StampPos{
+    pub fn name(&self) -> &'static str{
+        POS_NAMES.iter().find(|(p, _)| p == self).map(|(_, n)| *n).unwrap_or_default()
+    }
+    pub fn from_name(name: &str) -> Option<Self>{
+        POS_NAMES.iter().find(|(_, n)| *n == name).map(|(p, _)| *p)
+    }
+    /// top left corner of a w x h stamp in a page_w x page_h page, margin apart from the edges
+    pub fn place(&self, page_w: f64, page_h: f64, w: f64, h: f64, margin: f64) -> (f64, f64){
+        let i = POS_NAMES.iter().position(|(p, _)| p == self).unwrap_or(4);
+        let along = |k: usize, page: f64, len: f64| match k {
+            0 => margin,
+            1 => (page - len) / 2.0,
+            _ => page - len - margin,
+        };
+        (along(i % 3, page_w, w), along(i / 3, page_h, h))
+    }
+}
+impl StampPages{
+    /// the stamp is drawn on the page, last is the number of the last page of the project
+    pub fn includes(&self, page: usize, last: usize) -> bool{
+        match self {
+            StampPages::All         => true,
+            StampPages::First       => page == 0,
+            StampPages::Last        => page == last,
+            StampPages::Range(f, l) => (*f <= page) && (page <= *l),
+        }
+    }
+}
+/// stamps drawn on the page
+pub fn for_page(stamps: &[Stamp], page: usize, last: usize) -> Vec<Stamp>{
+    stamps.iter().filter(|s| s.pages.includes(page, last)).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stamp() {
+        assert_eq!(StampPos::from_name("bottom-right"), Some(StampPos::BottomRight));
+        assert_eq!(StampPos::from_name(StampPos::Top.name()), Some(StampPos::Top));
+        assert_eq!(StampPos::from_name("middle"), None);
+        assert_eq!(StampPos::TopLeft.place(100.0, 50.0, 20.0, 10.0, 2.0), (2.0, 2.0));
+        assert_eq!(StampPos::Center.place(100.0, 50.0, 20.0, 10.0, 2.0), (40.0, 20.0));
+        assert_eq!(StampPos::BottomRight.place(100.0, 50.0, 20.0, 10.0, 2.0), (78.0, 38.0));
+
+        let credit = Stamp{ content: StampContent::Text("credit".to_string()), pos: StampPos::Bottom,
+                            opacity: 100, size: 4, pages: StampPages::Last };
+        let draft  = Stamp{ content: StampContent::Text("DRAFT".to_string()), pos: StampPos::Center,
+                            opacity: 30, size: 20, pages: StampPages::All };
+        let stamps = [draft.clone(), credit.clone()];
+        assert_eq!(for_page(&stamps, 0, 9), vec![draft.clone()]);
+        assert_eq!(for_page(&stamps, 9, 9), vec![draft, credit]);
+        assert!(StampPages::Range(2, 4).includes(4, 9));
+        assert!(!StampPages::Range(2, 4).includes(5, 9));
+        assert!(StampPages::First.includes(0, 9));
+    }
+}
@@ -0,0 +1,131 @@
+//! pages of a partial export and the numbers in their file names
+//!
+//! a subset is the subtree of a node or a range of page numbers. the files are
+//! numbered as in the whole export (global), so that they replace the files of
+//! a previous export, or from 0 in the subset (local).
+
+use std::rc::Rc;
+
+use crate::scenario_node::Item;
+use crate::scenario_node::ScenarioNode;
+
+#[derive(Debug, Clone, Default)]
+pub enum Scope{
+    #[default]
+    All,
+    Subtree(Rc<ScenarioNode>),
+    Pages(usize, usize), // first and last page number
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Subset{
+    pub scope : Scope,
+    pub local : bool,
+}
+impl Subset{
+    /// subtree of node. a mat or an ovimg stands for the page or scene containing it
+    pub fn subtree(node: &Rc<ScenarioNode>, local: bool) -> Self{
+        let node = match &*node.value.borrow() {
+            Item::Mat(_) | Item::Ovimg(_) => ScenarioNode::get_container(node).unwrap_or(node.clone()),
+            _                             => node.clone(),
+        };
+        Self{ scope: Scope::Subtree(node), local }
+    }
+    pub fn is_all(&self) -> bool{ matches!(self.scope, Scope::All) }
+    /// page sn with the number seq in the whole export is exported
+    pub fn contains(&self, sn: &Rc<ScenarioNode>, seq: usize) -> bool{
+        match &self.scope {
+            Scope::All                => true,
+            Scope::Pages(first, last) => (*first..=*last).contains(&seq),
+            Scope::Subtree(node)      => is_descendant(sn, node),
+        }
+    }
+}
+/// sn is node or under node, following the containers up from sn
+pub fn is_descendant(sn: &Rc<ScenarioNode>, node: &Rc<ScenarioNode>) -> bool{
+    let mut p = Some(sn.clone());
+    while let Some(n) = p {
+        if Rc::ptr_eq(&n, node) { return true; }
+        p = ScenarioNode::get_container(&n);
+    }
+    false
+}
+
+// Numbering ///////////////////////////////////////////
+/// numbers of the pages in the subset while traversing every page
+#[derive(Debug, Clone, Default)]
+pub struct Numbering{
+    global : usize,
+    local  : usize,
+}
+impl Numbering{
+    /// number of the next page for its file name, None if it is not exported
+    pub fn next(&mut self, subset: &Subset, sn: &Rc<ScenarioNode>) -> Option<usize>{
+        let global = self.global;
+        self.global+= 1;
+        if !subset.contains(sn, global) { return None; }
+        self.local+= 1;
+        Some(if subset.local { self.local - 1 } else { global })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use crate::import_util::script_import;
+    use crate::scenario_node::Ovimg;
+
+    fn pages(root: &Rc<ScenarioNode>) -> Vec<Rc<ScenarioNode>>{
+        let mut vec = vec![root.clone()];
+        let mut pages = Vec::new();
+        while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+            if let Item::Page(_) | Item::Pmat(_) = &*sn.value.borrow() {
+                pages.push(sn.clone()); }
+        }
+        pages
+    }
+    fn numbers(root: &Rc<ScenarioNode>, subset: &Subset) -> Vec<Option<usize>>{
+        let mut n = Numbering::default();
+        pages(root).iter().map(|sn| n.next(subset, sn)).collect()
+    }
+
+    #[test]
+    fn test_subset() {
+        let script = "@image img/a.png\n\
+                      a1\n\
+                      \n\
+                      a2\n\
+                      @image img/b.png\n\
+                      b1\n\
+                      --\n\
+                      b1 second\n\
+                      \n\
+                      b2\n";
+        let root = script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+        assert_eq!(numbers(&root, &Subset::default()), vec![Some(0), Some(1), Some(2), Some(3)]);
+
+        let range = Subset{ scope: Scope::Pages(1, 2), local: false };
+        assert_eq!(numbers(&root, &range), vec![None, Some(1), Some(2), None]);
+        let range = Subset{ scope: Scope::Pages(1, 2), local: true };
+        assert_eq!(numbers(&root, &range), vec![None, Some(0), Some(1), None]);
+
+        let scene_b = ScenarioNode::get_belong_scene(&pages(&root)[2]).unwrap();
+        assert_eq!(numbers(&root, &Subset::subtree(&scene_b, false)), vec![None, None, Some(2), Some(3)]);
+        assert_eq!(numbers(&root, &Subset::subtree(&scene_b, true)),  vec![None, None, Some(0), Some(1)]);
+        let page = pages(&root)[1].clone();
+        assert_eq!(numbers(&root, &Subset::subtree(&page, false)), vec![None, Some(1), None, None]);
+
+        // not the last of the neighbors
+        let scene_a = ScenarioNode::get_belong_scene(&pages(&root)[0]).unwrap();
+        assert_eq!(numbers(&root, &Subset::subtree(&scene_a, false)), vec![Some(0), Some(1), None, None]);
+        let page = pages(&root)[0].clone();
+        assert_eq!(numbers(&root, &Subset::subtree(&page, false)), vec![Some(0), None, None, None]);
+        let page = pages(&root)[2].clone();
+        let mat = page.child.borrow().clone().unwrap().neighbor.borrow().clone().unwrap();
+        assert_eq!(mat.get_mat_text(), Some("b1 second".to_string()));
+        assert_eq!(numbers(&root, &Subset::subtree(&mat, false)), vec![None, None, Some(2), None]);
+        *mat.value.borrow_mut() = Item::Ovimg(Ovimg::default()); // an ovimg stands for its page as well
+        assert_eq!(numbers(&root, &Subset::subtree(&mat, false)), vec![None, None, Some(2), None]);
+    }
+}
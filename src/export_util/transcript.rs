@@ -0,0 +1,270 @@
+//! exports mat texts of every page in tree order
+//!
+//! pages are numbered in the same order as the exported images (0000.png, ..).
+//! mats directly under a scene are shown on every page of the scene, so they
+//! are listed once under the scene heading without page number.
+
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::scenario_node::ScenarioNode;
+use crate::scenario_node::Item;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TranscriptFormat { Text, Markdown, Csv }
+impl TranscriptFormat {
+    pub fn from_path(path: &Path) -> Option<Self>{
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "txt"         => Some(Self::Text),
+            "md"          => Some(Self::Markdown),
+            "csv"         => Some(Self::Csv),
+            _             => None,
+        }
+    }
+}
+
+// strip_markup ////////////////////////////////////////
+/// removes pango markup tags and decodes entities
+pub fn strip_markup(text: &str) -> String{
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '<' => { for c in chars.by_ref() { if c == '>' { break; } } },
+            '&' => {
+                let mut entity = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ';' { closed = true; break; }
+                    entity.push(c);
+                    if entity.len() > 8 { break; }
+                }
+                let decoded = match entity.as_str() {
+                    "amp"  => Some('&'),
+                    "lt"   => Some('<'),
+                    "gt"   => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    e if e.starts_with("#x") => u32::from_str_radix(&e[2..], 16).ok().and_then(char::from_u32),
+                    e if e.starts_with('#')  => e[1..].parse::<u32>().ok().and_then(char::from_u32),
+                    _ => None,
+                };
+                match (closed, decoded) {
+                    (true, Some(d)) => out.push(d),
+                    (true, None)    => { out.push('&'); out.push_str(&entity); out.push(';'); },
+                    (false, _)      => { out.push('&'); out.push_str(&entity); },
+                }
+            },
+            _ => out.push(c),
+        }
+    }
+    out
+}
+// csv_field ///////////////////////////////////////////
+fn csv_field(s: &str) -> String{
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+// scene_name //////////////////////////////////////////
+pub(super) fn scene_name(sn: &Rc<ScenarioNode>) -> String{
+    if let Some(b) = sn.get_scene_bgimg() {
+        if let Some(f) = b.file_name() {
+            return f.to_string_lossy().to_string(); }
+    }
+    "(no image)".to_string()
+}
+// group_names /////////////////////////////////////////
+/// names of groups from the top level to sn (inclusive)
+pub(super) fn group_names(sn: &Rc<ScenarioNode>) -> Vec<String>{
+    let mut names = Vec::new();
+    let mut p = Some(sn.clone());
+    while let Some(n) = p {
+        if let Some(name) = n.get_group_name() {
+            names.push(name); }
+        p = ScenarioNode::get_container(&n);
+    }
+    names.reverse();
+    names
+}
+
+// Transcript //////////////////////////////////////////
+struct Transcript{
+    format      : TranscriptFormat,
+    keep_markup : bool,
+    out         : String,
+    groups      : Vec<String>,
+    scene       : String,
+}
+impl Transcript{
+    fn text(&self, sn: &Rc<ScenarioNode>) -> String{
+        let text = sn.get_mat_text().unwrap_or_default();
+        if self.keep_markup { text } else { strip_markup(&text) }
+    }
+    fn group(&mut self, sn: &Rc<ScenarioNode>){
+        self.groups = group_names(sn);
+        // an unnamed group has no heading
+        let name  = if let Some(n) = sn.get_group_name() { n } else { return; };
+        let level = self.groups.len();
+        match self.format {
+            TranscriptFormat::Text     => {
+                let bar = "=".repeat(level + 1);
+                self.out += &format!("{bar} {name} {bar}\n\n"); },
+            TranscriptFormat::Markdown => {
+                self.out += &format!("{} {name}\n\n", "#".repeat(level.min(6))); },
+            TranscriptFormat::Csv      => (),
+        }
+    }
+    fn scene(&mut self, sn: &Rc<ScenarioNode>){
+        let container = ScenarioNode::get_container(sn);
+        self.groups = if let Some(p) = container { group_names(&p) } else { vec![] };
+        self.scene  = scene_name(sn);
+        match self.format {
+            TranscriptFormat::Text     => {
+                self.out += &format!("-- scene: {} --\n\n", self.scene); },
+            TranscriptFormat::Markdown => {
+                let level = (self.groups.len() + 1).min(6);
+                self.out += &format!("{} Scene: {}\n\n", "#".repeat(level), self.scene); },
+            TranscriptFormat::Csv      => (),
+        }
+    }
+    fn page(&mut self, page: Option<usize>, mats: &[Rc<ScenarioNode>]){
+        let page_str = page.map(|p| format!("{:04}", p)).unwrap_or_default();
+        match self.format {
+            TranscriptFormat::Text     => {
+                if page.is_some() {
+                    self.out += &format!("[{page_str}]\n"); }
+                for m in mats {
+                    let text = self.text(m);
+                    if let Some(s) = m.get_mat_speaker() {
+                        self.out += &format!("{s}: {text}\n");
+                    } else {
+                        self.out += &format!("{text}\n");
+                    }
+                }
+                self.out += "\n";
+            },
+            TranscriptFormat::Markdown => {
+                if page.is_some() {
+                    self.out += &format!("**{page_str}**\n\n"); }
+                for m in mats {
+                    let text = self.text(m).replace('\n', "  \n");
+                    if let Some(s) = m.get_mat_speaker() {
+                        self.out += &format!("**{s}**: {text}\n\n");
+                    } else {
+                        self.out += &format!("{text}\n\n");
+                    }
+                }
+            },
+            TranscriptFormat::Csv      => {
+                for m in mats {
+                    let text = self.text(m);
+                    self.out += &format!("{},{},{},{},{}\n",
+                                         page_str,
+                                         csv_field(&self.groups.join(" / ")),
+                                         csv_field(&self.scene),
+                                         csv_field(&m.get_mat_speaker().unwrap_or_default()),
+                                         csv_field(&text));
+                }
+            },
+        }
+    }
+}
+// child_mats //////////////////////////////////////////
+/// mats directly under a page or a scene
+pub(super) fn child_mats(sn: &Rc<ScenarioNode>) -> Vec<Rc<ScenarioNode>>{
+    let mut mats = Vec::new();
+    let mut p = sn.child.borrow().clone();
+    while let Some(n) = p {
+        if n.is_mat() { mats.push(n.clone()); }
+        p = n.neighbor.borrow().clone();
+    }
+    mats
+}
+
+// transcript //////////////////////////////////////////
+pub fn transcript(root: &Rc<ScenarioNode>, format: TranscriptFormat, keep_markup: bool) -> String{
+    let mut t = Transcript{
+        format,
+        keep_markup,
+        out    : String::new(),
+        groups : Vec::new(),
+        scene  : String::new(),
+    };
+    if format == TranscriptFormat::Csv {
+        t.out += "page,group,scene,speaker,text\n"; }
+
+    let mut img_seq = 0;
+    let mut vec = vec![root.clone()];
+    while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+        match &*sn.value.borrow() {
+            Item::Group(_) => { t.group(&sn); },
+            Item::Scene(_) => {
+                t.scene(&sn);
+                let mats = child_mats(&sn);
+                if !mats.is_empty() {
+                    t.page(None, &mats); }
+            },
+            Item::Page(_) | Item::Pmat(_) => {
+                let mats = if sn.is_pmat() { vec![sn.clone()] } else { child_mats(&sn) };
+                t.page(Some(img_seq), &mats);
+                img_seq += 1;
+            },
+            _ => (),
+        }
+    }
+    t.out
+}
+// export_transcript ///////////////////////////////////
+pub fn export_transcript(root: &Rc<ScenarioNode>, path: &Path, keep_markup: bool) -> anyhow::Result<()>{
+    let format = TranscriptFormat::from_path(path)
+        .ok_or_else(|| anyhow::anyhow!("unknown transcript format: {path:?}"))?;
+    std::fs::write(path, transcript(root, format, keep_markup))?;
+    Ok(())
+}
+
+// test ////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use crate::import_util::script_import;
+
+    #[test]
+    fn test_strip_markup() {
+        assert_eq!(strip_markup("<b>bold</b> &amp; <span foreground=\"red\">red</span>"), "bold & red");
+        assert_eq!(strip_markup("&#65;&#x42; &unknown; a & b"), "AB &unknown; a & b");
+    }
+
+    #[test]
+    fn test_transcript() {
+        let script = "# part 0\n\
+                      # part 1\n\
+                      @image img/a.png\n\
+                      <b>hello</b>\n\
+                      \n\
+                      mat 1\n\
+                      --\n\
+                      mat, \"2\"\n";
+        let root = script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+        let root = root.neighbor.borrow().clone().unwrap(); // part 1
+        let pmat = root.child.borrow().clone().unwrap().child.borrow().clone().unwrap();
+        pmat.set_mat_speaker(Some("Alice".to_string()));
+
+        assert_eq!(transcript(&root, TranscriptFormat::Text, false),
+                   "== part 1 ==\n\n-- scene: a.png --\n\n[0000]\nAlice: hello\n\n[0001]\nmat 1\nmat, \"2\"\n\n");
+        assert_eq!(transcript(&root, TranscriptFormat::Csv, true),
+                   "page,group,scene,speaker,text\n\
+                    0000,part 1,a.png,Alice,<b>hello</b>\n\
+                    0001,part 1,a.png,,mat 1\n\
+                    0001,part 1,a.png,,\"mat, \"\"2\"\"\"\n");
+        let md = transcript(&root, TranscriptFormat::Markdown, false);
+        assert!(md.starts_with("# part 1\n\n## Scene: a.png\n\n**0000**\n\n**Alice**: hello\n\n"));
+
+        root.set_group_name("");
+        assert!(transcript(&root, TranscriptFormat::Text, false).starts_with("-- scene: a.png --\n\n"));
+        assert!(transcript(&root, TranscriptFormat::Markdown, false).starts_with("# Scene: a.png\n\n"));
+    }
+}
@@ -0,0 +1,177 @@
+//! frame sequence of all pages for a local video encoder
+//!
+//! every frame is written as png at a constant frame rate. the timing is
+//! also written as an ffconcat file (runs of identical frames) and a CMX3600
+//! EDL (one event per page). transitions are rendered into the frames.
+
+use crate::isv2_parameter::VideoSetting;
+use crate::scenario_node::Timing;
+use crate::scenario_node::Transition;
+
+// Span ////////////////////////////////////////////////
+/// frames of a page. the transition from the previous page takes its first frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span{
+    pub start             : u64,
+    pub frames            : u64,
+    pub transition        : Transition,
+    pub transition_frames : u64,
+}
+fn ms_to_frame(ms: u64, fps: u32) -> u64{
+    (ms * fps as u64 + 500) / 1000
+}
+/// spans of pages. durations are accumulated in ms so that rounding does not drift.
+pub fn spans(timings: &[Timing], s: &VideoSetting) -> Vec<Span>{
+    let fps    = s.fps.max(1);
+    let mut ms = 0u64;
+    timings.iter().enumerate().map(|(i, t)|{
+        let start = ms_to_frame(ms, fps);
+        ms += t.duration_ms.unwrap_or(s.duration_ms).max(1) as u64;
+        let frames     = ms_to_frame(ms, fps) - start;
+        let transition = if i == 0 { Transition::Cut } else { t.transition };
+        let transition_frames = match transition {
+            Transition::Cut => 0,
+            _               => ms_to_frame(t.transition_ms as u64, fps).min(frames),
+        };
+        Span{ start, frames, transition, transition_frames }
+    }).collect()
+}
+
+// timeline ////////////////////////////////////////////
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layer{
+    Black,
+    Page(usize, Option<usize>), // index and revealed chars, None: all
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoFrame{
+    pub base : Layer,
+    pub over : Option<(Layer, f64)>, // painted on base with the opacity
+}
+/// every frame of the pages whose typewriter text lengths are text_lens
+pub fn timeline(text_lens: &[usize], spans: &[Span], s: &VideoSetting) -> Vec<VideoFrame>{
+    let fps = s.fps.max(1) as u64;
+    let cps = s.typewriter_cps.filter(|c| *c > 0).map(|c| c as u64);
+    let reveal = |page: usize, k: u64| {
+        Layer::Page(page, cps.map(|c| (k * c / fps) as usize).filter(|c| *c < text_lens[page])) };
+    let mut frames: Vec<VideoFrame> = Vec::new();
+    for (page, sp) in spans.iter().enumerate() {
+        let prev = match frames.last() {
+            Some(VideoFrame{ base, over: None }) => *base,
+            _ if page > 0                        => Layer::Page(page - 1, None),
+            _                                    => Layer::Black,
+        };
+        let tf = sp.transition_frames;
+        for k in 0..sp.frames {
+            if k >= tf {
+                frames.push(VideoFrame{ base: reveal(page, k - tf), over: None });
+                continue;
+            }
+            let a = (k + 1) as f64 / (tf + 1) as f64;
+            frames.push(match sp.transition {
+                Transition::FadeBlack if a <= 0.5 => VideoFrame{ base: prev, over: Some((Layer::Black, a * 2.0)) },
+                Transition::FadeBlack             => VideoFrame{ base: Layer::Black, over: Some((reveal(page, 0), a * 2.0 - 1.0)) },
+                _                                 => VideoFrame{ base: prev, over: Some((reveal(page, 0), a)) },
+            });
+        }
+    }
+    frames
+}
+
+// frame_name //////////////////////////////////////////
+pub fn frame_name(i: usize) -> String{
+    format!("{:06}.png", i)
+}
+// ffconcat ////////////////////////////////////////////
+/// concat demuxer script of the frames in dir. identical frames are merged
+/// into one entry, and the last file is repeated so that its duration is used.
+pub fn ffconcat(frames: &[VideoFrame], fps: u32, dir: &str) -> String{
+    let mut out = String::from("ffconcat version 1.0\n");
+    let mut i = 0;
+    while i < frames.len() {
+        let run = frames[i..].iter().take_while(|f| **f == frames[i]).count();
+        out += &format!("file '{}/{}'\nduration {:.6}\n", dir, frame_name(i), run as f64 / fps.max(1) as f64);
+        if i + run == frames.len() {
+            out += &format!("file '{}/{}'\n", dir, frame_name(i)); }
+        i += run;
+    }
+    out
+}
+// edl /////////////////////////////////////////////////
+fn timecode(frame: u64, fps: u32) -> String{
+    let fps = fps.max(1) as u64;
+    let s   = frame / fps;
+    format!("{:02}:{:02}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60, frame % fps)
+}
+/// CMX3600 edl of the rendered sequence (reel AX), one cut per page.
+/// rendered transitions are noted as comments.
+pub fn edl(title: &str, names: &[String], spans: &[Span], fps: u32) -> String{
+    let mut out = format!("TITLE: {}\nFCM: NON-DROP FRAME\n\n", title);
+    for (i, (name, sp)) in names.iter().zip(spans).enumerate() {
+        let (tc_in, tc_out) = (timecode(sp.start, fps), timecode(sp.start + sp.frames, fps));
+        out += &format!("{:03}  AX       V     C        {} {} {} {}\n", (i + 1) % 1000, tc_in, tc_out, tc_in, tc_out);
+        out += &format!("* FROM CLIP NAME: {}\n", name);
+        match sp.transition {
+            Transition::Cut       => (),
+            Transition::Crossfade => out += &format!("* TRANSITION: CROSSFADE {} FRAMES (RENDERED)\n", sp.transition_frames),
+            Transition::FadeBlack => out += &format!("* TRANSITION: FADE BLACK {} FRAMES (RENDERED)\n", sp.transition_frames),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spans() {
+        let s = VideoSetting{ fps: 30, duration_ms: 1000, typewriter_cps: None };
+        let t = |duration_ms, transition, transition_ms| Timing{ duration_ms, transition, transition_ms };
+        let sp = spans(&[t(Some(1010), Transition::Crossfade, 500),
+                         t(Some(1010), Transition::Crossfade, 500),
+                         t(None, Transition::FadeBlack, 5000)], &s);
+        assert_eq!(sp.iter().map(|s| (s.start, s.frames, s.transition_frames)).collect::<Vec<_>>(),
+                   vec![(0, 30, 0), (30, 31, 15), (61, 30, 30)]); // 30.3 + 30.3 frames do not drift
+        assert_eq!(sp[0].transition, Transition::Cut);
+    }
+
+    #[test]
+    fn test_timeline() {
+        let s  = VideoSetting{ fps: 10, duration_ms: 500, typewriter_cps: Some(20) };
+        let sp = vec![Span{ start: 0, frames: 3, transition: Transition::Cut,       transition_frames: 0 },
+                      Span{ start: 3, frames: 3, transition: Transition::Crossfade, transition_frames: 1 },
+                      Span{ start: 6, frames: 3, transition: Transition::FadeBlack, transition_frames: 3 }];
+        let f = |base, over| VideoFrame{ base, over };
+        assert_eq!(timeline(&[3, 0, 1], &sp, &s),
+                   vec![f(Layer::Page(0, Some(0)), None),
+                        f(Layer::Page(0, Some(2)), None),
+                        f(Layer::Page(0, None),    None),
+                        f(Layer::Page(0, None),    Some((Layer::Page(1, None), 0.5))),
+                        f(Layer::Page(1, None),    None),
+                        f(Layer::Page(1, None),    None),
+                        f(Layer::Page(1, None),    Some((Layer::Black, 0.5))),
+                        f(Layer::Page(1, None),    Some((Layer::Black, 1.0))),
+                        f(Layer::Black,            Some((Layer::Page(2, Some(0)), 0.5)))]);
+    }
+
+    #[test]
+    fn test_timing_files() {
+        let p = |i| VideoFrame{ base: Layer::Page(i, None), over: None };
+        let c = ffconcat(&[p(0), p(0), p(1)], 25, "frames");
+        assert_eq!(c, "ffconcat version 1.0\n\
+                       file 'frames/000000.png'\nduration 0.080000\n\
+                       file 'frames/000002.png'\nduration 0.040000\n\
+                       file 'frames/000002.png'\n");
+        let sp = vec![Span{ start: 0,  frames: 45, transition: Transition::Cut,       transition_frames: 0 },
+                      Span{ start: 45, frames: 30, transition: Transition::Crossfade, transition_frames: 6 }];
+        let e = edl("t", &["0000 a".to_string(), "0001 b".to_string()], &sp, 30);
+        assert_eq!(e.lines().collect::<Vec<_>>(),
+                   vec!["TITLE: t", "FCM: NON-DROP FRAME", "",
+                        "001  AX       V     C        00:00:00:00 00:00:01:15 00:00:00:00 00:00:01:15",
+                        "* FROM CLIP NAME: 0000 a",
+                        "002  AX       V     C        00:00:01:15 00:00:02:15 00:00:01:15 00:00:02:15",
+                        "* FROM CLIP NAME: 0001 b",
+                        "* TRANSITION: CROSSFADE 6 FRAMES (RENDERED)"]);
+    }
+}
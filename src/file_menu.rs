@@ -3,8 +3,11 @@ pub mod actions{
     use std::fs::OpenOptions;
     use std::io::BufReader;
     use std::io;
+    use std::path::Path;
     use std::path::PathBuf;
+    use std::rc::Rc;
 
+    use gtk::AlertDialog;
     use gtk::FileDialog;
     use gtk::FileFilter;
     use gtk::SingleSelection;
@@ -14,25 +17,49 @@ pub mod actions{
     use gtk::gio::File;
     use gtk::gio::ListStore;
     use gtk::gio::SimpleAction;
+    use gtk::glib::clone;
     use gtk::glib::error::Error;
     use gtk::glib::variant::Variant;
     use gtk::prelude::*;
 
+    use anyhow::Context;
     use serde::{Deserialize, Serialize};
     use serde_json::ser::Formatter;
 
+    use crate::export_util::image_format::Content;
+    use crate::export_util::image_format::ImageFormat;
+    use crate::export_util::profile::ExportTarget;
+    use crate::export_util::subset::Subset;
+    use crate::export_util::transcript;
+    use crate::import_util::image_import;
+    use crate::import_util::script_import;
     use crate::isv2_mediator::Isv2Mediator;
     use crate::isv2_parameter::Isv2Parameter;
     use crate::isv2_parameter::Isv2ParameterSerde;
+    use crate::operation_history::OperationHistory;
+    use crate::operation_history::OperationHistoryItem;
     use crate::preview_window::PreviewWindow;
     use crate::scenario_node::ScenarioNode;
     use crate::scenario_node::ScenarioNodeSerde;
+    use crate::scenario_node::Rescale;
     use crate::scenario_node_object::ScenarioNodeObject;
+    use crate::translation_util::translation;
     use crate::tree_util::tree_manipulate;
 
     pub const ACT_FILE_SAVE_AS    : &str = "file_save_as";
     pub const ACT_FILE_OPEN       : &str = "file_open";
     pub const ACT_FILE_EXPORT_IMG : &str = "file_export_img";
+    pub const ACT_FILE_EXPORT_IMG_ALL_LANG : &str = "file_export_img_all_lang";
+    pub const ACT_FILE_EXPORT_IMG_SVG : &str = "file_export_img_svg";
+    pub const ACT_FILE_EXPORT_IMG_ORA : &str = "file_export_img_ora";
+    pub const ACT_FILE_EXPORT_IMG_CONTENT : &str = "file_export_img_content";
+    pub const ACT_FILE_EXPORT_PDF : &str = "file_export_pdf";
+    pub const ACT_FILE_EXPORT_PACKAGE : &str = "file_export_package";
+    pub const ACT_FILE_EXPORT_TRANSCRIPT : &str = "file_export_transcript";
+    pub const ACT_FILE_EXPORT_TRANSLATION : &str = "file_export_translation";
+    pub const ACT_FILE_IMPORT_TRANSLATION : &str = "file_import_translation";
+    pub const ACT_FILE_IMPORT_SCRIPT : &str = "file_import_script";
+    pub const ACT_FILE_IMPORT_IMG_DIR : &str = "file_import_img_dir";
 
     // formatter ///////////////////////////////////////////
     #[derive(Debug, Clone, Copy, PartialEq)]
@@ -154,17 +181,27 @@ pub mod actions{
             param.set_property("project_dir", path_buf);
 
             // write project.json
-            let param_ser = Isv2ParameterSerde::from(&param);
-            let prj_ser   = ProjectFileSerde::from(param_ser, sn_ser);
-            let out_file  = {
-                if let Ok(f) = OpenOptions::new().read(false).write(true).create(true).open(file.path().unwrap()) { f }
-                else { return; } };
-            let mut isv2ser = serde_json::ser::Serializer::with_formatter(out_file, Isv2Formatter::new());
-            if let Err(_) = prj_ser.serialize(&mut isv2ser){
-                println!("(dialog_save_func) write failed! {:?}, {:?}", file.path().unwrap(), file);
+            if let Err(e) = write_project_file(&file.path().unwrap(), &param, sn_ser){
+                println!("(dialog_save_func) write failed! {:?}, {:?}", e, file);
             }
         })
     }
+    // write_project_file //////////////////////////////////
+    pub fn write_project_file(path  : &Path,
+                              param : &Isv2Parameter,
+                              sn_ser: Option<Vec<ScenarioNodeSerde>>) -> anyhow::Result<()>{
+        if let (true, Some(sn_ser)) = (param.normalized_geometry(), &sn_ser) {
+            Rescale::to_normalized((param.target_width(), param.target_height())).serde(sn_ser);
+        }
+        let param_ser = Isv2ParameterSerde::from(param);
+        let prj_ser   = ProjectFileSerde::from(param_ser, sn_ser);
+        let out_file  = OpenOptions::new().read(false).write(true).create(true).truncate(true).open(path)
+            .with_context(||format!("opening {path:?} failed"))?;
+        let mut isv2ser = serde_json::ser::Serializer::with_formatter(out_file, Isv2Formatter::new());
+        prj_ser.serialize(&mut isv2ser)
+            .with_context(||format!("writing {path:?} failed"))?;
+        Ok(())
+    }
     // act_save_func ///////////////////////////////////////
     fn act_save_func(store: ListStore,
                      param: Isv2Parameter,
@@ -205,44 +242,46 @@ pub mod actions{
             path_buf.pop();
             param.set_property("project_dir", path_buf);
 
-            let in_file = {
-                if let Ok(f) = std::fs::File::open(file.path().unwrap()) { f }
-                else { println!("(dialog_open_func) cannnot open file: {:?}", file.path().unwrap()); return; } };
-            let reader = BufReader::new(in_file);
-
-            /* ---- serde_json ---- */
-            // let prj_ser : ProjectFileSerde = {
-            //     if let Ok(p) = serde_json::from_reader(reader) { p }
-            //     else { println!("(dialog_open_func) parse failed!"); return; }};
-            /* ---- serde_json ---- */
-
-            /* ---- serde_path_to_error ----*/
-            let prj_ser = &mut serde_json::Deserializer::from_reader(reader);
-            let result: Result<ProjectFileSerde, _> = serde_path_to_error::deserialize(prj_ser);
-            if let Err(err) = result {
-                let err_path = err.path().to_string();
-                println!("(dialog_open_func) parse failed! {:?}", err_path);
-                return;
-            }
-            let prj_ser = result.expect("ProjectFileSerde is expected");
-            /* ---------------------------- */
-
-            // deserialize of parameter
-            param.copy_from_serde(&prj_ser.param_ser);
+            let root = match read_project_file(&file.path().unwrap(), &param) {
+                Ok(r)  => r,
+                Err(e) => { println!("(dialog_open_func) {:?}", e); return; },
+            };
 
             // deserialize of store
             store.remove_all();
-            if let Some(sn_tree) = prj_ser.sn_ser {
-                if let Some(sn_rev) = ScenarioNode::from_serde(sn_tree){
-                    let root_sno = ScenarioNodeObject::new_from(sn_rev);
-                    tree_manipulate::append_neighbors( &store, root_sno.get_node(), 0);
-                    mediator.emit_by_name::<()>("sno-selected", &[&selection]);
-                } else {
-                    mediator.emit_by_name::<()>("unset-sno", &[&selection]);
-                }
+            if let Some(sn_rev) = root {
+                let root_sno = ScenarioNodeObject::new_from(sn_rev);
+                tree_manipulate::append_neighbors( &store, root_sno.get_node(), 0);
+                mediator.emit_by_name::<()>("sno-selected", &[&selection]);
+            } else {
+                mediator.emit_by_name::<()>("unset-sno", &[&selection]);
             }
         })
     }
+    // read_project_file ///////////////////////////////////
+    /// reads the parameters into param and returns the tree of the project
+    pub fn read_project_file(path  : &Path,
+                             param : &Isv2Parameter) -> anyhow::Result<Option<Rc<ScenarioNode>>>{
+        let in_file = std::fs::File::open(path).with_context(||format!("opening {path:?} failed"))?;
+        let reader  = BufReader::new(in_file);
+
+        let prj_ser = &mut serde_json::Deserializer::from_reader(reader);
+        let prj_ser: ProjectFileSerde = serde_path_to_error::deserialize(prj_ser)
+            .map_err(|e| anyhow::anyhow!("parsing {path:?} failed at {}", e.path()))?;
+
+        // deserialize of parameter
+        param.copy_from_serde(&prj_ser.param_ser);
+
+        // deserialize of tree
+        let sn_tree = if let Some(t) = prj_ser.sn_ser { t } else { return Ok(None); };
+        if param.normalized_geometry() {
+            Rescale::from_normalized((param.target_width(), param.target_height())).serde(&sn_tree);
+        }
+        let root = ScenarioNode::from_serde(sn_tree);
+        if let Some(r) = &root {
+            tree_manipulate::fix_loaded_ids(r); }
+        Ok(root)
+    }
     // act_open_func ///////////////////////////////////////
     fn act_open_func(store     : ListStore,
                      param     : Isv2Parameter,
@@ -275,22 +314,447 @@ pub mod actions{
 
         act_open
     }
+    // append_imported /////////////////////////////////////
+    /// lists the top level nodes which an import appended after the last one in store,
+    /// or the whole tree from root when store is empty, as one operation to be undone
+    fn append_imported(store   : &ListStore,
+                       history : &OperationHistory,
+                       last    : Option<ScenarioNodeObject>,
+                       root    : Rc<ScenarioNode>){
+        let n = store.n_items();
+        let first = if let Some(l) = &last { l.get_node().neighbor.borrow().clone() } else { Some(root) };
+        if let Some(f) = first {
+            tree_manipulate::append_neighbors(store, f, n as i32); }
+        let new_snos = (n..store.n_items())
+            .filter_map(|i| store.item(i).and_downcast::<ScenarioNodeObject>())
+            .map(Rc::new).collect();
+        history.push(OperationHistoryItem::new_import(store, last.as_ref(), new_snos));
+    }
+    // dialog_import_script_func ///////////////////////////
+    fn dialog_import_script_func(store     : ListStore,
+                                 param     : Isv2Parameter,
+                                 mediator  : Isv2Mediator,
+                                 selection : SingleSelection,
+                                 history   : Rc<OperationHistory>
+    ) -> Box<dyn FnOnce(Result<File, Error>) + 'static>{
+        Box::new( move|result| {
+            let file   = if let Ok(f) = result { f } else { return; };
+            let script = if let Some(p) = file.path() { p } else { return; };
+
+            let root: Option<Rc<ScenarioNode>> = store.item(0).map(|sno|{
+                sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() });
+            let last = store.item(store.n_items().saturating_sub(1)).and_downcast::<ScenarioNodeObject>();
+            let root = match script_import::import_script_file(&script,
+                                                               root,
+                                                               &param.property::<PathBuf>("project_dir")) {
+                Ok(Some(r)) => r,
+                Ok(None)    => { println!("(dialog_import_script_func) no node is imported"); return; },
+                Err(e)      => { println!("(dialog_import_script_func) {:?}", e); return; },
+            };
+
+            append_imported(&store, &history, last, root);
+            mediator.emit_by_name::<()>("sno-selected", &[&selection]);
+        })
+    }
+    // act_import_script ///////////////////////////////////
+    pub fn act_import_script(store     : ListStore,
+                             param     : Isv2Parameter,
+                             mediator  : Isv2Mediator,
+                             selection : SingleSelection,
+                             pwin      : impl IsA<Window>,
+                             history   : Rc<OperationHistory>
+    ) -> SimpleAction{
+        let act_import_script = SimpleAction::new(ACT_FILE_IMPORT_SCRIPT, None);
+        act_import_script.connect_activate(move|_act, _val|{
+            let file_dialog = FileDialog::builder().modal(true).build();
+            let param_path = param.property::<PathBuf>("project_dir");
+            if param_path.exists() && param_path.is_dir() {
+                file_dialog.set_initial_folder(Some(&gio::File::for_path(param_path)));
+            }
+            let file_filter = FileFilter::new();
+            file_filter.add_pattern("*.txt");
+            file_filter.add_pattern("*.md");
+            file_filter.set_name(Some("script(txt, md)"));
+            let model = gio::ListStore::with_type(FileFilter::static_type());
+            model.append(&file_filter);
+            file_dialog.set_filters(Some(&model));
+            file_dialog.set_default_filter(Some(&file_filter));
+
+            file_dialog.open(Some(&pwin),
+                             None::<Cancellable>.as_ref(),
+                             dialog_import_script_func(store.clone(),
+                                                       param.clone(),
+                                                       mediator.clone(),
+                                                       selection.clone(),
+                                                       history.clone()));
+        });
+        act_import_script
+    }
+    // dialog_import_img_dir_func //////////////////////////
+    fn dialog_import_img_dir_func(store     : ListStore,
+                                  param     : Isv2Parameter,
+                                  mediator  : Isv2Mediator,
+                                  selection : SingleSelection,
+                                  history   : Rc<OperationHistory>
+    ) -> Box<dyn FnOnce(Result<File, Error>) + 'static>{
+        Box::new( move|result| {
+            let file = if let Ok(f) = result { f } else { return; };
+            let dir  = if let Some(p) = file.path() { p } else { return; };
+
+            let root: Option<Rc<ScenarioNode>> = store.item(0).map(|sno|{
+                sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() });
+            let last = store.item(store.n_items().saturating_sub(1)).and_downcast::<ScenarioNodeObject>();
+            let root = match image_import::import_image_folder(&dir,
+                                                               root,
+                                                               &param.property::<PathBuf>("project_dir")) {
+                Ok((Some(r), n)) if n > 0 => r,
+                Ok(_)  => { println!("(dialog_import_img_dir_func) no image is imported"); return; },
+                Err(e) => { println!("(dialog_import_img_dir_func) {:?}", e); return; },
+            };
+
+            append_imported(&store, &history, last, root);
+            mediator.emit_by_name::<()>("sno-selected", &[&selection]);
+        })
+    }
+    // act_import_img_dir //////////////////////////////////
+    pub fn act_import_img_dir(store     : ListStore,
+                              param     : Isv2Parameter,
+                              mediator  : Isv2Mediator,
+                              selection : SingleSelection,
+                              pwin      : impl IsA<Window>,
+                              history   : Rc<OperationHistory>
+    ) -> SimpleAction{
+        let act_import_img_dir = SimpleAction::new(ACT_FILE_IMPORT_IMG_DIR, None);
+        act_import_img_dir.connect_activate(move|_act, _val|{
+            let file_dialog = FileDialog::builder().modal(true).build();
+            let param_path = param.property::<PathBuf>("project_dir");
+            if param_path.exists() && param_path.is_dir() {
+                file_dialog.set_initial_folder(Some(&gio::File::for_path(param_path)));
+            }
+            file_dialog.select_folder(Some(&pwin),
+                                      None::<Cancellable>.as_ref(),
+                                      dialog_import_img_dir_func(store.clone(),
+                                                                 param.clone(),
+                                                                 mediator.clone(),
+                                                                 selection.clone(),
+                                                                 history.clone()));
+        });
+        act_import_img_dir
+    }
+    // dialog_export_transcript_func ///////////////////////
+    fn dialog_export_transcript_func(store : ListStore,
+                                     pwin  : Window
+    ) -> Box<dyn FnOnce(Result<File, Error>) + 'static>{
+        Box::new( move|result| {
+            let file = if let Ok(f) = result { f } else { return; };
+            let path = if let Some(p) = file.path() { p } else { return; };
+            let root = if let Some(sno) = store.item(0) {
+                sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                else { println!("(dialog_export_transcript_func) store has noitem"); return; };
+
+            let dialog = AlertDialog::builder().modal(true).build();
+            dialog.set_message("Pango markup in the texts");
+            dialog.set_buttons(&["Strip markup", "Keep markup"]);
+            dialog.set_default_button(0);
+            dialog.set_cancel_button(0);
+            gtk::glib::spawn_future_local(async move {
+                let keep_markup = dialog.choose_future(Some(&pwin)).await == Ok(1);
+                if let Err(e) = transcript::export_transcript(&root, &path, keep_markup) {
+                    println!("(dialog_export_transcript_func) {:?}", e);
+                }
+            });
+        })
+    }
+    // act_export_transcript ///////////////////////////////
+    pub fn act_export_transcript(store : ListStore,
+                                 param : Isv2Parameter,
+                                 pwin  : impl IsA<Window>
+    ) -> SimpleAction{
+        let act_export_transcript = SimpleAction::new(ACT_FILE_EXPORT_TRANSCRIPT, None);
+        act_export_transcript.connect_activate(move|_act, _val|{
+            let file_dialog = FileDialog::builder().modal(true).initial_name("transcript.txt").build();
+            let param_path = param.property::<PathBuf>("project_dir");
+            if param_path.exists() && param_path.is_dir() {
+                file_dialog.set_initial_folder(Some(&gio::File::for_path(param_path)));
+            }
+            let model = gio::ListStore::with_type(FileFilter::static_type());
+            for (name, pattern) in [("text(txt)", "*.txt"), ("Markdown(md)", "*.md"), ("CSV(csv)", "*.csv")] {
+                let file_filter = FileFilter::new();
+                file_filter.add_pattern(pattern);
+                file_filter.set_name(Some(name));
+                model.append(&file_filter);
+            }
+            file_dialog.set_filters(Some(&model));
+
+            file_dialog.save(Some(&pwin),
+                             None::<Cancellable>.as_ref(),
+                             dialog_export_transcript_func(store.clone(),
+                                                           pwin.clone().upcast::<Window>()));
+        });
+        act_export_transcript
+    }
+    // translation_file_dialog /////////////////////////////
+    fn translation_file_dialog(param: &Isv2Parameter) -> FileDialog{
+        let file_dialog = FileDialog::builder().modal(true).build();
+        let param_path = param.property::<PathBuf>("project_dir");
+        if param_path.exists() && param_path.is_dir() {
+            file_dialog.set_initial_folder(Some(&gio::File::for_path(param_path)));
+        }
+        let model = gio::ListStore::with_type(FileFilter::static_type());
+        for (name, patterns) in [("gettext PO(po)", vec!["*.po"]), ("XLIFF(xlf, xliff)", vec!["*.xlf", "*.xliff"])] {
+            let file_filter = FileFilter::new();
+            for p in patterns { file_filter.add_pattern(p); }
+            file_filter.set_name(Some(name));
+            model.append(&file_filter);
+        }
+        file_dialog.set_filters(Some(&model));
+        file_dialog
+    }
+    // act_export_translation //////////////////////////////
+    pub fn act_export_translation(store : ListStore,
+                                  param : Isv2Parameter,
+                                  pwin  : impl IsA<Window>
+    ) -> SimpleAction{
+        let act_export_translation = SimpleAction::new(ACT_FILE_EXPORT_TRANSLATION, None);
+        act_export_translation.connect_activate(move|_act, _val|{
+            let file_dialog = translation_file_dialog(&param);
+            // the file name is the language
+            let lang = param.property::<String>("language");
+            file_dialog.set_initial_name(Some(&format!("{}.po", if lang.is_empty() { "en" } else { &lang })));
+            file_dialog.save(Some(&pwin),
+                             None::<Cancellable>.as_ref(),
+                             clone!(@strong store, @strong param => move|result|{
+                                 let file = if let Ok(f) = result { f } else { return; };
+                                 let path = if let Some(p) = file.path() { p } else { return; };
+                                 let root = if let Some(sno) = store.item(0) {
+                                     sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                                     else { println!("(act_export_translation) store has noitem"); return; };
+                                 if let Err(e) = translation::export_translation(&root, &param, &path) {
+                                     println!("(act_export_translation) {:?}", e);
+                                 }
+                             }));
+        });
+        act_export_translation
+    }
+    // dialog_import_translation_func //////////////////////
+    fn dialog_import_translation_func(store     : ListStore,
+                                      param     : Isv2Parameter,
+                                      mediator  : Isv2Mediator,
+                                      selection : SingleSelection,
+                                      pwin      : Window
+    ) -> Box<dyn FnOnce(Result<File, Error>) + 'static>{
+        Box::new( move|result| {
+            let file = if let Ok(f) = result { f } else { return; };
+            let path = if let Some(p) = file.path() { p } else { return; };
+            let root = if let Some(sno) = store.item(0) {
+                sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                else { println!("(dialog_import_translation_func) store has noitem"); return; };
+
+            let dialog = AlertDialog::builder().modal(true).build();
+            match translation::import_translation(&root, &param, &path) {
+                Ok(report) => {
+                    println!("(dialog_import_translation_func) {}\n{}", report.summary(), report.detail(usize::MAX));
+                    dialog.set_message(&report.summary());
+                    dialog.set_detail(&report.detail(20));
+                    mediator.emit_by_name::<()>("sno-selected", &[&selection]);
+                },
+                Err(e) => {
+                    println!("(dialog_import_translation_func) {:?}", e);
+                    dialog.set_message("importing translation failed");
+                    dialog.set_detail(&format!("{:#}", e));
+                },
+            }
+            dialog.show(Some(&pwin));
+        })
+    }
+    // act_import_translation //////////////////////////////
+    pub fn act_import_translation(store     : ListStore,
+                                  param     : Isv2Parameter,
+                                  mediator  : Isv2Mediator,
+                                  selection : SingleSelection,
+                                  pwin      : impl IsA<Window>
+    ) -> SimpleAction{
+        let act_import_translation = SimpleAction::new(ACT_FILE_IMPORT_TRANSLATION, None);
+        act_import_translation.connect_activate(move|_act, _val|{
+            let file_dialog = translation_file_dialog(&param);
+            file_dialog.open(Some(&pwin),
+                             None::<Cancellable>.as_ref(),
+                             dialog_import_translation_func(store.clone(),
+                                                            param.clone(),
+                                                            mediator.clone(),
+                                                            selection.clone(),
+                                                            pwin.clone().upcast::<Window>()));
+        });
+        act_import_translation
+    }
     // act_export_img //////////////////////////////////////
-    pub fn act_export_img(store : ListStore,
-                          param : Isv2Parameter,
-                          pwin  : PreviewWindow,
-                          root  : impl IsA<Window>) -> SimpleAction{
-        let act_export_img = SimpleAction::new(ACT_FILE_EXPORT_IMG, None);
-        act_export_img.connect_activate(move|_act, _val|{
+    fn act_export_img_func(store : ListStore,
+                           param : Isv2Parameter,
+                           pwin  : PreviewWindow,
+                           root  : impl IsA<Window>,
+                           all_languages: bool,
+                           format: ImageFormat) -> Box<dyn Fn(&SimpleAction, Option<&Variant>) + 'static>{
+        Box::new( move|_act, _val|{
             let sn = store.item(0);
             if sn.is_some(){
                 let sn = sn.unwrap().downcast_ref::<ScenarioNodeObject>().expect("sno").get_node();
-                pwin.export_images(&sn, &param, &root);
+                pwin.export_images(&sn, &param, &root, ExportTarget::of_param(&param, all_languages, format), &Subset::default());
             } else {
                 println!("(act_export_img) store has noitem");
             }
 
+        })
+    }
+    pub fn act_export_img(store : ListStore,
+                          param : Isv2Parameter,
+                          pwin  : PreviewWindow,
+                          root  : impl IsA<Window>) -> SimpleAction{
+        let act_export_img = SimpleAction::new(ACT_FILE_EXPORT_IMG, None);
+        act_export_img.connect_activate(act_export_img_func(store, param, pwin, root, false, ImageFormat::Png));
+        act_export_img
+    }
+    // act_export_img_all_lang /////////////////////////////
+    pub fn act_export_img_all_lang(store : ListStore,
+                                   param : Isv2Parameter,
+                                   pwin  : PreviewWindow,
+                                   root  : impl IsA<Window>) -> SimpleAction{
+        let act_export_img = SimpleAction::new(ACT_FILE_EXPORT_IMG_ALL_LANG, None);
+        act_export_img.connect_activate(act_export_img_func(store, param, pwin, root, true, ImageFormat::Png));
+        act_export_img
+    }
+    // act_export_img_svg //////////////////////////////////
+    pub fn act_export_img_svg(store : ListStore,
+                              param : Isv2Parameter,
+                              pwin  : PreviewWindow,
+                              root  : impl IsA<Window>) -> SimpleAction{
+        let act_export_img = SimpleAction::new(ACT_FILE_EXPORT_IMG_SVG, None);
+        act_export_img.connect_activate(move|_act, _val|{
+            let dialog = AlertDialog::builder().modal(true).build();
+            dialog.set_message("Background images of the SVG files");
+            dialog.set_buttons(&["Embed", "Link", "Cancel"]);
+            dialog.set_default_button(0);
+            dialog.set_cancel_button(2);
+            dialog.choose(Some(&root),
+                          None::<Cancellable>.as_ref(),
+                          clone!(@strong store, @strong param, @strong pwin, @strong root => move|result|{
+                              let link_bg = match result {
+                                  Ok(0) => false,
+                                  Ok(1) => true,
+                                  _     => return,
+                              };
+                              let sn = if let Some(sno) = store.item(0) {
+                                  sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                                  else { println!("(act_export_img_svg) store has noitem"); return; };
+                              pwin.export_images(&sn, &param, &root, ExportTarget::of_param(&param, false, ImageFormat::Svg{ link_bg }), &Subset::default());
+                          }));
         });
         act_export_img
     }
+    // act_export_img_ora //////////////////////////////////
+    pub fn act_export_img_ora(store : ListStore,
+                              param : Isv2Parameter,
+                              pwin  : PreviewWindow,
+                              root  : impl IsA<Window>) -> SimpleAction{
+        let act_export_img = SimpleAction::new(ACT_FILE_EXPORT_IMG_ORA, None);
+        act_export_img.connect_activate(act_export_img_func(store, param, pwin, root, false, ImageFormat::Ora));
+        act_export_img
+    }
+    // act_export_img_content //////////////////////////////
+    /// exports png without the scene background, or with the texts only
+    pub fn act_export_img_content(store : ListStore,
+                                  param : Isv2Parameter,
+                                  pwin  : PreviewWindow,
+                                  root  : impl IsA<Window>) -> SimpleAction{
+        let act_export_img = SimpleAction::new(ACT_FILE_EXPORT_IMG_CONTENT, None);
+        act_export_img.connect_activate(move|_act, _val|{
+            let dialog = AlertDialog::builder().modal(true).build();
+            dialog.set_message("Contents of the images");
+            dialog.set_buttons(&["Transparent background", "Text only", "Cancel"]);
+            dialog.set_default_button(0);
+            dialog.set_cancel_button(2);
+            dialog.choose(Some(&root),
+                          None::<Cancellable>.as_ref(),
+                          clone!(@strong store, @strong param, @strong pwin, @strong root => move|result|{
+                              let content = match result {
+                                  Ok(0) => Content::Transparent,
+                                  Ok(1) => Content::TextOnly,
+                                  _     => return,
+                              };
+                              let sn = if let Some(sno) = store.item(0) {
+                                  sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                                  else { println!("(act_export_img_content) store has noitem"); return; };
+                              let targets = ExportTarget::of_param(&param, false, ImageFormat::Png).into_iter()
+                                  .map(|t| ExportTarget{ content, ..t }).collect();
+                              pwin.export_images(&sn, &param, &root, targets, &Subset::default());
+                          }));
+        });
+        act_export_img
+    }
+    // act_export_doc_func /////////////////////////////////
+    /// asks an output file and exports all pages into it by export
+    fn act_export_doc_func(store   : ListStore,
+                           param   : Isv2Parameter,
+                           pwin    : PreviewWindow,
+                           root    : impl IsA<Window>,
+                           filters : &[(&str, &str)],
+                           export  : fn(&PreviewWindow, &Rc<ScenarioNode>, &Isv2Parameter, &Path) -> anyhow::Result<usize>
+    ) -> Box<dyn Fn(&SimpleAction, Option<&Variant>) + 'static>{
+        let filters: Vec<(String, String)> = filters.iter().map(|(n, p)| (n.to_string(), p.to_string())).collect();
+        Box::new( move|_act, _val|{
+            let ext  = filters[0].1.trim_start_matches("*.");
+            let name = Path::new(&param.property::<String>("project_file_name")).with_extension(ext);
+            let file_dialog = FileDialog::builder().modal(true).initial_name(name.to_string_lossy()).build();
+            let param_path = param.property::<PathBuf>("project_dir");
+            if param_path.exists() && param_path.is_dir() {
+                file_dialog.set_initial_folder(Some(&gio::File::for_path(param_path)));
+            }
+            let model = gio::ListStore::with_type(FileFilter::static_type());
+            for (name, pattern) in &filters {
+                let file_filter = FileFilter::new();
+                file_filter.add_pattern(pattern);
+                file_filter.set_name(Some(name));
+                model.append(&file_filter);
+            }
+            file_dialog.set_filters(Some(&model));
+
+            file_dialog.save(Some(&root),
+                             None::<Cancellable>.as_ref(),
+                             clone!(@strong store, @strong param, @strong pwin, @strong root => move|result|{
+                                 let file = if let Ok(f) = result { f } else { return; };
+                                 let path = if let Some(p) = file.path() { p } else { return; };
+                                 let sn = if let Some(sno) = store.item(0) {
+                                     sno.downcast_ref::<ScenarioNodeObject>().expect("sno").get_node() }
+                                     else { println!("(act_export_doc) store has noitem"); return; };
+                                 if let Err(e) = export(&pwin, &sn, &param, &path) {
+                                     println!("(act_export_doc) {:?}", e);
+                                     let dialog = AlertDialog::builder().modal(true).build();
+                                     dialog.set_message("Exporting failed");
+                                     dialog.set_detail(&format!("{:#}", e));
+                                     dialog.show(Some(&root));
+                                 }
+                             }));
+        })
+    }
+    // act_export_pdf //////////////////////////////////////
+    pub fn act_export_pdf(store : ListStore,
+                          param : Isv2Parameter,
+                          pwin  : PreviewWindow,
+                          root  : impl IsA<Window>) -> SimpleAction{
+        let act_export_pdf = SimpleAction::new(ACT_FILE_EXPORT_PDF, None);
+        act_export_pdf.connect_activate(act_export_doc_func(store, param, pwin, root,
+                                                            &[("PDF(pdf)", "*.pdf")],
+                                                            PreviewWindow::export_pdf));
+        act_export_pdf
+    }
+    // act_export_package //////////////////////////////////
+    pub fn act_export_package(store : ListStore,
+                              param : Isv2Parameter,
+                              pwin  : PreviewWindow,
+                              root  : impl IsA<Window>) -> SimpleAction{
+        let act_export_package = SimpleAction::new(ACT_FILE_EXPORT_PACKAGE, None);
+        act_export_package.connect_activate(act_export_doc_func(store, param, pwin, root,
+                                                                &[("EPUB(epub)", "*.epub"), ("comic archive(cbz)", "*.cbz")],
+                                                                PreviewWindow::export_package));
+        act_export_package
+    }
 }
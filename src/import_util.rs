@@ -0,0 +1,408 @@
+use std::rc::Rc;
+
+use crate::scenario_node::ScenarioNode;
+use crate::scenario_node::Item;
+use crate::tree_util::tree_manipulate;
+
+// new_node ////////////////////////////////////////////////
+fn new_node(item: Item) -> Rc<ScenarioNode>{
+    let n = ScenarioNode::new();
+    n.set_value(item);
+    n.id.set(tree_manipulate::gen_id());
+    Rc::new(n)
+}
+
+pub mod script_import{
+    //! converts a plain-text/Markdown script into scenario nodes
+    //!
+    //!   # heading          -> group (deeper headings are nested groups)
+    //!   ![alt](img.png)    -> scene with bgimg (`@image img.png` in plain text,
+    //!                         `@image` alone makes a scene without bgimg)
+    //!   text lines         -> text of a mat, lines are joined with '\n'
+    //!   --                 -> separates mats in the same page
+    //!   (blank line)       -> page break
+    //!
+    //! a page with one mat becomes a pmat, a page with several mats becomes
+    //! a page with mats. Pages before the first image directive are put into
+    //! a scene without bgimg.
+
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+
+    use dunce;
+
+    use crate::scenario_node::ScenarioNode;
+    use crate::scenario_node::{Item, Group, Scene, Page, Mat};
+
+    use super::new_node;
+
+    // ScriptLine //////////////////////////////////////////
+    #[derive(Debug, PartialEq)]
+    enum ScriptLine{
+        Heading(usize, String),
+        Image(Option<String>),
+        MatSeparator,
+        Blank,
+        Text(String),
+    }
+    fn parse_line(line: &str) -> ScriptLine{
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return ScriptLine::Blank; }
+        if trimmed == "--" {
+            return ScriptLine::MatSeparator; }
+        // heading
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            return ScriptLine::Heading(level, trimmed[level..].trim().to_string()); }
+        // markdown image
+        if trimmed.starts_with("![") && trimmed.ends_with(')') {
+            if let Some(p) = trimmed.find("](") {
+                let path = trimmed[p+2..trimmed.len()-1].trim();
+                let path = path.split_whitespace().next().unwrap_or(""); // drop title
+                let path = path.trim_start_matches('<').trim_end_matches('>');
+                if path.is_empty() {
+                    return ScriptLine::Image(None); }
+                return ScriptLine::Image(Some(path.to_string()));
+            }
+        }
+        // plain text image directive
+        if trimmed == "@image" {
+            return ScriptLine::Image(None); }
+        if let Some(path) = trimmed.strip_prefix("@image ") {
+            return ScriptLine::Image(Some(path.trim().to_string())); }
+
+        ScriptLine::Text(line.trim_end().to_string())
+    }
+    // resolve_img_path ////////////////////////////////////
+    /// path in the script is relative to the script, it is stored relative to project_dir
+    pub fn resolve_img_path(path: &str, base_dir: &Path, project_dir: &Path) -> PathBuf{
+        let abs_path = base_dir.join(path);
+        let abs_path = if let Ok(p) = dunce::canonicalize(&abs_path) { p } else {
+            println!("(resolve_img_path) {:?} is not found", abs_path);
+            return abs_path; };
+        let project_dir = dunce::canonicalize(project_dir).unwrap_or(project_dir.to_path_buf());
+        if let Ok(rel_path) = abs_path.strip_prefix(&project_dir) {
+            rel_path.to_path_buf()
+        } else {
+            abs_path
+        }
+    }
+    // ScriptBuilder ///////////////////////////////////////
+    struct ScriptBuilder<'a>{
+        root        : Option<Rc<ScenarioNode>>,
+        groups      : Vec<(usize, Rc<ScenarioNode>)>,
+        scene       : Option<Rc<ScenarioNode>>,
+        mat_lines   : Vec<String>,
+        page_mats   : Vec<String>,
+        base_dir    : &'a Path,
+        project_dir : &'a Path,
+    }
+    impl ScriptBuilder<'_>{
+        // add_top: adds a group/scene to the current group or to the top level
+        fn add_top(&mut self, n: Rc<ScenarioNode>){
+            if let Some((_, g)) = self.groups.last() {
+                ScenarioNode::append_last_child(g.clone(), n);
+            } else if let Some(root) = &self.root {
+                ScenarioNode::mv_to_neighbor(ScenarioNode::get_last_neighbor(root), n);
+            } else {
+                self.root = Some(n);
+            }
+        }
+        fn flush_mat(&mut self){
+            if self.mat_lines.is_empty() { return; }
+            self.page_mats.push(self.mat_lines.join("\n"));
+            self.mat_lines.clear();
+        }
+        fn flush_page(&mut self){
+            self.flush_mat();
+            if self.page_mats.is_empty() { return; }
+            if self.scene.is_none() {
+                let s = new_node(Item::Scene(Scene::default()));
+                self.add_top(s.clone());
+                self.scene = Some(s);
+            }
+            let scene = self.scene.clone().unwrap();
+            let mut texts: Vec<_> = self.page_mats.drain(..).collect();
+            if texts.len() == 1 {
+                let m = Mat{ text: texts.pop().unwrap(), ..Default::default() };
+                ScenarioNode::append_last_child(scene, new_node(Item::Pmat(m)));
+            } else {
+                let page = new_node(Item::Page(Page::default()));
+                ScenarioNode::append_last_child(scene, page.clone());
+                for t in texts {
+                    let m = Mat{ text: t, ..Default::default() };
+                    ScenarioNode::append_last_child(page.clone(), new_node(Item::Mat(m)));
+                }
+            }
+        }
+        fn feed(&mut self, line: ScriptLine){
+            match line {
+                ScriptLine::Heading(level, name) => {
+                    self.flush_page();
+                    self.scene = None;
+                    while let Some((l, _)) = self.groups.last() {
+                        if *l < level { break; }
+                        self.groups.pop();
+                    }
+                    let g = new_node(Item::Group(Group{ name }));
+                    self.add_top(g.clone());
+                    self.groups.push((level, g));
+                },
+                ScriptLine::Image(path) => {
+                    self.flush_page();
+                    let s = Scene{ bgimg: path.map(|p| resolve_img_path(&p, self.base_dir, self.project_dir)), ..Default::default() };
+                    let s = new_node(Item::Scene(s));
+                    self.add_top(s.clone());
+                    self.scene = Some(s);
+                },
+                ScriptLine::MatSeparator => { self.flush_mat(); },
+                ScriptLine::Blank        => { self.flush_page(); },
+                ScriptLine::Text(t)      => { self.mat_lines.push(t); },
+            }
+        }
+    }
+    // import_script ///////////////////////////////////////
+    /// appends the nodes of the script after the top level nodes of root,
+    /// and returns the root of the whole tree
+    pub fn import_script(text       : &str,
+                         root       : Option<Rc<ScenarioNode>>,
+                         base_dir   : &Path,
+                         project_dir: &Path) -> Option<Rc<ScenarioNode>>{
+        let mut builder = ScriptBuilder{
+            root,
+            groups    : Vec::new(),
+            scene     : None,
+            mat_lines : Vec::new(),
+            page_mats : Vec::new(),
+            base_dir,
+            project_dir,
+        };
+        for line in text.trim_start_matches('\u{feff}').lines() {
+            builder.feed(parse_line(line));
+        }
+        builder.flush_page();
+        builder.root
+    }
+    // import_script_file //////////////////////////////////
+    pub fn import_script_file(script     : &Path,
+                              root       : Option<Rc<ScenarioNode>>,
+                              project_dir: &Path) -> anyhow::Result<Option<Rc<ScenarioNode>>>{
+        use anyhow::Context;
+        let text = std::fs::read_to_string(script)
+            .with_context(||format!("reading {script:?} failed"))?;
+        let base_dir = script.parent().unwrap_or(Path::new("."));
+        Ok(import_script(&text, root, base_dir, project_dir))
+    }
+
+    // test ////////////////////////////////////////////////
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_line() {
+            assert_eq!(parse_line("## chapter 1"), ScriptLine::Heading(2, "chapter 1".to_string()));
+            assert_eq!(parse_line("#hashtag"), ScriptLine::Text("#hashtag".to_string()));
+            assert_eq!(parse_line("![bg](img/a.png \"title\")"), ScriptLine::Image(Some("img/a.png".to_string())));
+            assert_eq!(parse_line("@image img/b.png"), ScriptLine::Image(Some("img/b.png".to_string())));
+            assert_eq!(parse_line("@image"), ScriptLine::Image(None));
+            assert_eq!(parse_line("  "), ScriptLine::Blank);
+            assert_eq!(parse_line("--"), ScriptLine::MatSeparator);
+        }
+
+        #[test]
+        fn test_import_script() {
+            let script = "# part 1\n\
+                          ## chapter 1\n\
+                          @image img/a.png\n\
+                          first line\n\
+                          second line\n\
+                          \n\
+                          mat 1\n\
+                          --\n\
+                          mat 2\n\
+                          # part 2\n\
+                          text without image\n";
+            let root = import_script(script, None, Path::new("."), Path::new(".")).unwrap();
+            assert_eq!(root.get_group_name(), Some("part 1".to_string()));
+
+            let chapter = root.child.borrow().clone().unwrap();
+            assert_eq!(chapter.get_group_name(), Some("chapter 1".to_string()));
+
+            let scene = chapter.child.borrow().clone().unwrap();
+            assert!(scene.is_scene());
+
+            let pmat = scene.child.borrow().clone().unwrap();
+            assert_eq!(pmat.get_mat_text(), Some("first line\nsecond line".to_string()));
+            assert!(pmat.is_pmat());
+
+            let page = pmat.neighbor.borrow().clone().unwrap();
+            assert!(page.is_page());
+            let mat1 = page.child.borrow().clone().unwrap();
+            let mat2 = mat1.neighbor.borrow().clone().unwrap();
+            assert_eq!(mat1.get_mat_text(), Some("mat 1".to_string()));
+            assert_eq!(mat2.get_mat_text(), Some("mat 2".to_string()));
+
+            let part2 = root.neighbor.borrow().clone().unwrap();
+            assert_eq!(part2.get_group_name(), Some("part 2".to_string()));
+            let scene2 = part2.child.borrow().clone().unwrap();
+            assert_eq!(scene2.get_scene_bgimg(), None);
+            assert!(scene2.child.borrow().as_ref().unwrap().is_pmat());
+
+            let unnamed = new_node(Item::Group(Group::default()));
+            assert_eq!(unnamed.get_group_name(), None);
+        }
+    }
+}
+
+pub mod image_import{
+    //! creates a scene for each image in a folder
+
+    use std::cmp::Ordering;
+    use std::collections::HashSet;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+
+    use anyhow::Context;
+    use dunce;
+
+    use crate::scenario_node::ScenarioNode;
+    use crate::scenario_node::{Item, Scene, Mat};
+
+    use super::new_node;
+    use super::script_import::resolve_img_path;
+
+    pub const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "bmp", "webp"];
+
+    // natural_cmp /////////////////////////////////////////
+    /// compares file names so that "2.png" comes before "10.png"
+    pub fn natural_cmp(a: &str, b: &str) -> Ordering{
+        let mut a = a.chars().peekable();
+        let mut b = b.chars().peekable();
+        loop {
+            match (a.peek().copied(), b.peek().copied()) {
+                (None, None)    => return Ordering::Equal,
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(ca), Some(cb)) if ca.is_ascii_digit() && cb.is_ascii_digit() => {
+                    let mut na = String::new();
+                    while let Some(c) = a.next_if(|c| c.is_ascii_digit()) { na.push(c); }
+                    let mut nb = String::new();
+                    while let Some(c) = b.next_if(|c| c.is_ascii_digit()) { nb.push(c); }
+                    let ta = na.trim_start_matches('0');
+                    let tb = nb.trim_start_matches('0');
+                    let ord = ta.len().cmp(&tb.len())
+                        .then_with(|| ta.cmp(tb))
+                        .then_with(|| na.len().cmp(&nb.len()));
+                    if ord != Ordering::Equal { return ord; }
+                },
+                (Some(ca), Some(cb)) => {
+                    let ord = ca.to_lowercase().cmp(cb.to_lowercase()).then_with(|| ca.cmp(&cb));
+                    if ord != Ordering::Equal { return ord; }
+                    a.next();
+                    b.next();
+                },
+            }
+        }
+    }
+    // list_images /////////////////////////////////////////
+    /// image files directly under dir, sorted naturally by file name
+    pub fn list_images(dir: &Path) -> anyhow::Result<Vec<PathBuf>>{
+        let mut images: Vec<PathBuf> = std::fs::read_dir(dir)
+            .with_context(||format!("reading {dir:?} failed"))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_file())
+            .filter(|p| p.extension()
+                    .and_then(|e| e.to_str())
+                    .map(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+                    .unwrap_or(false))
+            .collect();
+        images.sort_by(|a, b| natural_cmp(&a.file_name().unwrap_or_default().to_string_lossy(),
+                                          &b.file_name().unwrap_or_default().to_string_lossy()));
+        Ok(images)
+    }
+    // referenced_images ///////////////////////////////////
+    fn normalize(path: &Path, project_dir: &Path) -> PathBuf{
+        let path = project_dir.join(path);
+        dunce::canonicalize(&path).unwrap_or(path)
+    }
+    fn referenced_images(root: &Option<Rc<ScenarioNode>>, project_dir: &Path) -> HashSet<PathBuf>{
+        let mut set = HashSet::new();
+        let mut vec = if let Some(r) = root { vec![r.clone()] } else { vec![] };
+        while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+            if let Some(b) = sn.get_scene_bgimg() {
+                set.insert(normalize(&b, project_dir)); }
+        }
+        set
+    }
+    // import_image_folder /////////////////////////////////
+    /// appends a scene with an empty pmat for each image in dir after the top
+    /// level nodes of root. Images already used as bgimg of a scene are skipped.
+    /// returns the root of the whole tree and the number of the imported images
+    pub fn import_image_folder(dir        : &Path,
+                               root       : Option<Rc<ScenarioNode>>,
+                               project_dir: &Path) -> anyhow::Result<(Option<Rc<ScenarioNode>>, usize)>{
+        let images = list_images(dir)?;
+        let mut referenced = referenced_images(&root, project_dir);
+        let mut root = root;
+        let mut count = 0;
+        for img in images {
+            let name = img.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let bgimg = resolve_img_path(&name, dir, project_dir);
+            if !referenced.insert(normalize(&bgimg, project_dir)) {
+                println!("(import_image_folder) {:?} is already used, skipped", bgimg);
+                continue;
+            }
+            let s = Scene{ bgimg: Some(bgimg), ..Default::default() };
+            let scene = new_node(Item::Scene(s));
+            ScenarioNode::mv_to_child(scene.clone(), new_node(Item::Pmat(Mat::default())));
+            if let Some(r) = &root {
+                ScenarioNode::mv_to_neighbor(ScenarioNode::get_last_neighbor(r), scene);
+            } else {
+                root = Some(scene);
+            }
+            count += 1;
+        }
+        Ok((root, count))
+    }
+
+    // test ////////////////////////////////////////////////
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_natural_cmp() {
+            let mut names = vec!["img10.png", "img2.png", "Img1.png", "img02.png", "cover.png"];
+            names.sort_by(|a, b| natural_cmp(a, b));
+            assert_eq!(names, vec!["cover.png", "Img1.png", "img2.png", "img02.png", "img10.png"]);
+        }
+
+        #[test]
+        fn test_import_image_folder() {
+            let project_dir = std::env::temp_dir().join(format!("isv2_image_import_{}", std::process::id()));
+            let img_dir = project_dir.join("img");
+            std::fs::create_dir_all(&img_dir).unwrap();
+            for f in ["10.png", "2.jpg", "1.png", "note.txt"] {
+                std::fs::write(img_dir.join(f), b"").unwrap(); }
+
+            let s = Scene{ bgimg: Some(PathBuf::from("img/1.png")), ..Default::default() };
+            let root = new_node(Item::Scene(s));
+
+            let (root, count) = import_image_folder(&img_dir, Some(root), &project_dir).unwrap();
+            std::fs::remove_dir_all(&project_dir).unwrap();
+            assert_eq!(count, 2);
+
+            let root = root.unwrap();
+            let s2 = root.neighbor.borrow().clone().unwrap();
+            assert_eq!(s2.get_scene_bgimg(), Some(Path::new("img").join("2.jpg")));
+            assert!(s2.child.borrow().as_ref().unwrap().is_pmat());
+            let s10 = s2.neighbor.borrow().clone().unwrap();
+            assert_eq!(s10.get_scene_bgimg(), Some(Path::new("img").join("10.png")));
+            assert!(s10.neighbor.borrow().is_none());
+        }
+    }
+}
@@ -8,6 +8,18 @@ use gtk::glib::prelude::*;
 use gtk::glib;
 use gtk::subclass::prelude::*;
 
+use super::AnimationSetting;
+use super::SubsetSetting;
+use super::VideoSetting;
+use super::LanguageSetting;
+use super::ResampleSetting;
+use super::ContactSheetSetting;
+use crate::export_util::profile::ExportProfile;
+
+fn default_name_template() -> RefCell<String> {
+    RefCell::new(String::from(crate::export_util::naming::DEFAULT_TEMPLATE))
+}
+
 // Object holding the state
 #[derive(Debug, Properties, Serialize, Deserialize)]
 #[properties(wrapper_type = super::Isv2Parameter)]
@@ -23,7 +35,33 @@ pub struct Isv2Parameter {
     #[property(get, set)]
     pub(super) export_dir         : RefCell<String>,
     #[property(get, set)]
+    #[serde(default = "default_name_template")]
+    pub(super) name_template      : RefCell<String>, // file name of exported images, see export_util::naming
+    #[property(get, set)]
     pub(super) bgimg_en           : Cell<bool>,
+    #[property(get, set)]
+    #[serde(default)]
+    pub(super) language           : RefCell<String>, // "" means the original text
+    #[property(get, set)]
+    #[serde(default)]
+    pub(super) ref_language       : RefCell<String>, // "" hides the reference pane
+    #[serde(default)]
+    pub(super) languages          : RefCell<Vec<LanguageSetting>>, // the first one is the language of Mat::text
+    #[serde(default)]
+    pub(super) animation          : RefCell<AnimationSetting>,
+    #[serde(default)]
+    pub(super) video              : RefCell<VideoSetting>,
+    #[serde(default)]
+    pub(super) subset             : RefCell<SubsetSetting>,
+    #[serde(default)]
+    pub(super) profiles           : RefCell<Vec<ExportProfile>>,
+    #[property(get, set)]
+    #[serde(default)]
+    pub(super) normalized_geometry: Cell<bool>, // mat geometry is saved relative to the target size
+    #[serde(default)]
+    pub(super) resample           : RefCell<ResampleSetting>,
+    #[serde(default)]
+    pub(super) contact_sheet      : RefCell<ContactSheetSetting>,
 }
 
 #[glib::object_subclass]
@@ -57,7 +95,18 @@ impl Default for Isv2Parameter{
             project_dir        : RefCell::new(path),
             project_file_name  : RefCell::new(String::from("project.json")),
             export_dir         : RefCell::new(String::from("rel")),
+            name_template      : default_name_template(),
             bgimg_en           : Cell::new(true),
+            language           : RefCell::new(String::new()),
+            ref_language       : RefCell::new(String::new()),
+            languages          : RefCell::new(Vec::new()),
+            animation          : RefCell::new(AnimationSetting::default()),
+            video              : RefCell::new(VideoSetting::default()),
+            subset             : RefCell::new(SubsetSetting::default()),
+            profiles           : RefCell::new(Vec::new()),
+            normalized_geometry: Cell::new(false),
+            resample           : RefCell::new(ResampleSetting::default()),
+            contact_sheet      : RefCell::new(ContactSheetSetting::default()),
         }
     }
 }
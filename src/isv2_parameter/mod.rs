@@ -1,16 +1,110 @@
 mod imp;
 
 use std::cell::Cell;
+use std::cell::RefCell;
 
 use serde::{Deserialize, Serialize};
 
 use gtk::glib;
 use gtk::subclass::prelude::*;
 
+use crate::drawing_util::render::Filter;
+use crate::export_util::profile::ExportProfile;
+
 glib::wrapper! {
     pub struct Isv2Parameter(ObjectSubclass<imp::Isv2Parameter>);
 }
 
+// LanguageSetting /////////////////////////////////////////
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LanguageSetting{
+    pub code        : String,
+    #[serde(default)]
+    pub font_family : Option<String>, // replaces the font family of mats
+    #[serde(default)]
+    pub font_scale  : Option<u32>,    // [%] scales the font size of mats
+}
+
+// AnimationSetting ////////////////////////////////////////
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnimationSetting{
+    pub first_page     : usize,         // number of the exported image (0000.png)
+    pub last_page      : Option<usize>, // None: to the last page
+    pub hold_ms        : u32,           // [ms] display time of a page
+    pub fade_ms        : u32,           // [ms] crossfade to the next page, 0: off
+    pub fps            : u32,           // frame rate of crossfade and typewriter
+    pub typewriter_cps : Option<u32>,   // [chars/s] reveals mat text, None: off
+    pub loop_count     : u16,           // 0: infinite
+}
+impl Default for AnimationSetting{
+    fn default() -> Self{
+        Self{
+            first_page     : 0,
+            last_page      : None,
+            hold_ms        : 2000,
+            fade_ms        : 500,
+            fps            : 10,
+            typewriter_cps : None,
+            loop_count     : 0,
+        }
+    }
+}
+
+// VideoSetting ////////////////////////////////////////////
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoSetting{
+    pub fps            : u32,         // frame rate of the frame sequence
+    pub duration_ms    : u32,         // [ms] display time of a page without its own duration
+    pub typewriter_cps : Option<u32>, // [chars/s] reveals mat text, None: off
+}
+impl Default for VideoSetting{
+    fn default() -> Self{
+        Self{
+            fps            : 30,
+            duration_ms    : 3000,
+            typewriter_cps : None,
+        }
+    }
+}
+
+// SubsetSetting ///////////////////////////////////////////
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubsetSetting{
+    pub pages           : Option<(usize, usize)>, // first and last number of the pages, None: selected node
+    pub local_numbering : bool,                   // numbers files from 0 in the subset instead of the whole project
+}
+
+// ResampleSetting /////////////////////////////////////////
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResampleSetting{
+    pub high_quality : bool, // Lanczos filter for images in exports, the preview is always bilinear
+    pub supersample  : u32,  // [1-4] draws raster pages N times larger and scales them down
+}
+impl Default for ResampleSetting{
+    fn default() -> Self{ Self{ high_quality: true, supersample: 1 } }
+}
+impl ResampleSetting{
+    pub fn filter(&self) -> Filter{
+        if self.high_quality { Filter::Best } else { Filter::Fast }
+    }
+}
+
+// ContactSheetSetting /////////////////////////////////////
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContactSheetSetting{
+    pub columns     : u32, // thumbnails in a row
+    pub rows        : u32, // rows in a sheet
+    pub thumb_width : i32, // [px] width of a thumbnail, the height follows the target size
+}
+impl Default for ContactSheetSetting{
+    fn default() -> Self{ Self{ columns: 4, rows: 5, thumb_width: 320 } }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Isv2ParameterSerde{
     pub param: imp::Isv2Parameter,
@@ -23,7 +117,18 @@ impl From<&Isv2Parameter> for Isv2ParameterSerde {
             project_dir        : src.imp().project_dir.clone(),
             project_file_name  : src.imp().project_file_name.clone(),
             export_dir         : src.imp().export_dir.clone(),
+            name_template      : src.imp().name_template.clone(),
             bgimg_en           : Cell::new(src.imp().bgimg_en.get()),
+            language           : src.imp().language.clone(),
+            ref_language       : src.imp().ref_language.clone(),
+            languages          : RefCell::new(src.languages()),
+            animation          : RefCell::new(src.animation()),
+            video              : RefCell::new(src.video()),
+            subset             : RefCell::new(src.subset()),
+            profiles           : RefCell::new(src.profiles()),
+            normalized_geometry: Cell::new(src.imp().normalized_geometry.get()),
+            resample           : RefCell::new(src.resample()),
+            contact_sheet      : RefCell::new(src.contact_sheet()),
         };
         Self{
             param
@@ -38,7 +143,18 @@ impl From<&Isv2ParameterSerde> for Isv2Parameter{
         *obj.imp().project_dir.borrow_mut()        = (*src.param.project_dir.borrow()).to_path_buf();
         *obj.imp().project_file_name.borrow_mut()  = (*src.param.project_file_name.borrow()).clone();
         *obj.imp().export_dir.borrow_mut()         = (*src.param.export_dir.borrow()).clone();
+        *obj.imp().name_template.borrow_mut()      = (*src.param.name_template.borrow()).clone();
         obj.imp().bgimg_en.set( src.param.bgimg_en.get() );
+        *obj.imp().language.borrow_mut()           = (*src.param.language.borrow()).clone();
+        *obj.imp().ref_language.borrow_mut()       = (*src.param.ref_language.borrow()).clone();
+        *obj.imp().languages.borrow_mut()          = (*src.param.languages.borrow()).clone();
+        *obj.imp().animation.borrow_mut()          = (*src.param.animation.borrow()).clone();
+        *obj.imp().video.borrow_mut()              = (*src.param.video.borrow()).clone();
+        *obj.imp().subset.borrow_mut()             = (*src.param.subset.borrow()).clone();
+        *obj.imp().profiles.borrow_mut()           = (*src.param.profiles.borrow()).clone();
+        obj.imp().normalized_geometry.set( src.param.normalized_geometry.get() );
+        *obj.imp().resample.borrow_mut()           = (*src.param.resample.borrow()).clone();
+        *obj.imp().contact_sheet.borrow_mut()      = (*src.param.contact_sheet.borrow()).clone();
         obj
     }
 }
@@ -55,6 +171,48 @@ impl Isv2Parameter{
         self.imp().target_height.set( src.param.target_height.get() );
         // note: project_dir and project_file_name are updated when the file is opened
         *self.imp().export_dir.borrow_mut() = (*src.param.export_dir.borrow()).clone();
+        *self.imp().name_template.borrow_mut() = (*src.param.name_template.borrow()).clone();
         self.imp().bgimg_en.set( src.param.bgimg_en.get() );
+        *self.imp().language.borrow_mut() = (*src.param.language.borrow()).clone();
+        *self.imp().ref_language.borrow_mut() = (*src.param.ref_language.borrow()).clone();
+        *self.imp().languages.borrow_mut() = (*src.param.languages.borrow()).clone();
+        *self.imp().animation.borrow_mut() = (*src.param.animation.borrow()).clone();
+        *self.imp().video.borrow_mut() = (*src.param.video.borrow()).clone();
+        *self.imp().subset.borrow_mut() = (*src.param.subset.borrow()).clone();
+        *self.imp().profiles.borrow_mut() = (*src.param.profiles.borrow()).clone();
+        self.imp().normalized_geometry.set( src.param.normalized_geometry.get() );
+        *self.imp().resample.borrow_mut() = (*src.param.resample.borrow()).clone();
+        *self.imp().contact_sheet.borrow_mut() = (*src.param.contact_sheet.borrow()).clone();
+    }
+    // languages ///////////////////////////////////////////
+    pub fn languages(&self) -> Vec<LanguageSetting>{ self.imp().languages.borrow().clone() }
+    pub fn set_languages(&self, l: Vec<LanguageSetting>){ *self.imp().languages.borrow_mut() = l; }
+    pub fn language_setting(&self, lang: &str) -> Option<LanguageSetting>{
+        self.imp().languages.borrow().iter().find(|l| l.code == lang).cloned()
+    }
+    /// key of Mat::text_l10n for lang, "" means Mat::text (the first language)
+    pub fn text_key(&self, lang: &str) -> String{
+        match self.imp().languages.borrow().first() {
+            Some(base) if base.code == lang => String::new(),
+            _                               => lang.to_string(),
+        }
     }
+    // animation ///////////////////////////////////////////
+    pub fn animation(&self) -> AnimationSetting{ self.imp().animation.borrow().clone() }
+    pub fn set_animation(&self, a: AnimationSetting){ *self.imp().animation.borrow_mut() = a; }
+    // video ///////////////////////////////////////////////
+    pub fn video(&self) -> VideoSetting{ self.imp().video.borrow().clone() }
+    pub fn set_video(&self, v: VideoSetting){ *self.imp().video.borrow_mut() = v; }
+    // subset //////////////////////////////////////////////
+    pub fn subset(&self) -> SubsetSetting{ self.imp().subset.borrow().clone() }
+    pub fn set_subset(&self, s: SubsetSetting){ *self.imp().subset.borrow_mut() = s; }
+    // profiles ////////////////////////////////////////////
+    pub fn profiles(&self) -> Vec<ExportProfile>{ self.imp().profiles.borrow().clone() }
+    pub fn set_profiles(&self, p: Vec<ExportProfile>){ *self.imp().profiles.borrow_mut() = p; }
+    // resample ////////////////////////////////////////////
+    pub fn resample(&self) -> ResampleSetting{ self.imp().resample.borrow().clone() }
+    pub fn set_resample(&self, r: ResampleSetting){ *self.imp().resample.borrow_mut() = r; }
+    // contact_sheet ///////////////////////////////////////
+    pub fn contact_sheet(&self) -> ContactSheetSetting{ self.imp().contact_sheet.borrow().clone() }
+    pub fn set_contact_sheet(&self, c: ContactSheetSetting){ *self.imp().contact_sheet.borrow_mut() = c; }
 }
@@ -1,5 +1,9 @@
+mod cli;
 mod drawing_util;
+mod export_menu;
+mod export_util;
 mod file_menu;
+mod import_util;
 mod pref_menu;
 mod isv2_button;
 mod isv2_mediator;
@@ -17,6 +21,7 @@ mod tree_util;
 mod view_menu;
 mod status_bar;
 mod text_edit_util;
+mod translation_util;
 mod keybind;
 
 use std::path::PathBuf;
@@ -53,6 +58,7 @@ use crate::isv2_parameter::Isv2Parameter;
 use crate::operation_history::Operation;
 use crate::operation_history::OperationHistory;
 use crate::operation_history::OperationHistoryItem;
+use crate::export_menu::export_actions;
 use crate::pref_menu::pref_actions;
 use crate::preview_window::PreviewWindow;
 use crate::scenario_node::BranchType;
@@ -86,6 +92,10 @@ pub fn load_css() {
         gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
     );
 }
+// run_cli /////////////////////////////////////////////////
+pub fn run_cli(args: &[String]) -> Option<glib::ExitCode> {
+    cli::run(args)
+}
 // my_creator //////////////////////////////////////////////
 fn my_creator(obj: &Object) -> Option<ListModel>{
 
@@ -294,11 +304,17 @@ pub fn build_ui(app: &Application) {
         .child(&text_view)
         .build();
     text_view.set_mediator(mediator.clone().upcast::<Object>().downgrade());
+    text_view.set_parameter(param.clone().downgrade());
     preview_window.set_sno( o_node1.clone() );
     mediator.set_property("scenario_text_view", text_view.clone());
 
+    // text of the editing language | text of the reference language
+    let pane_text = Paned::builder().wide_handle(true).orientation(Orientation::Horizontal).build();
+    pane_text.set_start_child( Some( &text_scroll_window) );
+    pane_text.set_end_child( Some( &text_view.ref_pane()) );
+
     pane_l.set_start_child( Some( &preview_window) );
-    pane_l.set_end_child( Some( &text_scroll_window) );
+    pane_l.set_end_child( Some( &pane_text) );
 
     pane_l.set_resize_start_child(true);
     pane_l.set_shrink_start_child(false);
@@ -394,6 +410,157 @@ pub fn build_ui(app: &Application) {
     let menu_item_export_img = MenuItem::new(Some("_Export images"),
                                              Some( &("app.".to_string() + actions::ACT_FILE_EXPORT_IMG) ));
     menu_file.append_item(&menu_item_export_img);
+    let act_export_pdf = actions::act_export_pdf(model.clone(),
+                                                 param.clone(),
+                                                 preview_window.clone(),
+                                                 window.clone());
+    app.add_action(&act_export_pdf);
+    let menu_item_export_pdf = MenuItem::new(Some("Export _PDF"),
+                                             Some( &("app.".to_string() + actions::ACT_FILE_EXPORT_PDF) ));
+    menu_file.append_item(&menu_item_export_pdf);
+    let act_export_img_all_lang = actions::act_export_img_all_lang(model.clone(),
+                                                                   param.clone(),
+                                                                   preview_window.clone(),
+                                                                   window.clone());
+    app.add_action(&act_export_img_all_lang);
+    let menu_item_export_img_all_lang = MenuItem::new(Some("Export images(_all languages)"),
+                                                      Some( &("app.".to_string() + actions::ACT_FILE_EXPORT_IMG_ALL_LANG) ));
+    menu_file.append_item(&menu_item_export_img_all_lang);
+    let act_export_img_svg = actions::act_export_img_svg(model.clone(),
+                                                         param.clone(),
+                                                         preview_window.clone(),
+                                                         window.clone());
+    app.add_action(&act_export_img_svg);
+    let menu_item_export_img_svg = MenuItem::new(Some("Export images(_SVG)"),
+                                                 Some( &("app.".to_string() + actions::ACT_FILE_EXPORT_IMG_SVG) ));
+    menu_file.append_item(&menu_item_export_img_svg);
+    let act_export_img_ora = actions::act_export_img_ora(model.clone(),
+                                                         param.clone(),
+                                                         preview_window.clone(),
+                                                         window.clone());
+    app.add_action(&act_export_img_ora);
+    let menu_item_export_img_ora = MenuItem::new(Some("Export images(_OpenRaster)"),
+                                                 Some( &("app.".to_string() + actions::ACT_FILE_EXPORT_IMG_ORA) ));
+    menu_file.append_item(&menu_item_export_img_ora);
+    let act_export_img_content = actions::act_export_img_content(model.clone(),
+                                                                 param.clone(),
+                                                                 preview_window.clone(),
+                                                                 window.clone());
+    app.add_action(&act_export_img_content);
+    let menu_item_export_img_content = MenuItem::new(Some("Export images(_transparent / text only)"),
+                                                     Some( &("app.".to_string() + actions::ACT_FILE_EXPORT_IMG_CONTENT) ));
+    menu_file.append_item(&menu_item_export_img_content);
+    let act_export_subset = export_actions::act_export_subset(model.clone(),
+                                                              selection_model.clone(),
+                                                              param.clone(),
+                                                              preview_window.clone(),
+                                                              window.clone());
+    app.add_action(&act_export_subset);
+    let menu_item_export_subset = MenuItem::new(Some("Export images(s_ubset)"),
+                                                Some( &("app.".to_string() + export_actions::ACT_EXPORT_SUBSET) ));
+    menu_file.append_item(&menu_item_export_subset);
+    let act_export_profiles = export_actions::act_export_profiles(model.clone(),
+                                                                  param.clone(),
+                                                                  preview_window.clone(),
+                                                                  window.clone());
+    app.add_action(&act_export_profiles);
+    let menu_item_export_profiles = MenuItem::new(Some("Export images(p_rofiles)"),
+                                                  Some( &("app.".to_string() + export_actions::ACT_EXPORT_PROFILES) ));
+    menu_file.append_item(&menu_item_export_profiles);
+    let act_export_package = actions::act_export_package(model.clone(),
+                                                         param.clone(),
+                                                         preview_window.clone(),
+                                                         window.clone());
+    app.add_action(&act_export_package);
+    let menu_item_export_package = MenuItem::new(Some("Export EPUB/_CBZ"),
+                                                 Some( &("app.".to_string() + actions::ACT_FILE_EXPORT_PACKAGE) ));
+    menu_file.append_item(&menu_item_export_package);
+    let act_export_html = export_actions::act_export_html(model.clone(),
+                                                          param.clone(),
+                                                          preview_window.clone(),
+                                                          window.clone());
+    app.add_action(&act_export_html);
+    let menu_item_export_html = MenuItem::new(Some("Export _HTML viewer"),
+                                              Some( &("app.".to_string() + export_actions::ACT_EXPORT_HTML) ));
+    menu_file.append_item(&menu_item_export_html);
+    let act_export_sheet = export_actions::act_export_contact_sheet(model.clone(),
+                                                                    param.clone(),
+                                                                    preview_window.clone(),
+                                                                    window.clone());
+    app.add_action(&act_export_sheet);
+    let menu_item_export_sheet = MenuItem::new(Some("Export contact _sheet"),
+                                               Some( &("app.".to_string() + export_actions::ACT_EXPORT_SHEET) ));
+    menu_file.append_item(&menu_item_export_sheet);
+    let act_export_animation = export_actions::act_export_animation(model.clone(),
+                                                                    param.clone(),
+                                                                    preview_window.clone(),
+                                                                    window.clone());
+    app.add_action(&act_export_animation);
+    let menu_item_export_animation = MenuItem::new(Some("Export _animation"),
+                                                   Some( &("app.".to_string() + export_actions::ACT_EXPORT_ANIMATION) ));
+    menu_file.append_item(&menu_item_export_animation);
+    let act_export_video = export_actions::act_export_video(model.clone(),
+                                                            param.clone(),
+                                                            preview_window.clone(),
+                                                            window.clone());
+    app.add_action(&act_export_video);
+    let menu_item_export_video = MenuItem::new(Some("Export _video frames"),
+                                               Some( &("app.".to_string() + export_actions::ACT_EXPORT_VIDEO) ));
+    menu_file.append_item(&menu_item_export_video);
+    let act_export_renpy = export_actions::act_export_renpy(model.clone(),
+                                                            param.clone(),
+                                                            window.clone());
+    app.add_action(&act_export_renpy);
+    let menu_item_export_renpy = MenuItem::new(Some("Export _Ren'Py script"),
+                                               Some( &("app.".to_string() + export_actions::ACT_EXPORT_RENPY) ));
+    menu_file.append_item(&menu_item_export_renpy);
+    // export transcript ///////////////////////////////////
+    let act_export_transcript = actions::act_export_transcript(model.clone(),
+                                                               param.clone(),
+                                                               window.clone());
+    app.add_action(&act_export_transcript);
+    let menu_item_export_transcript = MenuItem::new(Some("Export _transcript"),
+                                                    Some( &("app.".to_string() + actions::ACT_FILE_EXPORT_TRANSCRIPT) ));
+    menu_file.append_item(&menu_item_export_transcript);
+    // export / import translation /////////////////////////
+    let act_export_translation = actions::act_export_translation(model.clone(),
+                                                                 param.clone(),
+                                                                 window.clone());
+    app.add_action(&act_export_translation);
+    let menu_item_export_translation = MenuItem::new(Some("Export t_ranslation(PO/XLIFF)"),
+                                                     Some( &("app.".to_string() + actions::ACT_FILE_EXPORT_TRANSLATION) ));
+    menu_file.append_item(&menu_item_export_translation);
+    let act_import_translation = actions::act_import_translation(model.clone(),
+                                                                 param.clone(),
+                                                                 mediator.clone(),
+                                                                 selection_model.clone(),
+                                                                 window.clone());
+    app.add_action(&act_import_translation);
+    let menu_item_import_translation = MenuItem::new(Some("Import tra_nslation(PO/XLIFF)"),
+                                                     Some( &("app.".to_string() + actions::ACT_FILE_IMPORT_TRANSLATION) ));
+    menu_file.append_item(&menu_item_import_translation);
+    // import script ///////////////////////////////////////
+    let act_import_script = actions::act_import_script(model.clone(),
+                                                       param.clone(),
+                                                       mediator.clone(),
+                                                       selection_model.clone(),
+                                                       window.clone(),
+                                                       history.clone());
+    app.add_action(&act_import_script);
+    let menu_item_import_script = MenuItem::new(Some("_Import script"),
+                                                Some( &("app.".to_string() + actions::ACT_FILE_IMPORT_SCRIPT) ));
+    menu_file.append_item(&menu_item_import_script);
+    // import image folder /////////////////////////////////
+    let act_import_img_dir = actions::act_import_img_dir(model.clone(),
+                                                         param.clone(),
+                                                         mediator.clone(),
+                                                         selection_model.clone(),
+                                                         window.clone(),
+                                                         history.clone());
+    app.add_action(&act_import_img_dir);
+    let menu_item_import_img_dir = MenuItem::new(Some("Import image _folder"),
+                                                 Some( &("app.".to_string() + actions::ACT_FILE_IMPORT_IMG_DIR) ));
+    menu_file.append_item(&menu_item_import_img_dir);
     ////////////////////////////////////////////////////////
     // view menu ///////////////////////////////////////////
     let menu_node_view = Menu::new();
@@ -437,6 +604,14 @@ pub fn build_ui(app: &Application) {
                                             &app,
                                             "win.");
 
+    // next_language ///////////////////////////////////
+    let act_next_language = view_actions::act_next_language(param.clone(), mediator.clone(), selection_model.clone());
+    window.add_action(&act_next_language);
+    keybind_conf.assign_acti32_and_accelkey(&vec![("NextLanguage", view_actions::ACT_NEXT_LANGUAGE, None)],
+                                            Some(&menu_node_view),
+                                            &app,
+                                            "win.");
+
     ////////////////////////////////////////////////////////
     // full screen
     let act_full_screen_preview = view_actions::act_preview(mediator.clone(),
@@ -453,6 +628,7 @@ pub fn build_ui(app: &Application) {
     menu.append_submenu(Some("Preferences"), &menu_pref);
     // edit_preference /////////////////////////////////////
     let act_edit_pref = pref_actions::act_open_pref_menu(param.clone(),
+                                                         model.clone(),
                                                          mediator.clone(),
                                                          selection_model.clone());
     app.add_action(&act_edit_pref);
@@ -678,6 +854,28 @@ mod test {
         m.dump();
     }
 
+    #[test]
+    fn test_rescale(){
+        use crate::scenario_node::Rescale;
+        let mut m = Mat::default();
+        m.pos = Position{x: 1919, y: 7};
+        m.dim = Dimension{w: 1, h: 1080};
+        m.r = 10; m.font_size = 24; m.font_outl_2 = 2.0;
+
+        // 1920x1080 -> 1280x720
+        let mut half = m.clone();
+        Rescale::between((1920, 1080), (1280, 720)).mat(&mut half);
+        assert_eq!((half.pos.x, half.pos.y, half.dim.w, half.dim.h), (1279, 5, 1, 720));
+        assert_eq!((half.r, half.font_size, half.font_outl_2), (7, 16, 2.0 * 720.0 / 1080.0));
+
+        // normalized units restore the same pixels
+        let mut norm = m.clone();
+        Rescale::to_normalized((1920, 1080)).mat(&mut norm);
+        Rescale::from_normalized((1920, 1080)).mat(&mut norm);
+        assert_eq!((norm.pos.x, norm.pos.y, norm.dim.w, norm.dim.h), (1919, 7, 1, 1080));
+        assert_eq!((norm.r, norm.font_size), (10, 24));
+    }
+
     #[test]
     fn test_search_labelled_node() {
         let sn_node1 = ScenarioNode::default();
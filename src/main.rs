@@ -10,6 +10,12 @@ const APP_ID: &str = "net.fwing.isv2";
 // main ////////////////////////////////////////////////////
 fn main() -> glib::ExitCode {
 
+    // command line operations without window
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(ret) = isv2::run_cli(&args) {
+        return ret;
+    }
+
     // Create a new application
     let app = Application::builder().application_id(APP_ID).build();
     app.connect_startup(|_| isv2::load_css());
@@ -27,6 +27,7 @@ pub enum Operation{
     MvToParentNeighbor,
     MvToDestNeighbor,
     MvToParent,
+    Import, // top level nodes appended after the last one
     Nop,
 }
 
@@ -64,6 +65,7 @@ pub struct OperationHistoryItem{
     pub src     : TreeManipulationHandle,
     pub dest    : TreeManipulationHandle,
     pub new_sno : Option<Rc<ScenarioNodeObject>>,
+    pub new_snos: Vec<Rc<ScenarioNodeObject>>, // for Import
 }
 impl OperationHistoryItem{
     pub fn default() -> Self{
@@ -72,6 +74,7 @@ impl OperationHistoryItem{
             src            : TreeManipulationHandle::default(),
             dest           : TreeManipulationHandle::default(),
             new_sno        : None,
+            new_snos       : Vec::new(),
         }
     }
     pub fn new_with_root_store(ope  : Operation,
@@ -88,6 +91,7 @@ impl OperationHistoryItem{
             src            : src_hdl,
             dest           : TreeManipulationHandle::default(),
             new_sno        : None,
+            new_snos       : Vec::new(),
         }
     }
     pub fn new_from_handle(
@@ -98,6 +102,24 @@ impl OperationHistoryItem{
             src            : hdl,
             dest           : TreeManipulationHandle::default(),
             new_sno        : None,
+            new_snos       : Vec::new(),
+        }
+    }
+    /// new_snos are appended in store after last, or make the whole list when last is None
+    pub fn new_import(store   : &gio::ListStore,
+                      last    : Option<&ScenarioNodeObject>,
+                      new_snos: Vec<Rc<ScenarioNodeObject>>) -> OperationHistoryItem{
+        let mut src_hdl = TreeManipulationHandle::default();
+        src_hdl.bt    = Cell::new(BranchType::Neighbor);
+        src_hdl.sno   = last.map(|l| l.clone().into());
+        src_hdl.store = Some(store.clone().into());
+
+        OperationHistoryItem{
+            ope            : Cell::new(Operation::Import),
+            src            : src_hdl,
+            dest           : TreeManipulationHandle::default(),
+            new_sno        : None,
+            new_snos,
         }
     }
     pub fn set_ope(&self, ope:Operation){
@@ -173,6 +195,14 @@ fn undo_add_root(h: &OperationHistoryItem){
 
     remove_node(&src_store, src_sno);
 }
+// undo_import /////////////////////////////////////////////
+fn undo_import(h: &OperationHistoryItem){
+    let src_store = h.src.store.as_ref().unwrap();
+
+    for new_sno in h.new_snos.iter().rev() { // from the last one
+        remove_node(src_store, new_sno);
+    }
+}
 // undo_moved_source ///////////////////////////////////////
 fn undo_moved_source(h: &OperationHistoryItem){
     let src_bt           = h.src.bt.get();
@@ -278,6 +308,21 @@ fn redo_add_root(h: &OperationHistoryItem){
     let src_sno   = h.src.sno.as_ref().unwrap();
     src_store.insert( 0, src_sno.as_ref() );
 }
+// redo_import /////////////////////////////////////////////
+fn redo_import(h: &OperationHistoryItem){
+    let src_store = h.src.store.as_ref().unwrap();
+    let mut last  = h.src.sno.clone();
+
+    for new_sno in &h.new_snos {
+        if let Some(l) = &last {
+            add_neighbor(l, new_sno, src_store);
+        } else { // to the empty list
+            new_sno.set_seq(0);
+            src_store.insert( 0, new_sno.as_ref() );
+        }
+        last = Some(new_sno.clone());
+    }
+}
 // redo_mv_to_neighbor /////////////////////////////////////
 fn redo_mv_to_parent_neighbor(h: &OperationHistoryItem){
     let dest_store      = h.dest.store.as_ref().unwrap();
@@ -386,6 +431,7 @@ impl OperationHistory{
             Operation::MvToParentChild    => undo_mv_to_parent_child(&h),
             Operation::MvToDestChild      => undo_mv_to_dest_child(&h),
             Operation::MvToParent         => undo_mv_to_parent(&h),
+            Operation::Import             => undo_import(&h),
             _ => ()
         }
 
@@ -408,6 +454,7 @@ impl OperationHistory{
             Operation::MvToParentChild    => redo_mv_to_parent_child(&h),
             Operation::MvToDestChild      => redo_mv_to_dest_child(&h),
             Operation::MvToParent         => redo_mv_to_parent(&h),
+            Operation::Import             => redo_import(&h),
             _ => ()
         }
         self.index.set( self.index.get() + 1 ); // increment after operation
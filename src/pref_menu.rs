@@ -7,6 +7,8 @@ pub mod pref_actions{
     use gtk::prelude::*;
     use gtk::glib::clone;
     use gtk::gio::SimpleAction;
+    use gtk::gio::ListStore;
+    use gtk::gio::Cancellable;
     use gtk::EventControllerKey;
     use gtk::Orientation;
     use gtk::SingleSelection;
@@ -17,10 +19,18 @@ pub mod pref_actions{
     use gtk::Grid;
     use gtk::Align;
     use gtk::Entry;
+    use gtk::CheckButton;
+    use gtk::AlertDialog;
     use gtk::glib::signal::Propagation;
 
+    use crate::export_util::naming;
+    use crate::export_util::naming::Part;
     use crate::isv2_mediator::Isv2Mediator;
     use crate::isv2_parameter::Isv2Parameter;
+    use crate::isv2_parameter::LanguageSetting;
+    use crate::isv2_parameter::ResampleSetting;
+    use crate::scenario_node::Rescale;
+    use crate::scenario_node_object::ScenarioNodeObject;
     use crate::sno_list::selection_to_sno;
 
     pub const ACT_EDIT_PREF   : &str = "edit_pref";
@@ -39,6 +49,85 @@ pub mod pref_actions{
         }
     }
 
+    // valid_name_template /////////////////////////////////
+    /// known placeholders only, {seq} or {id} for distinct names, and no path separator
+    pub fn valid_name_template(template: &str) -> bool {
+        let parts = if let Some(p) = naming::parse(template) { p } else { return false; };
+        parts.iter().any(|p| matches!(p, Part::Seq(_) | Part::Id)) &&
+            parts.iter().all(|p|{
+                match p {
+                    Part::Text(t) => !t.contains(['/', '\\']),
+                    _             => true,
+                }
+            }) &&
+            template.len() <= 200
+    }
+
+    // valid_language ///////////////////////////////////////
+    /// "" (original text) or a language tag like "en", "pt-BR", "zh_Hant"
+    pub fn valid_language(lang: &str) -> bool {
+        lang.len() <= 35 &&
+            lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') &&
+            !lang.starts_with(['-', '_'])
+    }
+
+    // parse_languages //////////////////////////////////////
+    /// "ja, en:Noto Sans:90" -> [ja, en(font family: Noto Sans, font size: 90%)]
+    /// the first language is the language of the original text
+    pub fn parse_languages(spec: &str) -> Option<Vec<LanguageSetting>> {
+        let mut langs: Vec<LanguageSetting> = Vec::new();
+        for item in spec.split(',') {
+            let item = item.trim();
+            if item.is_empty() { continue; }
+            let mut fields = item.splitn(3, ':').map(|f| f.trim());
+            let code = fields.next()?.to_string();
+            if code.is_empty() || !valid_language(&code) ||
+               langs.iter().any(|l| l.code == code) {
+                return None; }
+            let font_family = fields.next()
+                .filter(|f| !f.is_empty())
+                .map(|f| f.to_string());
+            let font_scale = match fields.next().map(|f| f.trim_end_matches('%')) {
+                Some(f) if !f.is_empty() => {
+                    let scale = f.parse::<u32>().ok()?;
+                    if (scale < 1) || (1000 < scale) { return None; }
+                    Some(scale)
+                },
+                _ => None,
+            };
+            langs.push(LanguageSetting{ code, font_family, font_scale });
+        }
+        Some(langs)
+    }
+    // format_languages /////////////////////////////////////
+    pub fn format_languages(langs: &[LanguageSetting]) -> String {
+        langs.iter().map(|l|{
+            match (&l.font_family, l.font_scale) {
+                (None,    None)    => l.code.clone(),
+                (Some(f), None)    => format!("{}:{}", l.code, f),
+                (f,       Some(s)) => format!("{}:{}:{}", l.code, f.clone().unwrap_or_default(), s),
+            }
+        }).collect::<Vec<_>>().join(", ")
+    }
+
+    // rescale_mats ////////////////////////////////////////
+    /// rescales every mat of the project from the previous target size
+    fn rescale_mats(store: &ListStore, from: (i32, i32), to: (i32, i32)) {
+        if (from.0 < 1) || (from.1 < 1) {
+            return; }
+        if let Some(sno) = store.item(0).and_downcast::<ScenarioNodeObject>() {
+            Rescale::between(from, to).tree(&sno.get_node());
+        }
+    }
+    // notify_prefs_changed ////////////////////////////////
+    fn notify_prefs_changed(mediator: &Isv2Mediator, selection: &SingleSelection) {
+        if let Some((sno,_store)) = selection_to_sno(selection) {
+            mediator.emit_by_name::<()>("scene-attribute-changed", &[&sno]);
+        }
+        // update the text view for the language
+        mediator.emit_by_name::<()>("sno-selected", &[selection]);
+    }
+
     // PrefEditWin /////////////////////////////////////////
     struct PrefEditWin{
         win             : Window,
@@ -47,10 +136,18 @@ pub mod pref_actions{
         target_width    : Entry,
         target_height   : Entry,
         export_dir      : Entry,
+        name_template   : Entry,
+        language        : Entry,
+        ref_language    : Entry,
+        languages       : Entry,
+        normalized      : CheckButton,
+        high_quality    : CheckButton,
+        supersample     : Entry,
         button_box      : Box,
         ok_button       : Button,
         cancel_button   : Button,
         param           : Isv2Parameter,
+        store           : ListStore,
         mediator        : Isv2Mediator,
         selection       : SingleSelection,
     }
@@ -95,18 +192,77 @@ pub mod pref_actions{
             if !valid_export_dir_name(&export_dir){
                 return; }
 
+            let name_template = self.name_template.buffer().text().trim().to_string();
+            if !valid_name_template(&name_template){
+                return; }
+
+            let languages = if let Some(l) = parse_languages(&self.languages.buffer().text()) { l }
+                            else { return; };
+            // the editing/reference language should be one of the languages when they are listed
+            let listed = |lang: &str| -> bool {
+                lang.is_empty() || languages.is_empty() || languages.iter().any(|l| l.code == lang) };
+
+            let language = self.language.buffer().text().trim().to_string();
+            if !valid_language(&language) || !listed(&language){
+                return; }
+            let ref_language = self.ref_language.buffer().text().trim().to_string();
+            if !valid_language(&ref_language) || !listed(&ref_language){
+                return; }
+
+            let supersample = {
+                if let Ok(n) = self.supersample.buffer().text().trim().parse::<u32>(){
+                    n }
+                else {
+                    return; }
+            };
+            if (supersample < 1) || (4 < supersample) {
+                return; }
+
+            let prev_size = (self.param.target_width(), self.param.target_height());
+            let size      = (target_width, target_height);
+
             self.param.set_property("target_width",  target_width);
             self.param.set_property("target_height", target_height);
             self.param.set_property("export_dir",    export_dir);
+            self.param.set_property("name_template", name_template);
+            self.param.set_property("language",      language);
+            self.param.set_property("ref_language",  ref_language);
+            self.param.set_languages(languages);
+            self.param.set_property("normalized_geometry", self.normalized.is_active());
+            self.param.set_resample(ResampleSetting{ high_quality: self.high_quality.is_active(), supersample });
 
-            if let Some((sno,_store)) = selection_to_sno(&self.selection) {
-                self.mediator.emit_by_name::<()>("scene-attribute-changed", &[&sno]);
+            // normalized geometry follows the target size, otherwise ask
+            if (prev_size != size) && self.param.normalized_geometry() {
+                rescale_mats(&self.store, prev_size, size);
+            } else if prev_size != size {
+                self.confirm_rescale(prev_size, size);
             }
+            notify_prefs_changed(&self.mediator, &self.selection);
 
             self.win.close();
         }
+        // confirm_rescale /////////////////////////////////
+        fn confirm_rescale(&self, prev_size: (i32, i32), size: (i32, i32)) {
+            let dialog = AlertDialog::builder().modal(true).build();
+            dialog.set_buttons(&["Rescale mats", "Keep positions"]);
+            dialog.set_message(
+                &format!("The target size is changed from {}x{} to {}x{}. Rescale all mats to the new size?",
+                         prev_size.0, prev_size.1, size.0, size.1));
+            dialog.set_default_button(0);
+            dialog.set_cancel_button(1);
+            let store     = self.store.clone();
+            let mediator  = self.mediator.clone();
+            let selection = self.selection.clone();
+            dialog.choose(None::<&Window>, None::<&Cancellable>, move|result|{
+                if let Ok(0) = result {
+                    rescale_mats(&store, prev_size, size);
+                    notify_prefs_changed(&mediator, &selection);
+                }
+            });
+        }
         // build ///////////////////////////////////////////
         fn build(param    : Isv2Parameter,
+                 store    : ListStore,
                  mediator : Isv2Mediator,
                  selection: SingleSelection) -> Rc<Self> {
             let win           = Window::builder().title( String::from("preferences") ).modal(true).build();
@@ -115,6 +271,13 @@ pub mod pref_actions{
             let target_width  = Entry::new();
             let target_height = Entry::new();
             let export_dir    = Entry::new();
+            let name_template = Entry::new();
+            let language      = Entry::new();
+            let ref_language  = Entry::new();
+            let languages     = Entry::new();
+            let normalized    = CheckButton::new();
+            let high_quality  = CheckButton::new();
+            let supersample   = Entry::new();
             let button_box    = Box::builder().orientation(Orientation::Horizontal).build();
             let ok_button     = Button::builder().css_classes(vec!["isv2_button"]).build();
             let cancel_button = Button::builder().css_classes(vec!["isv2_button"]).build();
@@ -122,9 +285,10 @@ pub mod pref_actions{
             // properties //////////////////////////////////
             let obj = Self{
                 win, vbox, grid,
-                target_width, target_height, export_dir,
+                target_width, target_height, export_dir, name_template, language, ref_language, languages, normalized,
+                high_quality, supersample,
                 button_box, ok_button, cancel_button,
-                param, mediator, selection};
+                param, store, mediator, selection};
             let obj = Rc::new(obj);
 
             let target_width_label =
@@ -145,6 +309,48 @@ pub mod pref_actions{
             obj.grid.attach(&obj.export_dir, 1, 2, 1, 1);
             obj.export_dir.buffer().set_text( &(obj.param.property::<String>("export_dir")) );
 
+            let name_template_label =
+                Label::builder().label("file name({seq}{group}{scene}{page}{id})").halign(Align::End).build();
+            obj.grid.attach(&name_template_label, 0, 3, 1, 1);
+            obj.grid.attach(&obj.name_template, 1, 3, 1, 1);
+            obj.name_template.buffer().set_text( &(obj.param.property::<String>("name_template")) );
+
+            let languages_label =
+                Label::builder().label("languages(code[:font[:size%]], ..)").halign(Align::End).build();
+            obj.grid.attach(&languages_label, 0, 4, 1, 1);
+            obj.grid.attach(&obj.languages, 1, 4, 1, 1);
+            obj.languages.buffer().set_text( &format_languages(&obj.param.languages()) );
+
+            let language_label =
+                Label::builder().label("editing language(empty: original)").halign(Align::End).build();
+            obj.grid.attach(&language_label, 0, 5, 1, 1);
+            obj.grid.attach(&obj.language, 1, 5, 1, 1);
+            obj.language.buffer().set_text( &(obj.param.property::<String>("language")) );
+
+            let ref_language_label =
+                Label::builder().label("reference language(empty: hidden)").halign(Align::End).build();
+            obj.grid.attach(&ref_language_label, 0, 6, 1, 1);
+            obj.grid.attach(&obj.ref_language, 1, 6, 1, 1);
+            obj.ref_language.buffer().set_text( &(obj.param.property::<String>("ref_language")) );
+
+            let normalized_label =
+                Label::builder().label("save mat geometry relative to the target size").halign(Align::End).build();
+            obj.grid.attach(&normalized_label, 0, 7, 1, 1);
+            obj.grid.attach(&obj.normalized, 1, 7, 1, 1);
+            obj.normalized.set_active( obj.param.normalized_geometry() );
+
+            let high_quality_label =
+                Label::builder().label("high quality resampling in export").halign(Align::End).build();
+            obj.grid.attach(&high_quality_label, 0, 8, 1, 1);
+            obj.grid.attach(&obj.high_quality, 1, 8, 1, 1);
+            obj.high_quality.set_active( obj.param.resample().high_quality );
+
+            let supersample_label =
+                Label::builder().label("export supersampling[1--4]").halign(Align::End).build();
+            obj.grid.attach(&supersample_label, 0, 9, 1, 1);
+            obj.grid.attach(&obj.supersample, 1, 9, 1, 1);
+            obj.supersample.buffer().set_text( &(obj.param.resample().supersample.to_string()) );
+
             // buttons /////////////////////////////////////
             obj.button_box.set_halign(Align::End);
             obj.button_box.set_homogeneous(true);
@@ -172,6 +378,10 @@ pub mod pref_actions{
             obj.target_width.connect_activate(kctrl_for_entry.clone());
             obj.target_height.connect_activate(kctrl_for_entry.clone());
             obj.export_dir.connect_activate(kctrl_for_entry.clone());
+            obj.name_template.connect_activate(kctrl_for_entry.clone());
+            obj.language.connect_activate(kctrl_for_entry.clone());
+            obj.ref_language.connect_activate(kctrl_for_entry.clone());
+            obj.languages.connect_activate(kctrl_for_entry.clone());
 
             obj.win.add_controller(Self::pref_editor_key_ctrl(obj.clone()));
 
@@ -183,11 +393,12 @@ pub mod pref_actions{
     }
     // act_open_pref_menu //////////////////////////////////
     pub fn act_open_pref_menu(param    : Isv2Parameter,
+                              store    : ListStore,
                               mediator : Isv2Mediator,
                               selection: SingleSelection) -> SimpleAction {
         let act_edit_pref = SimpleAction::new(ACT_EDIT_PREF, None);
         act_edit_pref.connect_activate(move|_act, _val|{
-            PrefEditWin::build(param.clone(), mediator.clone(), selection.clone());
+            PrefEditWin::build(param.clone(), store.clone(), mediator.clone(), selection.clone());
         });
         act_edit_pref
     }
@@ -197,6 +408,9 @@ pub mod pref_actions{
 #[cfg(test)]
 mod tests {
     use crate::pref_actions::valid_export_dir_name;
+    use crate::pref_actions::valid_language;
+    use crate::pref_actions::valid_name_template;
+    use crate::pref_actions::{parse_languages, format_languages};
 
     #[test]
     fn test_valid_export_dir_name() {
@@ -206,4 +420,43 @@ mod tests {
         assert_eq!(false, valid_export_dir_name("/"));
         assert_eq!(false, valid_export_dir_name("a/b"));
     }
+
+    #[test]
+    fn test_valid_name_template() {
+        assert_eq!(true,  valid_name_template("{seq}"));
+        assert_eq!(true,  valid_name_template("{group}_{scene}_{page}_{id}"));
+        assert_eq!(true,  valid_name_template("p{seq:3}"));
+        assert_eq!(false, valid_name_template("{scene}_{page}")); // names can collide
+        assert_eq!(false, valid_name_template("{seq}/{page}"));
+        assert_eq!(false, valid_name_template("{seq}_{name}"));
+        assert_eq!(false, valid_name_template("{seq"));
+        assert_eq!(false, valid_name_template(""));
+    }
+
+    #[test]
+    fn test_valid_language() {
+        assert_eq!(true,  valid_language(""));
+        assert_eq!(true,  valid_language("en"));
+        assert_eq!(true,  valid_language("pt-BR"));
+        assert_eq!(false, valid_language("-en"));
+        assert_eq!(false, valid_language("en/us"));
+        assert_eq!(false, valid_language("日本語"));
+    }
+
+    #[test]
+    fn test_parse_languages() {
+        let langs = parse_languages("ja, en:Noto Sans:90%, zh::110").unwrap();
+        assert_eq!(langs.len(), 3);
+        assert_eq!(langs[0].code, "ja");
+        assert_eq!(langs[0].font_family, None);
+        assert_eq!(langs[1].font_family, Some("Noto Sans".to_string()));
+        assert_eq!(langs[1].font_scale, Some(90));
+        assert_eq!(langs[2].font_family, None);
+        assert_eq!(langs[2].font_scale, Some(110));
+        assert_eq!(format_languages(&langs), "ja, en:Noto Sans:90, zh::110");
+        assert_eq!(parse_languages(""), Some(vec![]));
+        assert_eq!(parse_languages("ja, ja"), None);
+        assert_eq!(parse_languages("en::0"), None);
+        assert_eq!(parse_languages("e/n"), None);
+    }
 }
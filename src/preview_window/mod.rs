@@ -4,7 +4,6 @@ use glib::Object;
 use glib::WeakRef;
 use glib::closure_local;
 use glib::subclass::types::ObjectSubclassIsExt;
-use glib_sys;
 use glib::clone;
 use gtk::Align;
 use gtk::Box;
@@ -17,40 +16,60 @@ use gtk::Buildable;
 use gtk::ConstraintTarget;
 use gtk::EventControllerMotion;
 use gtk::GestureClick;
+use gtk::ListBox;
+use gtk::ListView;
+use gtk::ScrolledWindow;
 use gtk::SingleSelection;
 use gtk::TreeListRow;
 use gtk::Window;
-use gtk::cairo::Antialias;
 //use gtk::cairo::Context;
-use gtk::cairo::FontOptions;
 use gtk::cairo::Format;
 use gtk::cairo::ImageSurface;
-use gtk::gdk_pixbuf::InterpType;
+use gtk::cairo::PdfMetadata;
+use gtk::cairo::PdfOutline;
+use gtk::cairo::PdfSurface;
+use gtk::cairo::PDF_OUTLINE_ROOT;
 use gtk::gdk_pixbuf::Pixbuf;
 use gtk::glib;
-use gtk::pango::FontDescription;
-use gtk::pango::Layout;
-use gtk::pango::Style;
 use gtk::prelude::*;
 
 use std::cell::Cell;
-use std::fs::OpenOptions;
-use std::fs::File;
+use std::collections::HashSet;
+use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
-use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::SystemTime;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::Hasher;
-use std::collections::HashMap;
 use anyhow::Context;
 use anyhow::Result;
-use std::time::Duration;
 
 
 use crate::drawing_util::util::CursorState;
+use crate::export_util::animation;
+use crate::export_util::contact_sheet;
+use crate::export_util::html_viewer;
+use crate::export_util::image_format::Content;
+use crate::export_util::image_format::ImageFormat;
+use crate::export_util::manifest;
+use crate::export_util::metadata;
+use crate::export_util::stamp;
+use crate::export_util::stamp::StampContent;
+use crate::export_util::naming;
+use crate::export_util::package;
+use crate::export_util::pdf;
+use crate::export_util::preflight;
+use crate::export_util::preflight::PreflightLang;
+use crate::export_util::preflight::Severity;
+use crate::export_util::profile::ExportTarget;
+use crate::export_util::subset;
+use crate::export_util::subset::Subset;
+use crate::export_util::video;
+use crate::drawing_util::render;
+use crate::drawing_util::render::Filter;
+use crate::drawing_util::render::RenderSetting;
+use crate::drawing_util::render::Renderer;
+use crate::drawing_util::render::SceneJob;
 use crate::drawing_util::util;
 use crate::isv2_parameter::Isv2Parameter;
 use crate::scenario_node::Item;
@@ -58,6 +77,7 @@ use crate::scenario_node::LabelType;
 use crate::scenario_node::ScenarioNode;
 use crate::scenario_node_object::ScenarioNodeObject;
 use crate::status_bar::StatusBar;
+use crate::view_menu::view_actions::select_node;
 
 glib::wrapper! {
     pub struct PreviewWindow(ObjectSubclass<imp::PreviewWindow>)
@@ -65,6 +85,18 @@ glib::wrapper! {
         @implements Accessible, Buildable, ConstraintTarget;
 }
 
+// ExportSetup /////////////////////////////////////////////
+/// what the exports of the current language into one file or directory start with
+struct ExportSetup{
+    target_w : i32,
+    target_h : i32,
+    lang     : String,
+    text_key : String,
+    title    : String,
+    setting  : RenderSetting,
+    root     : Rc<ScenarioNode>,
+}
+
 // PreviewWindow ///////////////////////////////////////////
 impl PreviewWindow {
     pub fn set_mediator(&self, m: WeakRef<Object>){ *self.imp().mediator.borrow_mut() = m; }
@@ -77,53 +109,17 @@ impl PreviewWindow {
                            target_w   : i32,
                            target_h   : i32,
                            cr         : &cairo::Context,
-                           param      : Option<Isv2Parameter>
+                           param      : Option<Isv2Parameter>,
+                           bg_uri     : Option<&str>
     ){
-        if let Some(s) = ScenarioNode::get_belong_scene(sn){
-            // resolve label
-            let label_sno = {
-                if let Some(s_resolved) = ScenarioNode::search_def_label(s.clone()) { s_resolved }
-                else { s }}; // resolve ref label
-
-            // fill background
-            let c: Vec<_> =
-                label_sno.get_scene_bgcol().unwrap().iter().map(|c|{ (*c as f64) / 255.0 }).collect();
-            cr.set_source_rgb( c[0], c[1], c[2] );
-            cr.rectangle(0.0, 0.0, target_w as f64, target_h as f64);
-            if cr.fill().is_err(){
-                println!("fill background on PreviewWindow failed");
-                return; }
-
-            // draw image
-            if !label_sno.get_scene_bg_en().unwrap() { return; }
+        let job = ScenarioNode::get_belong_scene(sn).and_then(|s| SceneJob::of(&s, Path::new("")));
+        if let Some(mut job) = job {
             if let Some(param) = param {
-                if !param.property::<bool>("bgimg_en") { return; } }
-            if let Some(pbuf) = pbuf {
-                let (_, _, mut crop_w, mut crop_h, crop_en) = label_sno.get_scene_crop_pos_dim().unwrap();
-                if !crop_en {
-                    crop_w = pbuf.width();
-                    crop_h = pbuf.height();
-                }
-                let (_, crop_target_w, crop_target_h, crop_target_ofst_x, crop_target_ofst_y) =
-                    util::get_scale_offset(crop_w, crop_h, target_w, target_h);
-                let scale_crop_pixbuf = {
-                    if let Some(ref p) = scale_pbuf { p.clone() }
-                    else { return; }};
-
-                cr.set_source_pixbuf(&scale_crop_pixbuf,
-                                     crop_target_ofst_x as f64, crop_target_ofst_y as f64);
-                cr.rectangle(crop_target_ofst_x as f64, crop_target_ofst_y as f64,
-                             crop_target_w      as f64, crop_target_h      as f64);
-                if cr.fill().is_err(){
-                    println!("draw image on PreviewWindow failed!");
-                }
-            } else {
-                // println!("pbuf for background has not been prepared!");
-            }
+                job.bg_en &= param.property::<bool>("bgimg_en"); }
+            render::draw_scene(cr, &job, pbuf, scale_pbuf, target_w, target_h, bg_uri);
         } else {
             println!("belong scene is not found for {:?}!", sn);
         }
-
     }
     // new /////////////////////////////////////////////////
     pub fn new() -> Self {
@@ -247,7 +243,8 @@ impl PreviewWindow {
                                           target_w,
                                           target_h,
                                           cr,
-                                          Some(param.clone())
+                                          Some(param.clone()),
+                                          None
                 );
             }
             pwin.draw_mats(cr, _w, _h);
@@ -321,27 +318,8 @@ impl PreviewWindow {
                                   pbuf : &Pixbuf) -> Option<Pixbuf>{
         let target_w = param.property::<i32>("target_width");
         let target_h = param.property::<i32>("target_height");
-
-        let (mut crop_x, mut crop_y, mut crop_w, mut crop_h, crop_en)
-            = scene.get_scene_crop_pos_dim().unwrap();
-        if !crop_en {
-            crop_x = 0; crop_y = 0;
-            crop_w = pbuf.width();
-            crop_h = pbuf.height();
-        }
-        let crop_pbuf = {
-            if let Some (p) = Pixbuf::new( pbuf.colorspace(),
-                                           true,
-                                           pbuf.bits_per_sample(),
-                                           crop_w,
-                                           crop_h ) { p }
-            else { return None; } };
-        pbuf.copy_area( crop_x, crop_y, crop_w, crop_h, &crop_pbuf, 0, 0 );
-        let (_, crop_target_w, crop_target_h, _, _) =
-            util::get_scale_offset(crop_w, crop_h, target_w, target_h);
-        let crop_pbuf = crop_pbuf.scale_simple( crop_target_w, crop_target_h, InterpType::Bilinear ).unwrap();
-
-        crop_pbuf.copy()
+        let crop = SceneJob::of(scene, Path::new(""))?.crop;
+        render::scale_crop(pbuf, crop, target_w, target_h, Filter::Fast)
     }
     pub fn prepare_scale_crop_buf(&self, scene: Rc<ScenarioNode>){
         let pbuf = {
@@ -376,37 +354,641 @@ impl PreviewWindow {
                 overwrite.set(false); }
         }
     }
-    // export_images ///////////////////////////////////////
-    pub fn export_images(&self,
-                         n     : &Rc<ScenarioNode>,
-                         param : &Isv2Parameter,
-                         root  : &impl IsA<Window>){
-
-        let status_bar = (&*self.imp().status_bar.borrow()).clone().unwrap();
+    // preflight ///////////////////////////////////////////
+    /// problems of the pages under root for the languages and stamps of targets, in page order
+    pub fn preflight(&self, param: &Isv2Parameter, root: &Rc<ScenarioNode>, targets: &[ExportTarget]) -> Vec<preflight::Issue>{
+        let project_dir = param.property::<PathBuf>("project_dir");
+        let mut langs: Vec<PreflightLang> = Vec::new();
+        for t in targets {
+            if langs.iter().any(|l| l.lang == t.lang) {
+                continue; }
+            langs.push(PreflightLang{
+                lang        : t.lang.clone(),
+                text_key    : param.text_key(&t.lang),
+                font_family : param.language_setting(&t.lang).and_then(|s| s.font_family) });
+        }
+        let renderers: Vec<Renderer> = langs.iter().map(|l| Renderer::new(self.render_setting(param, &l.lang))).collect();
+        let installed = renderers.first().map(|r| r.font_families()).unwrap_or_default();
+        let mut issues = preflight::check(root, &project_dir, &langs, &installed);
+
+        // text overflow, mats of a scene are checked once
+        let mut checked = HashSet::new();
+        let mut page    = 0;
+        let mut vec     = vec![root.clone()];
+        while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+            if !(sn.is_page() || sn.is_pmat()) {
+                continue; }
+            let mut area = Vec::new();
+            if let Some(scene_node) = ScenarioNode::get_belong_scene(&sn) {
+                render::collect_mats_in_scene(&scene_node, &mut area); }
+            render::collect_mats(&sn, &mut area);
+            for (src, lbl_ref) in area {
+                for (l, renderer) in langs.iter().zip(&renderers) {
+                    if !checked.insert((src.id.get(), l.lang.clone())) {
+                        continue; }
+                    let jobs = render::mat_jobs(&[(src.clone(), lbl_ref.clone())], &l.text_key, None);
+                    if jobs.iter().any(|j| renderer.overflows(j)) {
+                        issues.push(preflight::overflow_issue(&src, page, l)); }
+                }
+            }
+            page+= 1;
+        }
+        // images of the stamps
+        for t in targets {
+            for s in &t.stamps {
+                if let StampContent::Image(i) = &s.content {
+                    if let Some(m) = preflight::image_problem(&project_dir, Path::new(i)) {
+                        issues.push(preflight::Issue{
+                            severity : Severity::Error,
+                            kind     : preflight::IssueKind::MissingImage,
+                            page     : 0,
+                            node     : root.clone(),
+                            message  : format!("stamp {}", m) }); }
+                }
+            }
+        }
+        // formats without a writer
+        for t in targets.iter().filter(|t| !render::can_write(t.format)) {
+            issues.push(preflight::Issue{
+                severity : Severity::Error,
+                kind     : preflight::IssueKind::UnsupportedFormat,
+                page     : 0,
+                node     : root.clone(),
+                message  : format!("{} can not be written into {}", t.format.extension(), t.out_dir.display()) });
+        }
+        issues.sort_by_key(|i| i.page);
+        issues
+    }
+    // confirm_preflight ///////////////////////////////////
+    /// shows the issues, true if the export goes on.
+    /// activating a row selects its node in the tree.
+    async fn confirm_preflight(root   : Window,
+                               issues : Vec<preflight::Issue>,
+                               sel    : Option<SingleSelection>) -> bool{
+        let errors = issues.iter().filter(|i| i.severity == Severity::Error).count();
+        let win    = Window::builder().title(String::from("export preflight")).modal(true).transient_for(&root)
+            .default_width(720).default_height(400).build();
+        let vbox   = Box::new(Orientation::Vertical, 4);
+        let label  = Label::builder()
+            .label(&format!("{} errors, {} warnings. activate a row to select the node.", errors, issues.len() - errors))
+            .halign(Align::Start).build();
+        let list   = ListBox::new();
+        for i in &issues {
+            let row = Label::builder().label(&i.line()).halign(Align::Start).build();
+            if i.severity == Severity::Error {
+                row.add_css_class("error"); }
+            list.append(&row);
+        }
+        list.connect_row_activated(move |_list, row|{
+            if let (Some(sel), Some(i)) = (&sel, issues.get(row.index() as usize)) {
+                select_node(sel, &i.node); }
+        });
+        let scrolled = ScrolledWindow::builder().child(&list).vexpand(true).build();
 
+        let (sender, receiver) = async_channel::bounded(1);
+        let button_box = Box::new(Orientation::Horizontal, 4);
+        button_box.set_halign(Align::End);
+        for (text, go_on) in [("Abort", false), ("Export anyway", true)] {
+            let button = Button::with_label(text);
+            button.connect_clicked(clone!(@strong sender => move|_b|{
+                let _ = sender.try_send(go_on); }));
+            button_box.append(&button);
+        }
+        win.connect_close_request(clone!(@strong sender => move|_w|{
+            let _ = sender.try_send(false);
+            glib::Propagation::Proceed
+        }));
+        vbox.append(&label);
+        vbox.append(&scrolled);
+        vbox.append(&button_box);
+        win.set_child(Some(&vbox));
+        win.present();
+
+        let go_on = receiver.recv().await.unwrap_or(false);
+        win.destroy();
+        go_on
+    }
+    /// selection of the tree view, to jump to a node from a report
+    fn tree_selection(&self) -> Option<SingleSelection>{
+        let mediator = self.imp().mediator.borrow().upgrade()?;
+        mediator.property::<Object>("list_view").downcast::<ListView>().ok()?
+            .model()?.downcast::<SingleSelection>().ok()
+    }
+    // prepare_scene_bufs //////////////////////////////////
+    /// loads the background image of the scene and its scaled and cropped buffer
+    fn prepare_scene_bufs(param: &Isv2Parameter, sn: &Rc<ScenarioNode>) -> (Option<Pixbuf>, Option<Pixbuf>){
         let target_w = param.property::<i32>("target_width");
         let target_h = param.property::<i32>("target_height");
-
-        // find root
+        if let Some(job) = SceneJob::of(sn, &param.property::<PathBuf>("project_dir")) {
+            render::load_scene(&job, target_w, target_h, param.resample().filter())
+        } else {
+            println!("the node is not scene");
+            (None, None)
+        }
+    }
+    // draw_page ///////////////////////////////////////////
+    /// draws the scene and the mats of a page or pmat node for exporting.
+    /// reveal limits the characters of the mats in the page for the typewriter.
+    fn draw_page(renderer   : &mut Renderer,
+                 sn         : &Rc<ScenarioNode>,
+                 pbuf       : &Option<Pixbuf>,
+                 scale_pbuf : &Option<Pixbuf>,
+                 cr         : &cairo::Context,
+                 text_key   : &str,
+                 bg_uri     : Option<&str>,
+                 reveal     : Option<usize>) -> bool{
+        let job =
+            if let Some(j) = render::page_job(sn, renderer.project_dir(), text_key, bg_uri, reveal) { j }
+            else { println!("the scene to which new node belongs was not found!"); return false; };
+        renderer.draw(cr, &job, Some((pbuf, scale_pbuf)));
+        true
+    }
+    // project_root ////////////////////////////////////////
+    /// root of the tree which n belongs to
+    fn project_root(n: &Rc<ScenarioNode>) -> Rc<ScenarioNode>{
         let mut p = n.clone();
         loop{
             let pp = p.parent.borrow().upgrade();
-            if pp.is_none() {
-                break; }
-            p = pp.unwrap();
+            if let Some(pp) = pp { p = pp; } else { return p; }
         }
-        // traverse
-        let mut img_seq    = 0;
+    }
+    // project_title ///////////////////////////////////////
+    /// file name of the project without the extension
+    fn project_title(param: &Isv2Parameter) -> String{
+        Path::new(&param.property::<String>("project_file_name"))
+            .file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+    }
+    /// setting of the exported images of the language, with the font settings of the preview
+    fn render_setting(&self, param: &Isv2Parameter, lang: &str) -> RenderSetting{
+        RenderSetting::new(&param.property::<PathBuf>("project_dir"),
+                           param.property::<i32>("target_width"),
+                           param.property::<i32>("target_height"),
+                           param.language_setting(lang))
+            .resample(&param.resample())
+            .font(&self.pango_context())
+    }
+    // export_setup ////////////////////////////////////////
+    /// the current language and the project of n
+    fn export_setup(&self, n: &Rc<ScenarioNode>, param: &Isv2Parameter) -> ExportSetup{
+        let lang = param.property::<String>("language");
+        ExportSetup{
+            target_w : param.property::<i32>("target_width"),
+            target_h : param.property::<i32>("target_height"),
+            text_key : param.text_key(&lang),
+            title    : Self::project_title(param),
+            setting  : self.render_setting(param, &lang),
+            root     : Self::project_root(n),
+            lang,
+        }
+    }
+    // for_each_page ///////////////////////////////////////
+    /// calls f for every page and pmat under root with the buffers of its scene,
+    /// in the same order as the exported images. returns the number of pages.
+    fn for_each_page(param : &Isv2Parameter,
+                     root  : &Rc<ScenarioNode>,
+                     mut f : impl FnMut(&Rc<ScenarioNode>, &Option<Pixbuf>, &Option<Pixbuf>) -> Result<()>) -> Result<usize>{
         let mut pbuf       = None::<Pixbuf>;
         let mut scale_pbuf = None::<Pixbuf>;
-        let mut vec        = vec![p.clone()]; // for ScenarioNdoe traversal
+        let mut page_num   = 0;
+        let mut vec        = vec![root.clone()];
+        while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+            match &*sn.value.borrow() {
+                Item::Scene(_) => {
+                    (pbuf, scale_pbuf) = Self::prepare_scene_bufs(param, &sn); },
+                Item::Page(_) | Item::Pmat(_) => {
+                    f(&sn, &pbuf, &scale_pbuf).with_context(|| format!("page {:04}", page_num))?;
+                    page_num += 1;
+                },
+                _ => (),
+            }
+        }
+        Ok(page_num)
+    }
+    // export_pdf //////////////////////////////////////////
+    /// exports every page of the current language into one pdf file.
+    /// mat texts are kept as text, and the outline is built from groups and scenes.
+    pub fn export_pdf(&self,
+                      n     : &Rc<ScenarioNode>,
+                      param : &Isv2Parameter,
+                      path  : &Path) -> Result<usize>{
+        let ExportSetup{ target_w, target_h, lang, text_key, title, setting, root: p } = self.export_setup(n, param);
+        let mut renderer = Renderer::new(setting);
+
+        let surface = PdfSurface::new(target_w as f64, target_h as f64, path)
+            .with_context(|| format!("can not create {}", path.display()))?;
+        surface.set_metadata(PdfMetadata::Title, &title)?;
+        surface.set_metadata(PdfMetadata::Creator, env!("CARGO_PKG_NAME"))?;
+        let cr = cairo::Context::new(&surface)?;
+
+        let file_name = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+        let mut meta  = metadata::ExportMetadata::new(&title, &lang, target_w, target_h);
+        let page_num = Self::for_each_page(param, &p, |sn, pbuf, scale_pbuf|{
+            cr.save()?;
+            if !Self::draw_page(&mut renderer, sn, pbuf, scale_pbuf, &cr, &text_key, None, None) {
+                anyhow::bail!("the page has no scene"); }
+            cr.restore()?;
+            cr.show_page()?;
+            let seq = meta.files.len();
+            meta.push(&file_name, Some(seq + 1), metadata::page_meta(sn, seq, &text_key));
+            Ok(())
+        })?;
+        // outline
+        let mut ids = Vec::new();
+        for e in pdf::outline(&p, true) {
+            let parent = e.parent.map(|i| ids[i]).unwrap_or(PDF_OUTLINE_ROOT);
+            ids.push( surface.add_outline(parent, &e.title, &format!("page={}", e.page), PdfOutline::empty())? );
+        }
+        drop(cr);
+        surface.finish();
+        surface.status()?;
+        meta.save(&metadata::sidecar_path(path))?;
+
+        if let Some(status_bar) = &*self.imp().status_bar.borrow() {
+            status_bar.set_status(&format!("{} pages:{}", page_num, path.display())); }
+        Ok(page_num)
+    }
+    // export_package //////////////////////////////////////
+    /// exports every page of the current language into a CBZ or an EPUB file
+    /// depending on the extension of path
+    pub fn export_package(&self,
+                          n     : &Rc<ScenarioNode>,
+                          param : &Isv2Parameter,
+                          path  : &Path) -> Result<usize>{
+        let ExportSetup{ target_w, target_h, lang, text_key, title, setting, root: p } = self.export_setup(n, param);
+        let mut renderer = Renderer::new(setting);
+
+        let mut pages = Vec::new();
+        let mut metas = Vec::new();
+        Self::for_each_page(param, &p, |sn, pbuf, scale_pbuf|{
+            let surface = ImageSurface::create(Format::ARgb32, target_w, target_h)?;
+            let cr = cairo::Context::new(&surface)?;
+            if !Self::draw_page(&mut renderer, sn, pbuf, scale_pbuf, &cr, &text_key, None, None) {
+                anyhow::bail!("the page has no scene"); }
+            drop(cr);
+            let mut png = Vec::new();
+            surface.write_to_png(&mut png)?;
+            pages.push(package::PackagePage{ png, text: package::page_text(sn, &text_key) });
+            metas.push(metadata::page_meta(sn, metas.len(), &text_key));
+            Ok(())
+        })?;
+
+        let info = package::PackageInfo{
+            title    : title,
+            lang     : lang.clone(),
+            width    : target_w,
+            height   : target_h,
+            modified : package::iso8601(SystemTime::now()),
+        };
+        package::write_package(path, &info, &p, &pages)?;
+        let file_name = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+        let mut meta  = metadata::ExportMetadata::new(&info.title, &lang, target_w, target_h);
+        for (i, m) in metas.into_iter().enumerate() {
+            meta.push(&file_name, Some(i + 1), m); }
+        meta.save(&metadata::sidecar_path(path))?;
+
+        if let Some(status_bar) = &*self.imp().status_bar.borrow() {
+            status_bar.set_status(&format!("{} pages:{}", pages.len(), path.display())); }
+        Ok(pages.len())
+    }
+    // export_html /////////////////////////////////////////
+    /// exports every page of the current language with a static viewer (dir/index.html).
+    /// text_on shows the text overlay when the viewer is opened. returns the number of pages.
+    pub fn export_html(&self,
+                       n       : &Rc<ScenarioNode>,
+                       param   : &Isv2Parameter,
+                       dir     : &Path,
+                       text_on : bool) -> Result<usize>{
+        let ExportSetup{ target_w, target_h, lang, text_key, title, setting, root: p } = self.export_setup(n, param);
+        let mut renderer = Renderer::new(setting);
+
+        let mut pages = Vec::new();
+        let mut metas = Vec::new();
+        Self::for_each_page(param, &p, |sn, pbuf, scale_pbuf|{
+            let surface = ImageSurface::create(Format::ARgb32, target_w, target_h)?;
+            let cr = cairo::Context::new(&surface)?;
+            if !Self::draw_page(&mut renderer, sn, pbuf, scale_pbuf, &cr, &text_key, None, None) {
+                anyhow::bail!("the page has no scene"); }
+            drop(cr);
+            let mut png = Vec::new();
+            surface.write_to_png(&mut png)?;
+            let i    = pages.len();
+            let name = sn.get_page_name().unwrap_or_default();
+            let page = html_viewer::ViewerPage{
+                image : html_viewer::image_name(i),
+                title : format!("{:04} {}", i, name).trim_end().to_string(),
+                text  : package::page_text(sn, &text_key) };
+            metas.push(metadata::page_meta(sn, i, &text_key));
+            pages.push((page, png));
+            Ok(())
+        })?;
+
+        html_viewer::write_viewer(dir, &title, &lang, &p, &pages, text_on)?;
+        let mut meta = metadata::ExportMetadata::new(&title, &lang, target_w, target_h);
+        for ((page, _), m) in pages.iter().zip(metas) {
+            meta.push(&page.image, None, m); }
+        meta.save(&dir.join(metadata::METADATA_NAME))?;
+
+        if let Some(status_bar) = &*self.imp().status_bar.borrow() {
+            status_bar.set_status(&format!("{} pages:{}", pages.len(), dir.join("index.html").display())); }
+        Ok(pages.len())
+    }
+    // export_contact_sheet ////////////////////////////////
+    /// exports thumbnails of every page in grids of the contact sheet setting
+    /// into a pdf, or png files numbered for each sheet. returns the number of sheets.
+    pub fn export_contact_sheet(&self,
+                                n     : &Rc<ScenarioNode>,
+                                param : &Isv2Parameter,
+                                path  : &Path) -> Result<usize>{
+        let ExportSetup{ target_w, target_h, lang, text_key, title, setting, root: p } = self.export_setup(n, param);
+        let project_dir = setting.project_dir.clone();
+        let layout      = contact_sheet::SheetLayout::new(&param.contact_sheet(), target_w, target_h);
+        let format      = contact_sheet::SheetFormat::from_path(path)
+            .with_context(|| format!("unknown contact sheet format: {}", path.display()))?;
+        let mut renderer = Renderer::new(setting.output(layout.thumb_w, layout.thumb_h));
+
+        let mut pages = Vec::new();
+        let mut metas = Vec::new();
+        let mut vec   = vec![p.clone()];
+        while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+            if !matches!(&*sn.value.borrow(), Item::Page(_) | Item::Pmat(_)) {
+                continue; }
+            let job = render::page_job(&sn, &project_dir, &text_key, None, None)
+                .with_context(|| format!("page {:04} has no scene", pages.len()))?;
+            let name = sn.get_page_name().unwrap_or_default();
+            metas.push(metadata::page_meta(&sn, pages.len(), &text_key));
+            pages.push((job, format!("{:04} {}", pages.len(), name).trim_end().to_string()));
+        }
+        anyhow::ensure!(!pages.is_empty(), "no page");
+
+        let headings = contact_sheet::headings(&p, pages.len());
+        let sheets   = layout.sheets(pages.len());
+        let cells = |sheet: usize| -> Vec<render::SheetCell> {
+            let range = (sheet * layout.per_sheet())..((sheet + 1) * layout.per_sheet()).min(pages.len());
+            range.map(|i| render::SheetCell{
+                index   : i,
+                job     : pages[i].0.clone(),
+                caption : pages[i].1.clone(),
+                heading : headings[i].clone() }).collect()
+        };
+        let sheet_title = |sheet: usize| {
+            let last = ((sheet + 1) * layout.per_sheet()).min(pages.len()) - 1;
+            format!("{}  {:04}-{:04}  ({}/{})", title, sheet * layout.per_sheet(), last, sheet + 1, sheets)
+        };
+        let mut meta = metadata::ExportMetadata::new(&title, &lang, layout.thumb_w, layout.thumb_h);
+        for (i, m) in metas.into_iter().enumerate() {
+            let sheet = i / layout.per_sheet();
+            match format {
+                contact_sheet::SheetFormat::Pdf => {
+                    let file = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+                    meta.push(&file, Some(sheet + 1), m); },
+                contact_sheet::SheetFormat::Png => {
+                    let sheet_path = contact_sheet::sheet_path(path, sheet, sheets);
+                    let file = sheet_path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+                    meta.push(&file, None, m); },
+            }
+        }
+        match format {
+            contact_sheet::SheetFormat::Pdf => {
+                let surface = PdfSurface::new(layout.width as f64, layout.height as f64, path)
+                    .with_context(|| format!("can not create {}", path.display()))?;
+                surface.set_metadata(PdfMetadata::Title, &title)?;
+                surface.set_metadata(PdfMetadata::Creator, env!("CARGO_PKG_NAME"))?;
+                let cr = cairo::Context::new(&surface)?;
+                for sheet in 0..sheets {
+                    renderer.draw_sheet(&cr, &layout, &sheet_title(sheet), &cells(sheet))?;
+                    cr.show_page()?;
+                }
+                drop(cr);
+                surface.finish();
+                surface.status()?;
+            },
+            contact_sheet::SheetFormat::Png => {
+                for sheet in 0..sheets {
+                    let surface = ImageSurface::create(Format::ARgb32, layout.width, layout.height)?;
+                    let cr = cairo::Context::new(&surface)?;
+                    renderer.draw_sheet(&cr, &layout, &sheet_title(sheet), &cells(sheet))?;
+                    drop(cr);
+                    let sheet_path = contact_sheet::sheet_path(path, sheet, sheets);
+                    let mut file = std::fs::File::create(&sheet_path)
+                        .with_context(|| format!("can not create {}", sheet_path.display()))?;
+                    surface.write_to_png(&mut file)?;
+                }
+            },
+        }
+        meta.save(&metadata::sidecar_path(path))?;
+
+        if let Some(status_bar) = &*self.imp().status_bar.borrow() {
+            status_bar.set_status(&format!("{} sheets:{}", sheets, path.display())); }
+        Ok(sheets)
+    }
+    // export_animation ////////////////////////////////////
+    /// exports the pages in the range of the animation setting into an animated
+    /// GIF, APNG or WebP depending on the extension of path. returns the number of frames.
+    pub fn export_animation(&self,
+                            n     : &Rc<ScenarioNode>,
+                            param : &Isv2Parameter,
+                            path  : &Path) -> Result<usize>{
+        let ExportSetup{ target_w, target_h, lang, text_key, title, setting, root: p } = self.export_setup(n, param);
+        let mut renderer = Renderer::new(setting);
+        let setting  = param.animation();
+        let format   = animation::AnimFormat::from_path(path)
+            .with_context(|| format!("unknown animation format: {}", path.display()))?;
+
+        // pages in the range
+        let mut pages = Vec::new();
+        let mut metas = Vec::new();
+        let mut seq   = 0;
+        Self::for_each_page(param, &p, |sn, pbuf, scale_pbuf|{
+            if setting.first_page <= seq && setting.last_page.is_none_or(|l| seq <= l) {
+                metas.push(metadata::page_meta(sn, seq, &text_key));
+                pages.push((sn.clone(), pbuf.clone(), scale_pbuf.clone())); }
+            seq += 1;
+            Ok(())
+        })?;
+        anyhow::ensure!(!pages.is_empty(), "no page in the range");
+
+        let mut render = |i: usize, chars: Option<usize>| -> Result<ImageSurface> {
+            let (sn, pbuf, scale_pbuf) = &pages[i];
+            let surface = ImageSurface::create(Format::ARgb32, target_w, target_h)?;
+            let cr = cairo::Context::new(&surface)?;
+            if !Self::draw_page(&mut renderer, sn, pbuf, scale_pbuf, &cr, &text_key, None, chars) {
+                anyhow::bail!("the page has no scene"); }
+            Ok(surface)
+        };
+        let lens: Vec<usize> = pages.iter().map(|(sn, _, _)| animation::page_text_len(sn, &text_key)).collect();
+        let frames = animation::plan(&lens, &setting);
+        let file = std::fs::File::create(path).with_context(|| format!("can not create {}", path.display()))?;
+        let mut writer = animation::AnimWriter::new(std::io::BufWriter::new(file), format,
+                                                    target_w as u32, target_h as u32, setting.loop_count, frames.len() as u32)?;
+        for f in &frames {
+            let surface = render(f.page, f.chars)?;
+            if let Some((chars, alpha)) = f.fade {
+                let next = render(f.page + 1, chars)?;
+                let cr = cairo::Context::new(&surface)?;
+                cr.set_source_surface(&next, 0.0, 0.0)?;
+                cr.paint_with_alpha(alpha)?;
+            }
+            writer.add_frame(&render::surface_rgba(surface)?, f.delay_ms)?;
+        }
+        writer.finish().with_context(|| format!("can not write {}", path.display()))?;
+        let file  = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+        let mut meta = metadata::ExportMetadata::new(&title, &lang, target_w, target_h);
+        for (i, m) in metas.into_iter().enumerate() {
+            meta.push(&file, Some(i + 1), m); }
+        meta.save(&metadata::sidecar_path(path))?;
+
+        if let Some(status_bar) = &*self.imp().status_bar.borrow() {
+            status_bar.set_status(&format!("{} frames:{}", frames.len(), path.display())); }
+        Ok(frames.len())
+    }
+    // export_video ////////////////////////////////////////
+    /// exports every page of the current language as a frame sequence
+    /// (dir/frames/NNNNNN.png) with the timing files timing.ffconcat and
+    /// timing.edl, using the timing of pages and pmats. returns the number of frames.
+    pub fn export_video(&self,
+                        n     : &Rc<ScenarioNode>,
+                        param : &Isv2Parameter,
+                        dir   : &Path) -> Result<usize>{
+        let ExportSetup{ target_w, target_h, lang, text_key, title, setting, root: p } = self.export_setup(n, param);
+        let mut renderer = Renderer::new(setting);
+        let setting  = param.video();
+
+        let mut pages = Vec::new();
+        Self::for_each_page(param, &p, |sn, pbuf, scale_pbuf|{
+            pages.push((sn.clone(), pbuf.clone(), scale_pbuf.clone()));
+            Ok(())
+        })?;
+        anyhow::ensure!(!pages.is_empty(), "no page");
+
+        let timings: Vec<_> = pages.iter().map(|(sn, _, _)| sn.get_timing().unwrap_or_default()).collect();
+        let names: Vec<_> = pages.iter().enumerate()
+            .map(|(i, (sn, _, _))| format!("{:04} {}", i, sn.get_page_name().unwrap_or_default())).collect();
+        let lens: Vec<usize> = pages.iter().map(|(sn, _, _)| animation::page_text_len(sn, &text_key)).collect();
+        let spans  = video::spans(&timings, &setting);
+        let frames = video::timeline(&lens, &spans, &setting);
+
+        let mut render = |layer: video::Layer| -> Result<ImageSurface> {
+            let surface = ImageSurface::create(Format::ARgb32, target_w, target_h)?;
+            let cr = cairo::Context::new(&surface)?;
+            match layer {
+                video::Layer::Black => {
+                    cr.set_source_rgb(0.0, 0.0, 0.0);
+                    cr.paint()?; },
+                video::Layer::Page(i, chars) => {
+                    let (sn, pbuf, scale_pbuf) = &pages[i];
+                    if !Self::draw_page(&mut renderer, sn, pbuf, scale_pbuf, &cr, &text_key, None, chars) {
+                        anyhow::bail!("the page has no scene"); }
+                },
+            }
+            Ok(surface)
+        };
+        let frame_dir = dir.join("frames");
+        std::fs::create_dir_all(&frame_dir).with_context(|| format!("can not create {}", frame_dir.display()))?;
+        for (i, f) in frames.iter().enumerate() {
+            let path = frame_dir.join(video::frame_name(i));
+            if i > 0 && frames[i - 1] == *f { // held frame
+                std::fs::copy(frame_dir.join(video::frame_name(i - 1)), &path)?;
+                continue;
+            }
+            let surface = render(f.base)?;
+            if let Some((layer, alpha)) = f.over {
+                let over = render(layer)?;
+                let cr = cairo::Context::new(&surface)?;
+                cr.set_source_surface(&over, 0.0, 0.0)?;
+                cr.paint_with_alpha(alpha)?;
+            }
+            let mut file = std::fs::File::create(&path).with_context(|| format!("can not create {}", path.display()))?;
+            surface.write_to_png(&mut file)?;
+        }
+        std::fs::write(dir.join("timing.ffconcat"), video::ffconcat(&frames, setting.fps, "frames"))?;
+        std::fs::write(dir.join("timing.edl"), video::edl(&title, &names, &spans, setting.fps))?;
+        let mut meta = metadata::ExportMetadata::new(&title, &lang, target_w, target_h);
+        for (i, ((sn, _, _), span)) in pages.iter().zip(&spans).enumerate() {
+            meta.push(&format!("frames/{}", video::frame_name(span.start as usize)), None,
+                      metadata::page_meta(sn, i, &text_key));
+            if let Some(f) = meta.files.last_mut() {
+                (f.first_frame, f.frame_count) = (Some(span.start), Some(span.frames)); }
+        }
+        meta.save(&dir.join(metadata::METADATA_NAME))?;
+
+        if let Some(status_bar) = &*self.imp().status_bar.borrow() {
+            status_bar.set_status(&format!("{} frames:{}", frames.len(), dir.display())); }
+        Ok(frames.len())
+    }
+    // page_input_hash /////////////////////////////////////
+    /// hash of the inputs drawn by draw_page, see manifest::input_hash
+    fn page_input_hash(project_dir: &Path, sn: &Rc<ScenarioNode>, settings: &impl serde::Serialize) -> Option<String>{
         let mut area: Vec<(Rc<ScenarioNode>, Option<Rc<ScenarioNode>>)> = Vec::new();
+        let scene_node = ScenarioNode::get_belong_scene(sn)?;
+        render::collect_mats_in_scene(&scene_node, &mut area);
+        render::collect_mats(sn, &mut area);
+        area.extend(render::collect_ovimgs(sn).into_iter().map(|o| (o, None)));
+        Some(manifest::input_hash(project_dir, &scene_node, &area, settings))
+    }
+    // manifest_target /////////////////////////////////////
+    /// see manifest::target_key
+    fn manifest_target(param: &Isv2Parameter, target: &ExportTarget) -> String{
+        let project_file = param.property::<PathBuf>("project_dir").join(param.property::<String>("project_file_name"));
+        manifest::target_key(&project_file, &target.lang, target.format.extension())
+    }
+    // keep_previous ///////////////////////////////////////
+    /// carries the entries of a page which could not be written over from the previous export,
+    /// so that its file is kept, not removed as stale
+    fn keep_previous(file_name    : &str,
+                     old_manifest : &manifest::Manifest,
+                     old_meta     : Option<&metadata::ExportMetadata>,
+                     new_manifest : &mut manifest::Manifest,
+                     new_meta     : &mut metadata::ExportMetadata){
+        if let Some(h) = old_manifest.files.get(file_name) {
+            new_manifest.files.insert(file_name.to_string(), h.clone()); }
+        if let Some(f) = old_meta.and_then(|m| m.files.iter().find(|f| f.file == file_name)) {
+            new_meta.files.push(f.clone()); }
+    }
+    // export_images ///////////////////////////////////////
+    /// exports the pages into each target: a language in its directory, size and format.
+    /// svg with linked backgrounds refers to bgNNNN.png written for each scene.
+    /// pages whose inputs are unchanged since the last export are skipped (see manifest).
+    /// only the pages in subset are written, and files of other pages are kept.
+    pub fn export_images(&self,
+                         n       : &Rc<ScenarioNode>,
+                         param   : &Isv2Parameter,
+                         root    : &impl IsA<Window>,
+                         targets : Vec<ExportTarget>,
+                         subset  : &Subset){
 
-        // check export dir
-        let mut path_buf = param.property::<PathBuf>("project_dir");
-        path_buf.push( param.property::<String>("export_dir") );
+        let status_bar = (&*self.imp().status_bar.borrow()).clone().unwrap();
+
+        let target_w = param.property::<i32>("target_width");
+        let target_h = param.property::<i32>("target_height");
+
+        let p = Self::project_root(n);
+        // traverse
+        let mut vec        = vec![p.clone()]; // for ScenarioNdoe traversal
+
+        // preflight -> show the issues and abort if the user chooses
+        let mut issues = self.preflight(param, &p, &targets);
+        if !subset.is_all() { // issues of the pages in the subset, and of the stamps
+            let mut numbering = subset::Numbering::default();
+            let mut pages_vec = vec![p.clone()];
+            let pages: HashSet<usize> = std::iter::from_fn(|| ScenarioNode::traverse(&mut pages_vec))
+                .filter(|n| n.is_page() || n.is_pmat()).enumerate()
+                .filter_map(|(i, n)| numbering.next(subset, &n).map(|_| i)).collect();
+            issues.retain(|i| pages.contains(&i.page) || Rc::ptr_eq(&i.node, &p));
+        }
+        if issues.is_empty() {
+            status_bar.set_status("preflight: no issue"); }
+        else {
+            println!("(export_images) preflight: {} issues", issues.len());
+            let go_on = gtk::glib::MainContext::default().block_on(
+                Self::confirm_preflight(root.clone().upcast::<Window>(), issues, self.tree_selection()) );
+            if !go_on {
+                status_bar.set_status("export is aborted by preflight");
+                return; }
+        }
+
+        // check export dirs
         // file exists -> show message and abort
-        if path_buf.exists() && path_buf.is_file() {
+        if let Some(path_buf) = targets.iter().map(|t| &t.out_dir).find(|d| d.is_file()) {
             let dialog = AlertDialog::builder().modal(true).build();
             dialog.set_buttons(&["Cancel exporting"]);
             dialog.set_message(
@@ -416,8 +998,10 @@ impl PreviewWindow {
             println!("(export_images) abort: target dir already exists as file");
             return;
         }
-        // dir exists -> show confirm message
-        if path_buf.exists() && path_buf.is_dir() {
+        // dir exists -> show confirm message, unless it has been exported before for the same target
+        for path_buf in targets.iter()
+            .filter(|t| t.out_dir.is_dir() && manifest::Manifest::load_for(&t.out_dir, &Self::manifest_target(param, t)).is_none())
+            .map(|t| &t.out_dir) {
             let overwrite: Rc<Cell<bool>> = Rc::new(Cell::new(false));
             gtk::glib::MainContext::default().block_on(
                 Self::confirm_exists_dir(root.clone(), overwrite.clone(), path_buf.clone()) );
@@ -426,24 +1010,32 @@ impl PreviewWindow {
                 return;
             }
         }
-        if !path_buf.exists(){
-            let mut path_buf = param.property::<PathBuf>("project_dir");
-            path_buf.push( param.property::<String>("export_dir") );
-            std::fs::create_dir(path_buf).expect("create_dir in export_images");
+        for t in &targets {
+            if !t.out_dir.exists() {
+                std::fs::create_dir_all(&t.out_dir).expect("create_dir in export_images"); }
         }
 
         // count page/pmat
         let mut total_num = 0;
+        let mut last_page = 0; // for the stamps on the last page
+        let mut numbering = subset::Numbering::default();
         loop{
             if let Some(sn) = ScenarioNode::traverse(&mut vec){
                 match &*sn.value.borrow() {
-                    Item::Page(_) | Item::Pmat(_) => { total_num+= 1; },
+                    Item::Page(_) | Item::Pmat(_) => {
+                        last_page+= 1;
+                        if numbering.next(subset, &sn).is_some() { total_num+= 1; } },
                     _ => ()
                 }
             } else {
                 break;
             }
         }
+        if total_num == 0 {
+            status_bar.set_status("no page to export");
+            return; }
+        total_num *= targets.len();
+        last_page -= 1;
 
         let export_cansel_flag_ctrl :Arc<Mutex<bool>> = Arc::new(Mutex::from(false));
         let export_cansel_flag_ref = Arc::clone(&export_cansel_flag_ctrl);
@@ -453,92 +1045,163 @@ impl PreviewWindow {
         let (sender, receiver) = async_channel::bounded(1);
 
         // sender : exporing loop //////////////////////////
-        let prev_win = self.clone();
+        let subset  = subset.clone();
+        let font_pc = self.pango_context();
         glib::spawn_future_local(glib::clone!(@strong sender,
                                               @strong param,
                                               @strong p,
-                                              @strong prev_win,
+                                              @strong subset,
                                               @strong status_bar => async move {
 
             gtk::glib::timeout_future_seconds(1).await; // wait for progress window
 
-            let mut vec = vec![p];
-            loop{
-                if *export_cansel_flag_ref.lock().unwrap() {
-                    println!("export is canceled");
-                    break; }
-
-                if let Some(sn) = ScenarioNode::traverse(&mut vec){
+            let mut exported_num = 0;
+            let project_dir   = param.property::<PathBuf>("project_dir");
+            let title = Self::project_title(&param);
+            'target: for target in &targets {
+                let (lang, out_dir, format) = (&target.lang, &target.out_dir, target.format);
+                let mut numbering = subset::Numbering::default();
+                let mut names     = naming::UniqueNames::default();
+                let mut bg_seq    = 0;
+                let mut bg_uri    = None::<String>;
+                let mut bg        = None::<(String, Rc<ScenarioNode>)>; // written with the first exported page of the scene
+                let mut scene_seq = 0;
+                let mut page_no   = 0; // in the whole project, for the stamps
+                let text_key      = param.text_key(lang);
+                let settings = |bg_uri: &Option<String>| {
+                    (target_w, target_h, target.width, target.height, lang.clone(), param.text_key(lang),
+                     param.language_setting(lang), format!("{:?}", format), target.content, param.resample(), bg_uri.clone()) };
+                let manifest_target = Self::manifest_target(&param, target);
+                let old_manifest = manifest::Manifest::load_for(out_dir, &manifest_target).unwrap_or_default();
+                let meta_path    = out_dir.join(metadata::METADATA_NAME);
+                let old_meta     = metadata::ExportMetadata::load(&meta_path);
+                let mut new_manifest = manifest::Manifest::new(&manifest_target);
+                let mut new_meta     = metadata::ExportMetadata::new(&title, lang, target.width, target.height);
+                // pages to be rendered and their (file name, input hash, path)
+                let mut tasks   = Vec::new();
+                let mut outputs = Vec::new();
+                let mut vec = vec![p.clone()];
+                while let Some(sn) = ScenarioNode::traverse(&mut vec){
                     match &*sn.value.borrow() {
-                        Item::Scene(_) => { // prepare scaled image
-                            let sn_ref = {
-                                if let Some(s) = ScenarioNode::search_def_label(sn.clone()) { s }
-                                else { sn.clone() }};
-                            let bgimg = {
-                                if let Some(b) = sn_ref.get_scene_bgimg() { b }
-                                else { println!("the node is not scene"); PathBuf::new() } };
-                            let mut img_path = param.property::<PathBuf>("project_dir");
-                            img_path.push( bgimg );
-                            if let Ok(p) = Pixbuf::from_file( img_path ) {
-                                pbuf = Some(p); }
-                            else {
-                                pbuf = None; }
-                            if pbuf.is_some(){
-                                scale_pbuf = Self::prepare_scale_crop_buf_sub(&param,
-                                                                              &sn_ref,
-                                                                              &pbuf.clone().unwrap()) };
+                        Item::Scene(_) => {
+                            scene_seq+= 1;
+                            (bg_uri, bg) = (None, None);
+                            // numbered by the scenes with an image file, so that a subset has the same names
+                            let bgimg = SceneJob::of(&sn, &project_dir).and_then(|j| j.bgimg);
+                            if let (ImageFormat::Svg{ link_bg: true }, Content::Full, true) =
+                                (format, target.content, bgimg.is_some_and(|b| b.is_file())) {
+                                let name = format!("bg{:04}.png", bg_seq);
+                                bg_uri = Some(name.clone());
+                                bg     = Some((name, sn.clone()));
+                                bg_seq+= 1;
+                            }
                         },
                         Item::Page(_) | Item::Pmat(_) => {
-                            area.clear();
-
-                            let scene_node =
-                                if let Some(p) = ScenarioNode::get_belong_scene(&sn) { p } // detects scene
-                            else { println!("the scene to which new node belongs was not found!"); return; };
-                            Self::collect_mats_in_scene(&scene_node, &mut area);
-
-                            Self::collect_mats(&sn, &mut area);
-                            // 0. prepare surface
-                            let surface = {
-                                if let Ok(sf) = ImageSurface::create(Format::ARgb32, target_w, target_h) { sf }
-                                else { println!("(export_images) creating surface failed"); return; } };
-                            let cr = {
-                                if let Ok(ctx) = cairo::Context::new(&surface) { ctx }
-                                else { println!("(export_images) creating context failed"); return; } };
-                            // 1. draw scene
-                            Self::draw_func_for_scene(&sn, &pbuf.clone(), &scale_pbuf, target_w, target_h, &cr, None);
-                            // 2. draw mats
-                            //prev_win.draw_mats_sub(&area, &prev_win.pango_context(), &cr, 0/* w */, 0/* h */);
-                            prev_win.draw_mats_sub(&area, &prev_win.pango_context(), &cr, 0/* w */, 0/* h */);
-
-                            // TODO:ディレクトリがなければ作成する
-                            let mut path_buf = param.property::<PathBuf>("project_dir");
-                            path_buf.push( param.property::<String>("export_dir") );
-                            path_buf.push( format!("{:04}.png", img_seq) );
-
-                            let mut out_file  = {
-                                if let Ok(f) = OpenOptions::new()
-                                    .read(false)
-                                    .write(true)
-                                    .create(true)
-                                    .open(&path_buf) { f }
-                                else { println!("(export_images) can not open: {}",
-                                                path_buf.to_str().unwrap()); return; } };
-
-                            surface.write_to_png(&mut out_file).expect("write_to_png in export_images");
-
-                            status_bar.set_status(&format!("{}/{}:{}", img_seq+1, total_num, path_buf.to_str().unwrap()));
-
-                            sender.send(false).await.expect("The channel needs to be open.");
-                            gtk::glib::timeout_future(Duration::from_millis(50)).await; // for async debug
-
-                            img_seq+= 1;
+                            page_no+= 1;
+                            let img_seq = if let Some(n) = numbering.next(&subset, &sn) { n } else { continue; };
+                            let stamps  = stamp::for_page(&target.stamps, page_no - 1, last_page);
+                            let stamp_files: Vec<_> = stamps.iter().map(|s| match &s.content {
+                                StampContent::Image(i) => manifest::file_stamp(&project_dir.join(i)),
+                                StampContent::Text(_)  => None }).collect();
+                            if let Some((name, s)) = bg.take() {
+                                let hash = manifest::input_hash(&project_dir, &s, &[], &settings(&None));
+                                if !old_manifest.is_fresh(out_dir, &name, &hash) {
+                                    let job = SceneJob::of(&s, &project_dir).unwrap();
+                                    if let (_, Some(b)) = render::load_scene(&job, target.width, target.height, param.resample().filter()) {
+                                        if let Err(e) = b.savev(out_dir.join(&name), "png", &[]) {
+                                            // the pages of the scene embed the background instead
+                                            println!("(export_images) can not write {}: {}", name, e);
+                                            bg_uri = None; }
+                                    } else {
+                                        bg_uri = None; }
+                                }
+                                if bg_uri.is_some() {
+                                    new_manifest.files.insert(name, hash); }
+                            }
+                            let name      = naming::render(&target.name_template, &naming::NameContext::of(&sn, img_seq));
+                            let file_name = format!("{}.{}", names.unique(name), format.extension());
+                            let path_buf  = out_dir.join(&file_name);
+                            let (hash, mut job) = {
+                                let hash = Self::page_input_hash(&project_dir, &sn, &(settings(&bg_uri), &stamps, stamp_files));
+                                let job  = render::page_job(&sn, &project_dir, &text_key, bg_uri.as_deref(), None);
+                                if let (Some(h), Some(j)) = (hash, job) { (h, j) }
+                                else {
+                                    println!("(export_images) {}: the page has no scene", file_name);
+                                    exported_num+= 1;
+                                    Self::keep_previous(&file_name, &old_manifest, old_meta.as_ref(), &mut new_manifest, &mut new_meta);
+                                    sender.send(false).await.expect("The channel needs to be open.");
+                                    continue;
+                                } };
+
+                            let page_meta = metadata::page_meta(&sn, page_no - 1, &text_key);
+                            if old_manifest.is_fresh(out_dir, &file_name, &hash) {
+                                exported_num+= 1;
+                                new_meta.push(&file_name, None, page_meta);
+                                new_manifest.files.insert(file_name, hash);
+                                status_bar.set_status(&format!("{}/{}:{} (unchanged)", exported_num, total_num, path_buf.to_str().unwrap()));
+                                sender.send(false).await.expect("The channel needs to be open.");
+                                continue;
+                            }
+                            job.stamps = stamps;
+                            tasks.push(render::ExportTask{
+                                job, format,
+                                path : path_buf.clone(),
+                                scene: scene_seq });
+                            outputs.push((file_name, hash, path_buf, page_meta));
                         },
                         _ => (),
                     }
+                }
+                // render on worker threads
+                let (done_sender, done_receiver) = async_channel::unbounded();
+                let setting = RenderSetting::new(&project_dir, target_w, target_h, param.language_setting(lang))
+                    .output(target.width, target.height)
+                    .content(target.content)
+                    .resample(&param.resample())
+                    .font(&font_pc);
+                let workers = render::spawn_workers(tasks, setting, Arc::clone(&export_cansel_flag_ref), done_sender);
+                while let Ok((i, result)) = done_receiver.recv().await {
+                    let (file_name, hash, path_buf, page_meta) = &outputs[i];
+                    exported_num+= 1;
+                    if let Err(e) = result {
+                        println!("(export_images) {:?}", e);
+                        Self::keep_previous(file_name, &old_manifest, old_meta.as_ref(), &mut new_manifest, &mut new_meta);
+                    } else {
+                        new_meta.push(file_name, None, page_meta.clone());
+                        new_manifest.files.insert(file_name.clone(), hash.clone());
+                        status_bar.set_status(&format!("{}/{}:{}", exported_num, total_num, path_buf.to_str().unwrap()));
+                    }
+                    sender.send(false).await.expect("The channel needs to be open.");
+                }
+                // done is closed, the workers are exiting
+                for w in workers {
+                    if w.join().is_err() {
+                        println!("(export_images) a rendering thread panicked"); }
+                }
+                let canceled = *export_cansel_flag_ref.lock().unwrap();
+                if canceled {
+                    println!("export is canceled"); }
+
+                // files not visited by the canceled or partial export are kept as they are
+                if canceled || !subset.is_all() {
+                    for (k, v) in &old_manifest.files {
+                        new_manifest.files.entry(k.clone()).or_insert(v.clone()); }
+                    if let Some(old_meta) = &old_meta {
+                        new_meta.merge_kept(old_meta); }
                 } else {
-                    break;
+                    for stale in old_manifest.stale(&new_manifest) {
+                        if let Err(e) = std::fs::remove_file(out_dir.join(stale)) {
+                            println!("(export_images) can not remove {}: {}", stale, e); }
+                    }
                 }
-            };
+                if let Err(e) = new_manifest.save(out_dir) {
+                    println!("(export_images) can not write manifest: {:?}", e); }
+                new_meta.files.sort_by_key(|f| f.page.seq);
+                if let Err(e) = new_meta.save(&meta_path) {
+                    println!("(export_images) can not write metadata: {:?}", e); }
+                if canceled {
+                    break 'target; }
+            }
             sender.send(true).await.expect("The channel needs to be open.");
         }));
 
@@ -605,197 +1268,16 @@ impl PreviewWindow {
         self.imp().area_state.set(CursorState::None);
         self.set_cursor_from_name( None );
     }
-    // draw_bg_mat_img /////////////////////////////////////
-    fn draw_bg_mat_img(param       : &Isv2Parameter,
-                       sn          : &ScenarioNode,
-                       img_mat_buf : &mut HashMap<u64, Pixbuf>,
-                       cr          : &cairo::Context,
-                       x: f64, y: f64, w: f64, h: f64
-    ) -> Result<()>{
-        // todo: use anyhow
-
-        let mut prj_path = param.property::<PathBuf>("project_dir");
-        if let Some(bgimg_path) = sn.get_mat_bgimg(){
-            prj_path.push(&bgimg_path); }
-
-        if !prj_path.is_file(){ anyhow::bail!("prj_path is not file"); }
-
-        let bg_file = File::open(prj_path.clone())
-            .with_context(||format!("opening {prj_path:?} failed"))?;
-        let m_data = bg_file.metadata()
-            .with_context(||format!("obtaining metadata of {prj_path:?} failed"))?;
-        let mod_time = m_data.modified()
-            .with_context(||format!("getting mod_tile of {prj_path:?} failed"))?;
-        let epoch = mod_time.duration_since(SystemTime::UNIX_EPOCH)
-            .with_context(||format!("getting epoch of {prj_path:?} failed"))?;
-
-        // generate hash of file name with timestamp
-        let prj_path_str = prj_path.clone().into_os_string().into_string().unwrap();
-        let mut hasher = DefaultHasher::new();
-        hasher.write(prj_path_str.as_bytes());
-        hasher.write(&epoch.as_secs().to_ne_bytes());
-        hasher.write(&epoch.as_nanos().to_ne_bytes());
-        let hash_u64 = hasher.finish();
-
-        let bg_buf_sub;
-        let bg_pbuf = if let Some(b) = img_mat_buf.get(&hash_u64){ b } else {
-            let ref pbuf = Pixbuf::from_file( prj_path.clone() )
-                .with_context(||format!("creating pixbuf of {prj_path:?} failed"))?;
-            img_mat_buf.insert(hash_u64, pbuf.clone());
-            bg_buf_sub = pbuf.clone();
-            &bg_buf_sub
-        };
-        // TODO: resize cache mount of img_mat_buf when it becomes too large
-
-        let scale_pbuf = {
-            if let Some(p) = bg_pbuf.scale_simple(w as i32, h as i32,
-                                                  InterpType::Bilinear) { p }
-            else { println!("scale_simple failed in {}:{}", file!(), line!()); anyhow::bail!(""); }
-        };
-        cr.set_source_pixbuf(&scale_pbuf, x, y);
-        cr.rectangle(x, y, w, h);
-        if cr.fill().is_err(){
-            println!("draw image on PreviewWindow failed!"); }
-
-        Ok(())
-    }
     // draw_mats ///////////////////////////////////////////
-    fn draw_mats_sub(&self,
-                     area: &Vec<(Rc<ScenarioNode>, Option<Rc<ScenarioNode>>)>,
-                     pc  : &gtk::pango::Context,
-                     cr  : &cairo::Context,
-                     _w: i32, _h: i32){
-        let mut area = area.clone();
-        area.reverse();
-
-        for ref area_item in area {
-            let (sn_source, sn_ref) = area_item;
-            let sn = if let Some(ref_target) = sn_ref { ref_target } else { sn_source };
-
-            // mat /////////////////////////////////////////
-            let (mut x, mut y, w, h) = {
-                if sn_source.get_label_type() == Some(LabelType::RefNoRect) {
-                    if let Some( tuple )  = sn_source.get_mat_pos_dim_f64() { tuple } else { return; }
-                } else {
-                    if let Some( tuple )  = sn.get_mat_pos_dim_f64() { tuple } else { return; }
-                }
-            };
-            let (pad_x, pad_y) = {
-                if let Some( tuple )  = sn.get_mat_text_pos_f64() { tuple } else { return; }
-            };
-            let (r, g, b, a) =
-                if let Some( tuple )  = sn.get_mat_rgba_tuple_f64() { tuple } else { return; };
-
-            if sn.get_mat_bg_en().unwrap() { // image mat
-                if let Err(e) = Self::draw_bg_mat_img( &self.imp().parameter.borrow().upgrade().unwrap(),
-                                                        &sn,
-                                                        &mut self.imp().img_mat_buf.borrow_mut(),
-                                                        cr,
-                                                        x, y, w, h) {
-                    println!("{:?}", e);
-                }
-            } else { // draw rectangle
-                cr.set_line_width(2.0); // TODO parameterize
-                cr.set_source_rgba( r, g, b, a );
-
-                let round = sn.get_mat_r().unwrap();
-                if round <= 0 {
-                    cr.rectangle(x, y, w, h);
-                } else {
-                    let round = round as f64;
-                    let m_pi = glib_sys::G_PI;
-                    cr.move_to(x-round,   y);
-                    cr.arc    (x,     y,        round,  1.0*m_pi,  1.5*m_pi);
-                    cr.line_to(x+w,   y-round);
-                    cr.arc    (x+w,   y,        round,  1.5*m_pi,  2.0*m_pi);
-                    cr.line_to(x+w+round, y+h+r);
-                    cr.arc    (x+w,   y+h,      round,  0.0*m_pi,  0.5*m_pi);
-                    cr.line_to(x,     y+h+round);
-                    cr.arc    (x,     y+h,      round,  0.5*m_pi,  1.0*m_pi);
-                    cr.close_path();
-                }
-                if cr.fill().is_err() {
-                    println!("fill draw_mats_sub failed!");
-                    return; }
-            }
-            // text ////////////////////////////////////////
-            let layout = Layout::new(pc);
-
-            if sn.get_mat_vertical().unwrap() {
-                layout.set_width ((h as i32) * pango::SCALE);     // swap when
-                layout.set_height((w as i32) * 3 * pango::SCALE); // vertical writing
-            } else {
-                layout.set_width ((w as i32) * pango::SCALE);
-                layout.set_height((h as i32) * 3 * pango::SCALE);
-            }
-            // note: Expand the height (width in vertical writing)
-            // to avoid unintentional omission of display.
-            // This is useful when line-spacing is set to 1 or less.
-            // The expanded width (x3) is provisional.
-            layout.set_markup(&sn_source.get_mat_text().unwrap());
-
-            // line spacing
-            layout.set_line_spacing(sn.get_mat_line_spacing().unwrap());
-
-            cr.set_source_rgba( r, g, b, a );
-            let mut fopt = FontOptions::new().expect("fopt");
-            fopt.set_antialias(Antialias::Good);
-            pangocairo::context_set_font_options(pc, Some(&fopt));
-
-            let mut font_desc= FontDescription::new();
-            font_desc.set_family( &sn.get_mat_font_family().unwrap() );
-            font_desc.set_style( Style::Normal );
-            font_desc.set_size( sn.get_mat_font_size().unwrap() * pango::SCALE);
-            if sn.get_mat_vertical().unwrap() {
-                font_desc.set_gravity(gtk::pango::Gravity::East); }
-
-            let w1 = util::StrumWeight::from_str( &(sn.get_mat_font_weight().unwrap()) ).expect("weight expression");
-            font_desc.set_weight(w1.0);
-
-            layout.set_font_description(Some(&font_desc));
-
-            // text mat
-            if sn.get_mat_vertical().unwrap() {
-                x = x + w - pad_x;
-            }
-            else {
-                x = x + pad_x;
-            }
-            y = y + pad_y;
-
-            cr.set_line_join(cairo::LineJoin::Round);
-
-            cr.save().expect("save context before stroke text decoration");
-            cr.move_to(x, y);
-            if sn.get_mat_vertical().unwrap() {
-                cr.rotate(0.5 * glib_sys::G_PI); }
-            pangocairo::layout_path(cr, &layout);
-            let (r, g, b, a) =
-                if let Some( tuple )  = sn.get_mat_font_rgba_tuple_f64_2() { tuple } else { return; };
-            cr.set_source_rgba( r, g, b, a );
-            let font_outl_2 =
-                if let Some( w )  = sn.get_mat_font_outl_2() { w } else { return; };
-            cr.set_line_width(font_outl_2);
-            cr.stroke().expect("stroke text");
-            cr.restore().expect("restore context for stroke text decorateion");
-
-            // text foreground
-            cr.save().expect("save context before stroke text");
-            cr.move_to(x, y);
-            if sn.get_mat_vertical().unwrap() {
-                cr.rotate(0.5 * glib_sys::G_PI); }
-            let (r, g, b, a) =
-                if let Some( tuple )  = sn.get_mat_font_rgba_tuple_f64() { tuple } else { return; };
-            cr.set_source_rgba( r, g, b, a );
-            pangocairo::show_layout(cr, &layout);
-            cr.restore().expect("restore context for stroke text");
-        }
-
-    }
     pub fn draw_mats(&self, cr: &cairo::Context, _w: i32, _h: i32){
-        self.draw_mats_sub(&*self.imp().area.borrow(),
-                            &self.pango_context(),
-                            cr, _w, _h);
+        let param = if let Some(p) = self.imp().parameter.borrow().upgrade() { p } else { return; };
+        let lang = param.property::<String>("language");
+        let mats = render::mat_jobs(&self.imp().area.borrow(), &param.text_key(&lang), None);
+        render::draw_mats(&self.pango_context(), cr, &mats,
+                          param.language_setting(&lang).as_ref(),
+                          &param.property::<PathBuf>("project_dir"),
+                          &mut self.imp().img_mat_buf.borrow_mut(),
+                          Filter::Fast);
     }
     // update_pixbuf ///////////////////////////////////////
     fn update_pixbuf(&self, sno: ScenarioNodeObject, force_update: bool) -> bool{
@@ -833,63 +1315,6 @@ impl PreviewWindow {
         true
     }
     // update_mat //////////////////////////////////////////
-    fn collect_mats(page_node : &Rc<ScenarioNode>,
-                    area      : &mut Vec::<(Rc<ScenarioNode>, Option<Rc<ScenarioNode>>)>) {
-        let mut p = page_node.clone();
-        loop {
-            // - it collects mat or pmat
-            // - While collecting mat, pmat will not be mixed
-            //    (guaranteed by tree manipulation constraints)
-            let p1;
-            match *p.value.borrow() {
-                Item::Page(_) => {
-                    p1 = p.child.borrow().clone();
-                }
-                Item::Mat(_) => {
-                    let lbl_ref_node = ScenarioNode::search_def_label(p.clone()); // reference if it has label-ref
-                    area.push( (p.clone(), lbl_ref_node) );
-                    p1 = p.neighbor.borrow().clone();
-                }
-                Item::Pmat(_) => {
-                    let lbl_ref_node = ScenarioNode::search_def_label(p.clone()); // reference if it has label-ref
-                    area.push( (p.clone(), lbl_ref_node) );
-                    p1 = None; /* exit when Pmat */
-                }
-                _ => { p1 = None; }
-            }
-            if p1.is_some() { /* child or neighbor is set if exists */
-                p = p1.unwrap();
-            } else {
-                break;
-            }
-        }
-    }
-    fn collect_mats_in_scene(scene_node : &Rc<ScenarioNode>,
-                             area      : &mut Vec::<(Rc<ScenarioNode>, Option<Rc<ScenarioNode>>)>) {
-        let mut p = scene_node.clone();
-        loop {
-            let p1;
-            match *p.value.borrow() {
-                Item::Scene(_) => {
-                    p1 = p.child.borrow().clone();
-                }
-                Item::Mat(_) => {
-                    let lbl_ref_node = ScenarioNode::search_def_label(p.clone()); // reference if it has label-ref
-                    area.push( (p.clone(), lbl_ref_node) );
-                    p1 = p.neighbor.borrow().clone();
-                }
-                Item::Page(_) | Item::Pmat(_) => {
-                    p1 = p.neighbor.borrow().clone();
-                }
-                _ => { p1 = None; }
-            }
-            if p1.is_some() { /* child or neighbor is set if exists */
-                p = p1.unwrap();
-            } else {
-                break;
-            }
-        }
-    }
     pub fn update_mat(&self, sno: ScenarioNodeObject, force_update: bool) -> bool{
         let scene_node =
             if let Some(p) = ScenarioNode::get_belong_scene(&sno.get_node()) { p } // detects scene
@@ -909,8 +1334,8 @@ impl PreviewWindow {
         // handles mat /////////////////////////////////////
         self.imp().area.borrow_mut().clear();
 
-        Self::collect_mats_in_scene(&scene_node, &mut(*self.imp().area.borrow_mut()));
-        Self::collect_mats(&page_node, &mut(*self.imp().area.borrow_mut()));
+        render::collect_mats_in_scene(&scene_node, &mut(*self.imp().area.borrow_mut()));
+        render::collect_mats(&page_node, &mut(*self.imp().area.borrow_mut()));
         true
     }
     // clear_mat_on_scene_node /////////////////////////////
@@ -1,4 +1,4 @@
-//! group
+//! group(name)
 //!   scene(同じ画層のまとまり:
 //!         bgimg, bgcol, crop, lbl(ラベル名), lblref(ラベル名参照))
 //!     page(クリック単位)
@@ -16,6 +16,7 @@
 //!        3.2. pmat(pageの特殊形, matと等価)
 
 use std::cell::{RefCell,Cell};
+use std::collections::BTreeMap;
 use std::collections::VecDeque;
 use std::convert::From;
 use std::fmt;
@@ -75,7 +76,7 @@ impl fmt::Display for ScenarioNodeSerde {
         let mut mat_text    = String::new();
         let mut scene_bgimg = String::new();
         match &*self.value.borrow() {
-            Item::Group    => {disp_str += "Group:";},
+            Item::Group(_) => {disp_str += "Group:";},
             Item::Scene(s) => {
                 disp_str += "Scene:";
                 if let Some(bgimg) = &s.bgimg{
@@ -123,7 +124,7 @@ pub struct ScenarioNode {
 impl Default for ScenarioNode{
     fn default() -> Self{
         ScenarioNode{
-            value   : RefCell::new(Item::Page( Page{ name: String::from("new_page") } )),
+            value   : RefCell::new(Item::Page( Page{ name: String::from("new_page"), ..Default::default() } )),
             bt      : Cell::new(BranchType::Child),
             parent  : RefCell::new(Weak::new()),
             child   : RefCell::new(None),
@@ -158,7 +159,7 @@ impl fmt::Display for ScenarioNode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut s= String::from( self.id.get().to_string() );
         match &(*self.value.borrow()){
-            Item::Group    => s+= "G,",
+            Item::Group(g) => s+= &("G(".to_owned() + &g.name + "),"),
             Item::Scene(c) => {
                 s+= "S,";
                 if let Some(ref a)= c.bgimg{ s+= &("b[".to_owned() + a.to_str().unwrap() + "]"); }
@@ -187,7 +188,7 @@ impl fmt::Display for ScenarioNode {
         s+= "p:";
         if let Some(p) = &self.parent.borrow().clone().upgrade(){
             match &(*p.value.borrow()){
-                Item::Group    => s+= "G",
+                Item::Group(_) => s+= "G",
                 Item::Scene(_c)=> s+= "S",
                 Item::Page(_p) => s+= "P",
                 Item::Mat(_m)  => s+= "M",
@@ -236,7 +237,7 @@ impl ScenarioNode {
     }
     pub fn new() -> ScenarioNode{
         ScenarioNode{
-            value   : RefCell::new(Item::Page( Page{ name: String::from("new_page") } )),
+            value   : RefCell::new(Item::Page( Page{ name: String::from("new_page"), ..Default::default() } )),
             bt      : Cell::new(BranchType::Child),
             parent  : RefCell::new(Weak::new()),
             child   : RefCell::new(None),
@@ -287,7 +288,7 @@ impl ScenarioNode {
         let mut page_text   = String::new();
         let mut scene_bgimg = String::new();
         match &*self.value.borrow() {
-            Item::Group    => {disp_str += "Group:";},
+            Item::Group(g) => {disp_str += "Group:"; page_text = g.name.clone(); },
             Item::Scene(s) => {
                 disp_str += "Scene:";
                 if let Some(bgimg) = &s.bgimg{
@@ -322,8 +323,19 @@ impl ScenarioNode {
             }
         }
     }
+    /// the node which has p in its children (parent is the previous node for a neighbor)
+    pub fn get_container(p : &Rc<ScenarioNode>) -> Option<Rc<ScenarioNode>> {
+        let mut p = p.clone();
+        loop {
+            let bt = p.bt.get();
+            let p1 = p.parent.borrow().upgrade()?;
+            if bt != BranchType::Neighbor {
+                return Some(p1); }
+            p = p1;
+        }
+    }
     pub fn get_belong_group(p : &Rc<ScenarioNode>) -> Option<Rc<ScenarioNode>> {
-        Self::get_belong_item(p, |i|{if let Item::Group = i {true} else {false} })
+        Self::get_belong_item(p, |i|{if let Item::Group(_) = i {true} else {false} })
     }
     pub fn get_belong_scene(p : &Rc<ScenarioNode>) -> Option<Rc<ScenarioNode>> {
         Self::get_belong_item(p, |i|{if let Item::Scene(_) = i {true} else {false} })
@@ -507,6 +519,25 @@ impl ScenarioNode {
 
         true
     }
+    // append_last_child ///////////////////////////////////
+    /// make B the last child node of A
+    pub fn append_last_child(a: Rc::<ScenarioNode>, b: Rc<ScenarioNode>) -> bool{
+        let a_c = a.child.borrow().clone();
+        if let Some(a_c) = a_c {
+            Self::mv_to_neighbor(Self::get_last_neighbor(&a_c), b)
+        } else {
+            Self::mv_to_child(a, b)
+        }
+    }
+    // get_last_neighbor ///////////////////////////////////
+    pub fn get_last_neighbor(a: &Rc<ScenarioNode>) -> Rc<ScenarioNode>{
+        let mut p = a.clone();
+        loop {
+            let p1 = p.neighbor.borrow().clone();
+            if let Some(n) = p1 { p = n; } else { break; }
+        }
+        p
+    }
     // search_def_label ////////////////////////////////////
     fn extract_label(item: &impl LabelledItem) -> Option<String> {
         if (item.get_label_type() == LabelType::Ref ||
@@ -598,9 +629,9 @@ impl ScenarioNode {
     // can_be_child ////////////////////////////////////////
     pub fn can_be_child(p: &Item, c: &Item) -> bool{ // can c become p's child?
         match p{
-            Item::Group => {
+            Item::Group(_) => {
                 match c {
-                    Item::Group    => true,
+                    Item::Group(_) => true,
                     Item::Scene(_) => true,
                     Item::Page(_)  => false,
                     Item::Mat(_)   => false,
@@ -629,9 +660,9 @@ impl ScenarioNode {
     // can_be_neighbor /////////////////////////////////////
     pub fn can_be_neighbor(p: &Item, n: &Item) -> bool{ // can c become p's neighbor?
         match p{
-            Item::Group => {
+            Item::Group(_) => {
                 match n {
-                    Item::Group    => true,
+                    Item::Group(_) => true,
                     Item::Scene(_) => true,
                     Item::Page(_)  => false,
                     Item::Mat(_)   => false,
@@ -641,7 +672,7 @@ impl ScenarioNode {
             },
             Item::Scene(_) => {
                 match n {
-                    Item::Group    => true,
+                    Item::Group(_) => true,
                     Item::Scene(_) => true,
                     _ => false,
                 }
@@ -682,16 +713,16 @@ impl ScenarioNode {
     // can_be_neighbor_or_child_auto ///////////////////////
     pub fn can_be_neighbor_or_child_auto(p: &Item, n: &Item) -> bool{
         match p{
-            Item::Group => {
+            Item::Group(_) => {
                 match n {
-                    Item::Group    => true,
+                    Item::Group(_) => true,
                     Item::Scene(_) => true,
                     _ => false,
                 }
             },
             Item::Scene(_) => {
                 match n {
-                    Item::Group    => true,
+                    Item::Group(_) => true,
                     Item::Scene(_) => true,
                     Item::Page(_)  => true,
                     Item::Mat(_)   => true,
@@ -701,7 +732,7 @@ impl ScenarioNode {
             },
             Item::Page(_) => {
                 match n {
-                    Item::Group    => true,
+                    Item::Group(_) => true,
                     Item::Scene(_) => true,
                     Item::Page(_)  => true,
                     Item::Mat(_)   => true,
@@ -711,7 +742,7 @@ impl ScenarioNode {
             },
             Item::Mat(_) | Item::Ovimg(_)=> {
                 match n {
-                    Item::Group    => true,
+                    Item::Group(_) => true,
                     Item::Scene(_) => true,
                     Item::Page(_)  => true,
                     Item::Mat(_)   => true,
@@ -721,7 +752,7 @@ impl ScenarioNode {
             },
             Item::Pmat(_) => {
                 match n {
-                    Item::Group    => true,
+                    Item::Group(_) => true,
                     Item::Scene(_) => true,
                     Item::Page(_)  => true,
                     Item::Mat(_)   => false,
@@ -779,6 +810,35 @@ impl ScenarioNode {
             _ => ()
         }
     }
+    //// group /////////////////////////////////////////////
+    // group_name //////////////////////////////////////////
+    /// None for an unnamed group (e.g. loaded from an older project)
+    pub fn get_group_name(&self) -> Option<String>{
+        match &(*self.value.borrow()){
+            Item::Group(g) if !g.name.trim().is_empty() => {
+                Some(g.name.clone())
+            },
+            _ => None,
+        }
+    }
+    pub fn set_group_name(&self, name: &str) {
+        match *self.value.borrow_mut(){
+            Item::Group(ref mut g) => {
+                g.name = name.to_string();
+            },
+            _ => ()
+        }
+    }
+    //// page //////////////////////////////////////////////
+    // page_name ///////////////////////////////////////////
+    /// name of a page or pmat
+    pub fn get_page_name(&self) -> Option<String>{
+        match &(*self.value.borrow()){
+            Item::Page(p) => Some(p.name.clone()),
+            Item::Pmat(m) => Some(m.name.clone()),
+            _ => None,
+        }
+    }
     //// mat ///////////////////////////////////////////////
     // mat_pos_dim /////////////////////////////////////////
     pub fn get_mat_pos_dim_with_label(sn: Rc::<ScenarioNode>) -> Option<(i32, i32, i32, i32)>{
@@ -856,6 +916,93 @@ impl ScenarioNode {
             _ => ()
         }
     }
+    // mat_text_l10n ///////////////////////////////////
+    /// translated text of lang, falls back to the original text when it is
+    /// not translated. An empty lang means the original text.
+    pub fn get_mat_text_lang(&self, lang: &str) -> Option<String>{
+        match &(*self.value.borrow()){
+            Item::Mat(ref m) | Item::Pmat(ref m) => {
+                match m.text_l10n.get(lang) {
+                    Some(t) if !t.is_empty() => Some(t.clone()),
+                    _                        => Some(m.text.clone()),
+                }
+            },
+            _ => None,
+        }
+    }
+    pub fn get_mat_text_l10n(&self, lang: &str) -> Option<String>{
+        match &(*self.value.borrow()){
+            Item::Mat(ref m) | Item::Pmat(ref m) => {
+                m.text_l10n.get(lang).cloned()
+            },
+            _ => None,
+        }
+    }
+    pub fn set_mat_text_l10n(&self, lang: &str, text: &str) {
+        match *self.value.borrow_mut(){
+            Item::Mat(ref mut m) | Item::Pmat(ref mut m) => {
+                m.text_l10n.insert(lang.to_string(), text.to_string());
+            },
+            _ => ()
+        }
+    }
+    /// text for editing: key "" is the original text, an untranslated key is empty
+    pub fn get_mat_text_variant(&self, key: &str) -> Option<String>{
+        if key.is_empty() { return self.get_mat_text(); }
+        match &(*self.value.borrow()){
+            Item::Mat(ref m) | Item::Pmat(ref m) => {
+                Some(m.text_l10n.get(key).cloned().unwrap_or_default())
+            },
+            _ => None,
+        }
+    }
+    /// an empty text removes the translation of key
+    pub fn set_mat_text_variant(&self, key: &str, text: &str) {
+        if key.is_empty() { return self.set_mat_text(text); }
+        match *self.value.borrow_mut(){
+            Item::Mat(ref mut m) | Item::Pmat(ref mut m) => {
+                if text.is_empty() {
+                    m.text_l10n.remove(key);
+                } else {
+                    m.text_l10n.insert(key.to_string(), text.to_string());
+                }
+            },
+            _ => ()
+        }
+    }
+    // mat_speaker /////////////////////////////////////
+    pub fn get_mat_speaker(&self) -> Option<String>{
+        match &(*self.value.borrow()){
+            Item::Mat(ref m) | Item::Pmat(ref m) => {
+                m.speaker.clone()
+            },
+            _ => None,
+        }
+    }
+    pub fn set_mat_speaker(&self, speaker: Option<String>) {
+        match *self.value.borrow_mut(){
+            Item::Mat(ref mut m) | Item::Pmat(ref mut m) => {
+                m.speaker = speaker;
+            },
+            _ => ()
+        }
+    }
+    // timing //////////////////////////////////////////
+    /// timing of a page or pmat
+    pub fn get_timing(&self) -> Option<Timing>{
+        match &(*self.value.borrow()){
+            Item::Page(ref p) => Some(p.timing.clone()),
+            Item::Pmat(ref m) => Some(m.timing.clone()),
+            _ => None,
+        }
+    }
+    pub fn set_timing(&self, timing: Timing) {
+        match *self.value.borrow_mut(){
+            Item::Page(ref mut p) => { p.timing = timing; },
+            Item::Pmat(ref mut m) => { m.timing = timing; },
+            _ => ()
+        }
+    }
     // mat_font_rgba ///////////////////////////////////
     pub fn get_mat_font_rgba(&self) -> Option<Vec<u32>>{
         match &(*self.value.borrow()){
@@ -1234,7 +1381,7 @@ impl ScenarioNode {
         if let Item::Scene(_) = &(*self.value.borrow()) { true } else { false } }
     // is_group ////////////////////////////////////////////
     pub fn is_group(&self) -> bool{
-        if let Item::Group = &(*self.value.borrow()) { true } else { false } }
+        if let Item::Group(_) = &(*self.value.borrow()) { true } else { false } }
     // is_pmat /////////////////////////////////////////////
     pub fn is_pmat(&self) -> bool{
         if let Item::Pmat(_) = &(*self.value.borrow()) { true } else { false } }
@@ -1246,7 +1393,7 @@ pub enum BranchType{ Child, Neighbor, }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Item{
-    Group,
+    Group(Group),
     Scene(Scene),
     Page(Page),
     Mat(Mat),
@@ -1255,16 +1402,49 @@ pub enum Item{
 }
 impl Default for Item{
     fn default() -> Self{
-        Item::Group
+        Item::Group(Group::default())
     }
 }
+// Group //////////////////////////////////////////////////
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Group {
+    #[serde(default)]
+    pub name : String,
+}
+impl Default for Group {
+    fn default() -> Self {Self{name: "".to_string()}} }
+// Transition /////////////////////////////////////////////
+/// how a page appears after the previous one in video export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Transition {
+    #[default]
+    Cut,
+    Crossfade,
+    FadeBlack, // fades out to black, then in from black
+}
+// Timing /////////////////////////////////////////////////
+/// display timing of a page or pmat for video export
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timing {
+    #[serde(default)]
+    pub duration_ms   : Option<u32>, // None: default duration of the export
+    #[serde(default)]
+    pub transition    : Transition,
+    #[serde(default)]
+    pub transition_ms : u32,
+}
+impl Timing {
+    pub fn is_default(&self) -> bool { *self == Self::default() }
+}
 // Page ///////////////////////////////////////////////////
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Page {
     pub name : String,
+    #[serde(default, skip_serializing_if = "Timing::is_default")]
+    pub timing : Timing,
 }
 impl Default for Page {
-    fn default() -> Self {Self{name: "".to_string()}} }
+    fn default() -> Self {Self{name: "".to_string(), timing: Timing::default()}} }
 // Color ///////////////////////////////////////////////////
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Color {
@@ -1408,6 +1588,12 @@ pub struct Mat {
     pub bg_en         : bool,
     #[serde(default)]
     pub text_pos      : Position,
+    #[serde(default)]
+    pub speaker       : Option<String>,
+    #[serde(default)]
+    pub text_l10n     : BTreeMap<String, String>, // language -> translated text
+    #[serde(default, skip_serializing_if = "Timing::is_default")]
+    pub timing        : Timing,                   // used by pmat only
 }
 impl Mat {
     pub fn dump(&self) {
@@ -1447,6 +1633,9 @@ impl Default for Mat{
             bgimg         : None,
             bg_en         : false,
             text_pos      : Position::from_xy(0, 0),
+            speaker       : None,
+            text_l10n     : BTreeMap::new(),
+            timing        : Timing::default(),
         }
     }
 }
@@ -1456,6 +1645,60 @@ impl LabelledItem for Mat{
     fn get_label_type(&self) -> LabelType {
         self.lbl_type }
 }
+// Rescale /////////////////////////////////////////////////
+/// normalized geometry is stored in 1/NORMALIZED_UNITS of the target size
+pub const NORMALIZED_UNITS: i32 = 10000;
+/// scale factors between two target sizes
+/// x/w follow the width, y/h the height and r/font size/outline the shorter side
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rescale {
+    pub sx : f64,
+    pub sy : f64,
+    pub s  : f64,
+}
+impl Rescale {
+    pub fn between(from: (i32, i32), to: (i32, i32)) -> Self{
+        let (fw, fh) = (from.0.max(1) as f64, from.1.max(1) as f64);
+        let (tw, th) = (to.0.max(1)   as f64, to.1.max(1)   as f64);
+        Self{ sx: tw / fw, sy: th / fh, s: tw.min(th) / fw.min(fh) }
+    }
+    pub fn to_normalized(target: (i32, i32)) -> Self{
+        Self::between(target, (NORMALIZED_UNITS, NORMALIZED_UNITS)) }
+    pub fn from_normalized(target: (i32, i32)) -> Self{
+        Self::between((NORMALIZED_UNITS, NORMALIZED_UNITS), target) }
+    // a positive length never becomes 0
+    fn len(v: i32, k: f64) -> i32{
+        let r = (v as f64 * k).round() as i32;
+        if v > 0 { r.max(1) } else { r }
+    }
+    fn pos(&self, p: &Position) -> Position{
+        Position::from_xy(Self::len(p.x, self.sx), Self::len(p.y, self.sy)) }
+    pub fn mat(&self, m: &mut Mat){
+        m.pos         = self.pos(&m.pos);
+        m.dim         = Dimension{ w: Self::len(m.dim.w, self.sx), h: Self::len(m.dim.h, self.sy) };
+        m.text_pos    = self.pos(&m.text_pos);
+        m.r           = Self::len(m.r, self.s);
+        m.font_size   = Self::len(m.font_size, self.s);
+        m.font_outl_2 = m.font_outl_2 * self.s;
+    }
+    pub fn item(&self, i: &mut Item){
+        match i {
+            Item::Mat(m) | Item::Pmat(m) => self.mat(m),
+            Item::Ovimg(o)               => o.pos = self.pos(&o.pos),
+            _                            => (),
+        }
+    }
+    /// every mat and ovimg under root and its neighbors
+    pub fn tree(&self, root: &Rc<ScenarioNode>){
+        let mut vec = vec![root.clone()];
+        while let Some(p) = ScenarioNode::traverse(&mut vec) {
+            self.item(&mut p.value.borrow_mut());
+        }
+    }
+    pub fn serde(&self, nodes: &[ScenarioNodeSerde]){
+        nodes.iter().for_each(|n| self.item(&mut n.value.borrow_mut()));
+    }
+}
 // print_opt_str ///////////////////////////////////////////
 fn print_opt_str(a: &Option<String>, prefix: String){
     if let Some(s) = a {
@@ -260,6 +260,61 @@ fn font_weight_select_box<F>(sno            : ScenarioNodeObject,
 
     weight_box
 }
+// timing_box //////////////////////////////////////////////
+/// duration and transition of a page or pmat for video export
+fn timing_box(sno  : ScenarioNodeObject,
+              store: gio::ListStore) -> Box {
+    let timing_box     = Box::builder().orientation(Orientation::Horizontal).build();
+    let duration_label = Label::new(Some("duration[ms]"));
+    let duration_entry = Entry::builder().placeholder_text("default").build();
+    let trans_dd       = DropDown::from_strings(&["cut", "crossfade", "fade black"]);
+    let trans_entry    = Entry::builder().build();
+    let timing = sno.get_node().get_timing().unwrap_or_default();
+
+    duration_entry.set_text(&timing.duration_ms.map(|d| d.to_string()).unwrap_or_default());
+    trans_dd.set_selected(match timing.transition {
+        scenario_node::Transition::Cut       => 0,
+        scenario_node::Transition::Crossfade => 1,
+        scenario_node::Transition::FadeBlack => 2,
+    });
+    trans_entry.set_text(&timing.transition_ms.to_string());
+
+    duration_entry.connect_changed(clone!(@strong sno, @strong store => move |e|{
+        let mut timing = sno.get_node().get_timing().unwrap_or_default();
+        timing.duration_ms = match e.text().trim() {
+            "" => None,
+            d  => if let Ok(d) = d.parse::<u32>() { Some(d) } else { return; },
+        };
+        sno.get_node().set_timing(timing);
+        store.items_changed(sno.get_seq() as u32, 1, 1);
+    }));
+    trans_dd.connect_selected_notify(clone!(@strong sno, @strong store => move |dd|{
+        let mut timing = sno.get_node().get_timing().unwrap_or_default();
+        timing.transition = match dd.selected() {
+            1 => scenario_node::Transition::Crossfade,
+            2 => scenario_node::Transition::FadeBlack,
+            _ => scenario_node::Transition::Cut,
+        };
+        sno.get_node().set_timing(timing);
+        store.items_changed(sno.get_seq() as u32, 1, 1);
+    }));
+    trans_dd.add_controller(dropdown_key_controller(trans_dd.clone()));
+    trans_entry.connect_changed(clone!(@strong sno, @strong store => move |e|{
+        if let Ok(t) = e.text().trim().parse::<u32>() {
+            let mut timing = sno.get_node().get_timing().unwrap_or_default();
+            timing.transition_ms = t;
+            sno.get_node().set_timing(timing);
+            store.items_changed(sno.get_seq() as u32, 1, 1);
+        }
+    }));
+
+    timing_box.append(&duration_label);
+    timing_box.append(&duration_entry);
+    timing_box.append(&trans_dd);
+    timing_box.append(&trans_entry);
+    timing_box.append(&Label::new(Some("[ms]")));
+    timing_box
+}
 // label_type_select_box ///////////////////////////////////
 fn label_type_select_box(sno              : ScenarioNodeObject,
                          gray_list        : Vec<impl IsA<Widget>>,
@@ -534,6 +589,21 @@ fn build_mat_attribute_box(b         : &ScenarioNodeAttributeBox,
                              round_entry.upcast_ref::<Widget>().clone()], true);
     }
 
+    // speaker /////////////////////////////////////////////
+    let speaker_box   = Box::builder().orientation(Orientation::Horizontal).build();
+    let speaker_label = Label::new(Some("speaker"));
+    let speaker_entry = Entry::builder().build();
+    speaker_entry.set_text(&sno.get_node().get_mat_speaker().unwrap_or_default());
+    speaker_entry.connect_changed(clone!(
+        @strong sno,
+        @strong store => move |se|{
+            let speaker = se.text().trim().to_string();
+            sno.get_node().set_mat_speaker(if speaker.is_empty() { None } else { Some(speaker) });
+            store.items_changed(sno.get_seq() as u32, 1, 1);
+        }));
+    speaker_box.append(&speaker_label);
+    speaker_box.append(&speaker_entry);
+
     // font family, size ///////////////////////////////////
     let font_dialog = FontDialog::builder().modal(true).build();
     let mut font_desc = FontDescription::new();
@@ -644,6 +714,7 @@ fn build_mat_attribute_box(b         : &ScenarioNodeAttributeBox,
     //temp_box.append(&name_box);
     temp_box.append(&bgimg_box.file_dialog_box);
     temp_box.append(&color_box.get_box());
+    temp_box.append(&speaker_box);
     temp_box.append(&label_box);
     temp_box.append(&posdim_box.get_box());
     temp_box.append(&round_box);
@@ -655,6 +726,9 @@ fn build_mat_attribute_box(b         : &ScenarioNodeAttributeBox,
     temp_box.append(&outl_box.hbox);
     temp_box.append(&lspacing_box.hbox);
     temp_box.append(&vertical_box);
+    if let scenario_node::Item::Pmat(_) = *sno.get_node().value.borrow() {
+        temp_box.append(&timing_box(sno.clone(), store.clone()));
+    }
 
     focus_tag
 }
@@ -1159,8 +1233,9 @@ fn build_page_attribute_box (p       : &scenario_node::Page,
                             .text(GString::from_string_checked(p.name.clone()).expect("page name is expected") ).build() );
 
     page_entry.connect_changed(glib::clone!(@strong store, @strong sno => move |e| {
-        *sno.get_node().value.borrow_mut() =
-            scenario_node::Item::Page( scenario_node::Page{ name: String::from(e.buffer().text().as_str()) });
+        if let scenario_node::Item::Page(ref mut p) = *sno.get_node().value.borrow_mut() {
+            p.name = String::from(e.buffer().text().as_str());
+        }
         store.items_changed(sno.get_seq() as u32, 1, 1);
     }));
 
@@ -1168,6 +1243,35 @@ fn build_page_attribute_box (p       : &scenario_node::Page,
     page_box.append(&page_entry);
 
     temp_box.append(&page_box);
+    temp_box.append(&timing_box(sno.clone(), store.clone()));
+}
+// build_group_attribute_box ///////////////////////////////
+fn build_group_attribute_box (g       : &scenario_node::Group,
+                              store   : gio::ListStore,
+                              sno     : ScenarioNodeObject,
+                              temp_box: &Box){
+    let group_box = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(0)
+        .build();
+    let group_label = Label::new(Some("group"));
+    let group_entry = Entry::builder()
+        .vexpand(false)
+        .valign(Align::Start)
+        .build();
+
+    group_entry.set_buffer( &EntryBuffer::builder()
+                             .text(GString::from_string_checked(g.name.clone()).expect("group name is expected") ).build() );
+
+    group_entry.connect_changed(glib::clone!(@strong store, @strong sno => move |e| {
+        sno.get_node().set_group_name(e.buffer().text().as_str());
+        store.items_changed(sno.get_seq() as u32, 1, 1);
+    }));
+
+    group_box.append(&group_label);
+    group_box.append(&group_entry);
+
+    temp_box.append(&group_box);
 }
 // change_item_type /////////////////////////////////////////
 fn change_item_type(b: ScenarioNodeAttributeBox, s: SingleSelection){
@@ -1191,6 +1295,10 @@ fn change_item_type(b: ScenarioNodeAttributeBox, s: SingleSelection){
     *b.imp().sno.borrow_mut() = Some( sno.clone() );
 
     match *sno.get_node().value.borrow(){
+        scenario_node::Item::Group(ref g) => {
+            build_group_attribute_box(g, store, sno, &temp_box);
+            *b.imp().focus_tag.borrow_mut() = None;
+        },
         scenario_node::Item::Page(ref p) => {
             build_page_attribute_box(p, store, sno, &temp_box);
             *b.imp().focus_tag.borrow_mut() = None;
@@ -84,7 +84,7 @@ impl AddNodeButton {
         add_node_button.button.set_label(label);
         add_node_button.button.connect_clicked(clone!(@strong add_node_button=> move |btn| {
                 let act_arg = match &add_node_button.node_type {
-                    scenario_node::Item::Group    => tree_manipulate::ActTreeNodeAddCmd::Group,
+                    scenario_node::Item::Group(_)    => tree_manipulate::ActTreeNodeAddCmd::Group,
                     scenario_node::Item::Scene(_) => tree_manipulate::ActTreeNodeAddCmd::Scene,
                     scenario_node::Item::Page(_)  => tree_manipulate::ActTreeNodeAddCmd::Page,
                     scenario_node::Item::Mat(_)   => tree_manipulate::ActTreeNodeAddCmd::Mat,
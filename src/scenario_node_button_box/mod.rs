@@ -14,7 +14,7 @@ use glib::object::Object;
 use crate::operation_history::OperationHistory;
 use crate::scenario_node::ScenarioNode;
 use crate::scenario_node::Item;
-use crate::scenario_node::{Scene, Page, Mat, Ovimg};
+use crate::scenario_node::{Group, Scene, Page, Mat, Ovimg};
 use crate::scenario_node;
 use crate::scenario_node_object::ScenarioNodeObject;
 use crate::scenario_node_button_box::imp::AddNodeButton;
@@ -36,7 +36,7 @@ impl ScenarioNodeButtonBox{
             } else {
                 println!("no node is selected");
                 let n = ScenarioNode::new(); // dummy node to indicate Group when empty list
-                n.set_value(Item::Group);    // empty list is treated as a group
+                n.set_value(Item::Group(Group::default()));    // empty list is treated as a group
                 let sno = ScenarioNodeObject::new_from( Rc::new(n) );
                 (sno, gio::ListStore::with_type(ScenarioNodeObject::static_type()))
             };
@@ -67,7 +67,7 @@ impl ScenarioNodeButtonBox{
             scenario_node::Item::Mat(Mat::default()),
             scenario_node::Item::Page(Page::default()),
             scenario_node::Item::Scene(Scene::default()),
-            scenario_node::Item::Group,
+            scenario_node::Item::Group(Group::default()),
         ];
         let names_len = names.len();
         for _i in 0..names_len {
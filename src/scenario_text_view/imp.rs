@@ -3,17 +3,24 @@ use once_cell::sync::Lazy;
 use glib::Object;
 use glib::WeakRef;
 use glib::subclass::Signal;
+use gtk::ScrolledWindow;
 use gtk::SingleSelection;
+use gtk::TextView;
 use gtk::glib;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use std::cell::RefCell;
 
+use crate::isv2_parameter::Isv2Parameter;
 use crate::scenario_node_object::ScenarioNodeObject;
 
 pub struct ScenarioTextView{
     pub(super) sno      : RefCell<Option<ScenarioNodeObject>>,
     pub(super) mediator : RefCell<WeakRef<Object>>,
+    pub(super) parameter: RefCell<WeakRef<Isv2Parameter>>,
+    pub(super) text_key : RefCell<String>,              // language being edited ("" is Mat::text)
+    pub(super) ref_view : RefCell<Option<TextView>>,    // text of the reference language
+    pub(super) ref_pane : RefCell<Option<ScrolledWindow>>,
 }
 
 #[glib::object_subclass]
@@ -44,6 +51,10 @@ impl Default for ScenarioTextView {
         Self{
             sno      : None.into(),
             mediator : RefCell::new(WeakRef::new()),
+            parameter: RefCell::new(WeakRef::new()),
+            text_key : RefCell::new(String::new()),
+            ref_view : RefCell::new(None),
+            ref_pane : RefCell::new(None),
         }
     }
 }
@@ -4,11 +4,15 @@ use glib::Object;
 use glib::WeakRef;
 use glib::clone;
 use glib::closure_local;
+use gtk::PolicyType;
+use gtk::ScrolledWindow;
 use gtk::SingleSelection;
+use gtk::TextView;
 use gtk::glib;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 
+use crate::isv2_parameter::Isv2Parameter;
 use crate::sno_list::selection_to_sno;
 
 glib::wrapper! {
@@ -19,26 +23,58 @@ glib::wrapper! {
 // ScenarioTextView ////////////////////////////////////////
 impl ScenarioTextView {
     pub fn set_mediator(&self, m: WeakRef<Object>){ *self.imp().mediator.borrow_mut() = m; }
+    pub fn set_parameter(&self, p: WeakRef<Isv2Parameter>){ *self.imp().parameter.borrow_mut() = p; }
+    /// side-by-side pane which shows the text of the reference language
+    pub fn ref_pane(&self) -> ScrolledWindow { self.imp().ref_pane.borrow().clone().expect("ref_pane") }
     // sno_selected ////////////////////////////////////////
     pub fn sno_selected(&self, s: SingleSelection){
+        // language
+        let (text_key, ref_key) =
+            if let Some(param) = self.imp().parameter.borrow().upgrade() {
+                let ref_lang = param.property::<String>("ref_language");
+                (param.text_key(&param.property::<String>("language")),
+                 if ref_lang.is_empty() { None } else { Some(param.text_key(&ref_lang)) })
+            } else {
+                (String::new(), None)
+            };
+        self.ref_pane().set_visible(ref_key.is_some());
+
         // update view
         let (sno, _store) =
             if let Some((a,b)) = selection_to_sno(&s) { (a,b) } else { return; /* todo: no item */ };
-        // set sno
-        *self.imp().sno.borrow_mut() = Some( sno.clone() );
+        // set sno (cleared while setting buffer not to write the text back to other language)
+        *self.imp().sno.borrow_mut() = None;
+        *self.imp().text_key.borrow_mut() = text_key.clone();
+
+        // update reference
+        let ref_view = self.imp().ref_view.borrow().clone().expect("ref_view");
+        let ref_text = ref_key.and_then(|k| sno.get_node().get_mat_text_variant(&k));
+        ref_view.buffer().set_text( &ref_text.unwrap_or_default() );
 
         // update buffer
         let t =
-            if let Some(t) = sno.get_node().get_mat_text() { t } else {
+            if let Some(t) = sno.get_node().get_mat_text_variant(&text_key) { t } else {
                 self.buffer().set_text( "" );
                 return; /* not mat/pmat */ };
         self.buffer().set_text( &t );
+        *self.imp().sno.borrow_mut() = Some( sno.clone() );
     }
 
     // new /////////////////////////////////////////////////
     pub fn new() -> Self {
         let obj:ScenarioTextView = Object::builder().build();
 
+        let ref_view = TextView::builder().editable(false).cursor_visible(false).build();
+        let ref_pane = ScrolledWindow::builder()
+            .hscrollbar_policy(PolicyType::Automatic)
+            .vscrollbar_policy(PolicyType::Automatic)
+            .hexpand(true).vexpand(true)
+            .child(&ref_view)
+            .visible(false)
+            .build();
+        *obj.imp().ref_view.borrow_mut() = Some(ref_view);
+        *obj.imp().ref_pane.borrow_mut() = Some(ref_pane);
+
         obj.connect_closure(
             "sno-selected",
             false,
@@ -49,7 +85,8 @@ impl ScenarioTextView {
 
         obj.buffer().connect_text_notify( clone!( @strong obj => move |s|{
             let sno = if let Some( sno ) = obj.imp().sno.borrow().as_ref() { sno.clone() } else { return; };
-            sno.get_node().set_mat_text( &s.text( &s.start_iter(), &s.end_iter(), true ).to_string() )  ;
+            let text_key = obj.imp().text_key.borrow().clone();
+            sno.get_node().set_mat_text_variant( &text_key, &s.text( &s.start_iter(), &s.end_iter(), true ).to_string() )  ;
             obj.imp().mediator.borrow().upgrade().expect("mediator").emit_by_name::<()>("mat-attribute-changed", &[&sno]);
 
         }) );
@@ -0,0 +1,475 @@
+pub mod translation{
+    //! translation round-trip of mat texts with gettext PO / XLIFF 1.2
+    //!
+    //! entries are keyed by the node id (msgctxt in PO, trans-unit id in XLIFF).
+    //! imported texts are stored in Mat::text_l10n, the original Mat::text is
+    //! never overwritten, so the base language (the first of the project
+    //! languages) can not be exported or imported.
+
+    use std::collections::HashMap;
+    use std::path::Path;
+    use std::rc::Rc;
+
+    use anyhow::Context;
+
+    use crate::isv2_parameter::Isv2Parameter;
+    use crate::scenario_node::ScenarioNode;
+    use crate::scenario_node::Item;
+
+    // TranslationEntry ////////////////////////////////////
+    #[derive(Debug, Clone, PartialEq, Default)]
+    pub struct TranslationEntry{
+        pub id     : i32,
+        pub source : String,
+        pub target : String,
+        pub note   : String,
+        pub fuzzy  : bool,
+    }
+    // ImportReport ////////////////////////////////////////
+    #[derive(Debug, Default)]
+    pub struct ImportReport{
+        pub lang           : String,
+        pub translated     : usize,
+        pub untranslated   : Vec<(i32, String)>, // (id, original text)
+        pub changed_source : Vec<i32>,           // translated, but the original text was edited after extraction
+        pub unknown_ids    : Vec<i32>,
+    }
+    impl ImportReport{
+        pub fn summary(&self) -> String{
+            let mut s = format!("language \"{}\": {} translated, {} untranslated",
+                                self.lang, self.translated, self.untranslated.len());
+            if !self.changed_source.is_empty() {
+                s += &format!(", {} with changed source", self.changed_source.len()); }
+            if !self.unknown_ids.is_empty() {
+                s += &format!(", {} unknown ids", self.unknown_ids.len()); }
+            s
+        }
+        pub fn detail(&self, max_lines: usize) -> String{
+            let mut lines: Vec<String> = Vec::new();
+            for (id, text) in &self.untranslated {
+                lines.push(format!("untranslated {}: {}", id, text.replace('\n', " "))); }
+            for id in &self.changed_source {
+                lines.push(format!("source changed {}", id)); }
+            for id in &self.unknown_ids {
+                lines.push(format!("unknown id {}", id)); }
+            let rest = lines.len().saturating_sub(max_lines);
+            lines.truncate(max_lines);
+            if rest > 0 {
+                lines.push(format!("... and {} more", rest)); }
+            lines.join("\n")
+        }
+    }
+
+    // lang_from_path //////////////////////////////////////
+    /// "en.po" -> "en"
+    pub fn lang_from_path(path: &Path) -> Option<String>{
+        let stem = path.file_stem()?.to_str()?;
+        if stem.is_empty() { None } else { Some(stem.to_string()) }
+    }
+
+    // extract_entries /////////////////////////////////////
+    /// mats with text in tree order, target is the current translation of lang
+    pub fn extract_entries(root: &Rc<ScenarioNode>, lang: &str) -> Vec<TranslationEntry>{
+        let mut entries = Vec::new();
+        let mut img_seq = 0;
+        let mut vec = vec![root.clone()];
+        while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+            let note = match &*sn.value.borrow() {
+                Item::Page(_)  => { img_seq += 1; continue; },
+                Item::Pmat(_)  => { img_seq += 1; format!("page {:04}", img_seq - 1) },
+                Item::Mat(_)   => {
+                    let in_page = ScenarioNode::get_container(&sn).map(|p| p.is_page()).unwrap_or(false);
+                    if in_page { format!("page {:04}", img_seq - 1) } else { "scene".to_string() }
+                },
+                _              => continue,
+            };
+            let source = sn.get_mat_text().unwrap_or_default();
+            if source.trim().is_empty() { continue; }
+            let note = if let Some(s) = sn.get_mat_speaker() { format!("{note}, speaker: {s}") } else { note };
+            entries.push(TranslationEntry{
+                id     : sn.id.get(),
+                source,
+                target : sn.get_mat_text_l10n(lang).unwrap_or_default(),
+                note,
+                fuzzy  : false,
+            });
+        }
+        entries
+    }
+    // apply_entries ///////////////////////////////////////
+    pub fn apply_entries(root: &Rc<ScenarioNode>, lang: &str, entries: &[TranslationEntry]) -> ImportReport{
+        let mut report = ImportReport{ lang: lang.to_string(), ..Default::default() };
+
+        let mut nodes: HashMap<i32, Rc<ScenarioNode>> = HashMap::new();
+        let mut vec = vec![root.clone()];
+        while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+            if sn.is_mat() || sn.is_pmat() {
+                nodes.insert(sn.id.get(), sn.clone()); }
+        }
+        for e in entries {
+            let sn = if let Some(sn) = nodes.get(&e.id) { sn } else {
+                report.unknown_ids.push(e.id); continue; };
+            if e.fuzzy || e.target.is_empty() { continue; }
+            sn.set_mat_text_l10n(lang, &e.target);
+            if sn.get_mat_text().unwrap_or_default() != e.source {
+                report.changed_source.push(e.id); }
+        }
+        // report mats without translation in tree order
+        for e in extract_entries(root, lang) {
+            if e.target.is_empty() {
+                report.untranslated.push((e.id, e.source));
+            } else {
+                report.translated += 1;
+            }
+        }
+        report
+    }
+
+    // po //////////////////////////////////////////////////
+    fn po_escape(s: &str) -> String{
+        s.replace('\\', "\\\\").replace('"', "\\\"").replace('\t', "\\t").replace('\n', "\\n")
+    }
+    fn po_unescape(s: &str) -> String{
+        let mut out = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' { out.push(c); continue; }
+            match chars.next() {
+                Some('n')  => out.push('\n'),
+                Some('t')  => out.push('\t'),
+                Some('r')  => out.push('\r'),
+                Some(c)    => out.push(c),
+                None       => out.push('\\'),
+            }
+        }
+        out
+    }
+    fn po_string(keyword: &str, s: &str) -> String{
+        if !s.contains('\n') {
+            return format!("{} \"{}\"\n", keyword, po_escape(s)); }
+        let mut out = format!("{} \"\"\n", keyword);
+        for line in s.split_inclusive('\n') {
+            out += &format!("\"{}\"\n", po_escape(line)); }
+        out
+    }
+    pub fn write_po(entries: &[TranslationEntry], lang: &str) -> String{
+        let mut out = String::new();
+        out += "msgid \"\"\n";
+        out += "msgstr \"\"\n";
+        out += &format!("\"Language: {}\\n\"\n", po_escape(lang));
+        out += "\"MIME-Version: 1.0\\n\"\n";
+        out += "\"Content-Type: text/plain; charset=UTF-8\\n\"\n";
+        out += "\"Content-Transfer-Encoding: 8bit\\n\"\n";
+        for e in entries {
+            out += "\n";
+            if !e.note.is_empty() {
+                out += &format!("#. {}\n", e.note); }
+            if e.fuzzy {
+                out += "#, fuzzy\n"; }
+            out += &po_string("msgctxt", &e.id.to_string());
+            out += &po_string("msgid",   &e.source);
+            out += &po_string("msgstr",  &e.target);
+        }
+        out
+    }
+    /// returns (language in the header, entries)
+    pub fn read_po(text: &str) -> anyhow::Result<(Option<String>, Vec<TranslationEntry>)>{
+        #[derive(PartialEq)]
+        enum Field { None, Ctxt, Id, Str }
+
+        let mut lang    = None;
+        let mut entries = Vec::new();
+        let mut field   = Field::None;
+        let (mut ctxt, mut id, mut msgstr) = (None::<String>, String::new(), String::new());
+        let (mut note, mut fuzzy) = (String::new(), false);
+
+        let mut flush = |ctxt: &mut Option<String>, id: &mut String, msgstr: &mut String,
+                         note: &mut String, fuzzy: &mut bool| -> anyhow::Result<()>{
+            if let Some(c) = ctxt.take() {
+                let node_id = c.trim().parse::<i32>()
+                    .with_context(||format!("invalid msgctxt: {c:?}"))?;
+                entries.push(TranslationEntry{
+                    id     : node_id,
+                    source : std::mem::take(id),
+                    target : std::mem::take(msgstr),
+                    note   : std::mem::take(note),
+                    fuzzy  : *fuzzy,
+                });
+            } else if id.is_empty() {
+                // header
+                for l in msgstr.lines() {
+                    if let Some(v) = l.strip_prefix("Language:") {
+                        let v = v.trim();
+                        if !v.is_empty() { lang = Some(v.to_string()); }
+                    }
+                }
+            }
+            id.clear(); msgstr.clear(); note.clear();
+            *fuzzy = false;
+            Ok(())
+        };
+
+        for (n, line) in text.trim_start_matches('\u{feff}').lines().enumerate() {
+            let line = line.trim();
+            let quoted = |s: &str| -> anyhow::Result<String>{
+                let s = s.trim();
+                if s.len() < 2 || !s.starts_with('"') || !s.ends_with('"') {
+                    anyhow::bail!("line {}: string is expected: {:?}", n+1, s); }
+                Ok(po_unescape(&s[1..s.len()-1]))
+            };
+            if line.is_empty() {
+                continue;
+            } else if let Some(c) = line.strip_prefix("#.") {
+                if field == Field::Str { flush(&mut ctxt, &mut id, &mut msgstr, &mut note, &mut fuzzy)?; field = Field::None; }
+                note = c.trim().to_string();
+            } else if let Some(f) = line.strip_prefix("#,") {
+                if field == Field::Str { flush(&mut ctxt, &mut id, &mut msgstr, &mut note, &mut fuzzy)?; field = Field::None; }
+                fuzzy = f.split(',').any(|f| f.trim() == "fuzzy");
+            } else if line.starts_with('#') {
+                continue;
+            } else if let Some(s) = line.strip_prefix("msgctxt ") {
+                if field == Field::Str { flush(&mut ctxt, &mut id, &mut msgstr, &mut note, &mut fuzzy)?; }
+                ctxt  = Some(quoted(s)?);
+                field = Field::Ctxt;
+            } else if let Some(s) = line.strip_prefix("msgid ") {
+                if field == Field::Str { flush(&mut ctxt, &mut id, &mut msgstr, &mut note, &mut fuzzy)?; }
+                id    = quoted(s)?;
+                field = Field::Id;
+            } else if let Some(s) = line.strip_prefix("msgstr ") {
+                msgstr = quoted(s)?;
+                field  = Field::Str;
+            } else if line.starts_with('"') {
+                let s = quoted(line)?;
+                match field {
+                    Field::Ctxt => { if let Some(c) = ctxt.as_mut() { c.push_str(&s); } },
+                    Field::Id   => { id.push_str(&s); },
+                    Field::Str  => { msgstr.push_str(&s); },
+                    Field::None => { anyhow::bail!("line {}: unexpected string", n+1); },
+                }
+            } else {
+                anyhow::bail!("line {}: unknown keyword: {:?}", n+1, line);
+            }
+        }
+        if field == Field::Str {
+            flush(&mut ctxt, &mut id, &mut msgstr, &mut note, &mut fuzzy)?; }
+        Ok((lang, entries))
+    }
+
+    // xliff ///////////////////////////////////////////////
+    pub fn xml_escape(s: &str) -> String{
+        s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    }
+    fn xml_unescape(s: &str) -> String{
+        let mut out = String::new();
+        let mut rest = s;
+        while let Some(p) = rest.find('&') {
+            out.push_str(&rest[..p]);
+            rest = &rest[p..];
+            let end = if let Some(e) = rest.find(';') { e } else { break; };
+            let decoded = match &rest[1..end] {
+                "amp"  => Some('&'),
+                "lt"   => Some('<'),
+                "gt"   => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                e if e.starts_with("#x") => u32::from_str_radix(&e[2..], 16).ok().and_then(char::from_u32),
+                e if e.starts_with('#')  => e[1..].parse::<u32>().ok().and_then(char::from_u32),
+                _ => None,
+            };
+            if let Some(d) = decoded {
+                out.push(d);
+                rest = &rest[end+1..];
+            } else {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+    pub fn write_xliff(entries: &[TranslationEntry], lang: &str) -> String{
+        let mut out = String::new();
+        out += "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+        out += "<xliff version=\"1.2\" xmlns=\"urn:oasis:names:tc:xliff:document:1.2\">\n";
+        out += &format!("  <file original=\"isv2\" source-language=\"und\" target-language=\"{}\" datatype=\"plaintext\">\n",
+                        xml_escape(lang));
+        out += "    <body>\n";
+        for e in entries {
+            out += &format!("      <trans-unit id=\"{}\" xml:space=\"preserve\">\n", e.id);
+            out += &format!("        <source>{}</source>\n", xml_escape(&e.source));
+            if e.fuzzy {
+                out += &format!("        <target state=\"needs-review-translation\">{}</target>\n", xml_escape(&e.target));
+            } else {
+                out += &format!("        <target>{}</target>\n", xml_escape(&e.target));
+            }
+            if !e.note.is_empty() {
+                out += &format!("        <note>{}</note>\n", xml_escape(&e.note)); }
+            out += "      </trans-unit>\n";
+        }
+        out += "    </body>\n";
+        out += "  </file>\n";
+        out += "</xliff>\n";
+        out
+    }
+    // attribute value in a start tag
+    fn xml_attr(tag: &str, name: &str) -> Option<String>{
+        let key = format!(" {}=", name);
+        let p = tag.find(&key)? + key.len();
+        let quote = tag[p..].chars().next()?;
+        if quote != '"' && quote != '\'' { return None; }
+        let v = &tag[p+1..];
+        Some(xml_unescape(&v[..v.find(quote)?]))
+    }
+    // (start tag, content) of the first element `name` in s
+    fn xml_element<'a>(s: &'a str, name: &str) -> Option<(&'a str, &'a str)>{
+        let open = format!("<{}", name);
+        let mut from = 0;
+        loop {
+            let p = from + s[from..].find(&open)?;
+            let after = &s[p+open.len()..];
+            // skip elements with the same prefix (e.g. <target-language>)
+            if !after.starts_with([' ', '>', '/', '\n', '\t', '\r']) { from = p + 1; continue; }
+            let tag_end = p + open.len() + after.find('>')?;
+            let tag = &s[p..tag_end];
+            if tag.ends_with('/') {
+                return Some((tag, "")); }
+            let close = format!("</{}>", name);
+            let content_end = tag_end + 1 + s[tag_end+1..].find(&close)?;
+            return Some((tag, &s[tag_end+1..content_end]));
+        }
+    }
+    /// returns (target-language, entries)
+    pub fn read_xliff(text: &str) -> anyhow::Result<(Option<String>, Vec<TranslationEntry>)>{
+        let lang = xml_element(text, "file").and_then(|(tag, _)| xml_attr(tag, "target-language"));
+        let mut entries = Vec::new();
+        let mut rest = text;
+        while let Some((tag, content)) = xml_element(rest, "trans-unit") {
+            let id = xml_attr(tag, "id").context("trans-unit without id")?;
+            let id = id.trim().parse::<i32>().with_context(||format!("invalid trans-unit id: {id:?}"))?;
+            let source = xml_element(content, "source").map(|(_, c)| xml_unescape(c)).unwrap_or_default();
+            let (target, fuzzy) = if let Some((tag, c)) = xml_element(content, "target") {
+                let state = xml_attr(tag, "state").unwrap_or_default();
+                (xml_unescape(c), state == "new" || state.starts_with("needs-"))
+            } else {
+                (String::new(), false)
+            };
+            let note = xml_element(content, "note").map(|(_, c)| xml_unescape(c)).unwrap_or_default();
+            entries.push(TranslationEntry{ id, source, target, note, fuzzy });
+
+            // content is a subslice of rest
+            let consumed = content.as_ptr() as usize - rest.as_ptr() as usize + content.len();
+            rest = &rest[consumed..];
+        }
+        Ok((lang, entries))
+    }
+
+    // l10n_key ////////////////////////////////////////////
+    /// the key of lang in Mat::text_l10n
+    fn l10n_key(param: &Isv2Parameter, lang: &str) -> anyhow::Result<String>{
+        let key = param.text_key(lang);
+        anyhow::ensure!(!key.is_empty(), "\"{lang}\" is the base language of the project, it has no translation");
+        Ok(key)
+    }
+    // export_translation //////////////////////////////////
+    /// the format is selected by the extension (po, xlf/xliff), the language
+    /// is the file name ("en.po" -> "en")
+    pub fn export_translation(root: &Rc<ScenarioNode>, param: &Isv2Parameter, path: &Path) -> anyhow::Result<()>{
+        let lang = lang_from_path(path).with_context(||format!("no language in {path:?}"))?;
+        let entries = extract_entries(root, &l10n_key(param, &lang)?);
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        let text = match ext.as_str() {
+            "po"            => write_po(&entries, &lang),
+            "xlf" | "xliff" => write_xliff(&entries, &lang),
+            _               => anyhow::bail!("unknown translation format: {path:?}"),
+        };
+        std::fs::write(path, text).with_context(||format!("writing {path:?} failed"))?;
+        Ok(())
+    }
+    // import_translation //////////////////////////////////
+    /// the language is taken from the file (PO header / target-language), or from the file name
+    pub fn import_translation(root: &Rc<ScenarioNode>, param: &Isv2Parameter, path: &Path) -> anyhow::Result<ImportReport>{
+        let text = std::fs::read_to_string(path).with_context(||format!("reading {path:?} failed"))?;
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        let (lang, entries) = match ext.as_str() {
+            "po"            => read_po(&text)?,
+            "xlf" | "xliff" => read_xliff(&text)?,
+            _               => anyhow::bail!("unknown translation format: {path:?}"),
+        };
+        let lang = lang.or(lang_from_path(path)).with_context(||format!("no language in {path:?}"))?;
+        Ok(apply_entries(root, &l10n_key(param, &lang)?, &entries))
+    }
+
+    // test ////////////////////////////////////////////////
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::import_util::script_import;
+
+        fn sample() -> Rc<ScenarioNode>{
+            let script = "@image a.png\n\
+                          <b>hello</b> & \"bye\"\n\
+                          second line\n\
+                          \n\
+                          mat 1\n\
+                          --\n\
+                          mat 2\n";
+            script_import::import_script(script, None, Path::new("."), Path::new(".")).unwrap()
+        }
+
+        #[test]
+        fn test_po_round_trip() {
+            let root = sample();
+            let mut entries = extract_entries(&root, "en");
+            assert_eq!(entries.len(), 3);
+            assert_eq!(entries[0].note, "page 0000");
+            assert_eq!(entries[2].note, "page 0001");
+
+            entries[0].target = "<b>hi</b>\nline 2".to_string();
+            entries[1].target = "m1".to_string();
+            let po = write_po(&entries, "en");
+            let (lang, read) = read_po(&po).unwrap();
+            assert_eq!(lang, Some("en".to_string()));
+            assert_eq!(read, entries);
+
+            let report = apply_entries(&root, "en", &read);
+            assert_eq!(report.translated, 2);
+            assert_eq!(report.untranslated, vec![(entries[2].id, "mat 2".to_string())]);
+            let pmat = root.child.borrow().clone().unwrap();
+            assert_eq!(pmat.get_mat_text_lang("en"), Some("<b>hi</b>\nline 2".to_string()));
+            assert_eq!(pmat.get_mat_text_lang(""), Some("<b>hello</b> & \"bye\"\nsecond line".to_string()));
+        }
+
+        #[test]
+        fn test_read_po_fuzzy_and_unknown() {
+            let po = "msgid \"\"\nmsgstr \"Language: fr\\n\"\n\n\
+                      #, fuzzy\nmsgctxt \"1\"\nmsgid \"a\"\nmsgstr \"b\"\n\n\
+                      msgctxt \"2\"\nmsgid \"c\"\nmsgstr \"\"\n\"d\"\n";
+            let (lang, entries) = read_po(po).unwrap();
+            assert_eq!(lang, Some("fr".to_string()));
+            assert!(entries[0].fuzzy);
+            assert_eq!(entries[1].target, "d");
+
+            let root = sample();
+            let report = apply_entries(&root, "fr", &entries);
+            assert_eq!(report.unknown_ids, vec![1, 2]);
+            assert_eq!(report.untranslated.len(), 3);
+        }
+
+        #[test]
+        fn test_xliff_round_trip() {
+            let root = sample();
+            let mut entries = extract_entries(&root, "en");
+            entries[0].target = "<i>hi</i> & 'bye'".to_string();
+            entries[1].fuzzy = true;
+            entries[1].target = "draft".to_string();
+            let xliff = write_xliff(&entries, "en");
+            let (lang, read) = read_xliff(&xliff).unwrap();
+            assert_eq!(lang, Some("en".to_string()));
+            assert_eq!(read, entries);
+
+            let report = apply_entries(&root, "en", &read);
+            assert_eq!(report.translated, 1);
+            assert_eq!(report.untranslated.len(), 2);
+        }
+    }
+}
@@ -18,7 +18,7 @@ pub mod tree_manipulate{
     use crate::operation_history::OperationHistoryItem;
     use crate::operation_history::TreeManipulationHandle;
     use crate::scenario_node::ScenarioNode;
-    use crate::scenario_node::{Scene, Page, Mat, Ovimg};
+    use crate::scenario_node::{Group, Scene, Page, Mat, Ovimg};
     use crate::scenario_node;
     use crate::scenario_node_object::ScenarioNodeObject;
     use crate::scenario_node_object::add_child;
@@ -72,7 +72,7 @@ pub mod tree_manipulate{
             // prepare new node ////////////////////////////
             let new_node = ScenarioNodeObject::new_with_seq_id(0, tree_manipulate::gen_id());
             *new_node.get_node().value.borrow_mut() = {
-                if      val == ActTreeNodeAddCmd::Group as i32 { scenario_node::Item::Group }
+                if      val == ActTreeNodeAddCmd::Group as i32 { scenario_node::Item::Group(Group::default()) }
                 else if val == ActTreeNodeAddCmd::Scene as i32 { scenario_node::Item::Scene(Scene::default()) }
                 else if val == ActTreeNodeAddCmd::Page  as i32 { scenario_node::Item::Page(Page::default()) }
                 else if val == ActTreeNodeAddCmd::Mat   as i32 { scenario_node::Item::Mat(Mat::default()) }
@@ -87,7 +87,7 @@ pub mod tree_manipulate{
                 } else {
                     println!("no node is selected");
                     let n = ScenarioNode::new(); // dummy node to indicate Group when empty list
-                    n.set_value(scenario_node::Item::Group);    // empty list is treated as a group
+                    n.set_value(scenario_node::Item::Group(Group::default()));    // empty list is treated as a group
                     let sno = ScenarioNodeObject::new_from( Rc::new(n) );
                     (sno, gio::ListStore::with_type(ScenarioNodeObject::static_type()))
                 };
@@ -135,9 +135,9 @@ pub mod tree_manipulate{
             // ope-sel conditions //////////////////////////////
             match *new_node.get_node().value.borrow() { // ope
                 // ope:grp /////////////////////////////////////
-                scenario_node::Item::Group => {
+                scenario_node::Item::Group(_) => {
                     match *sel_sno.get_node().value.borrow() { // sel
-                        scenario_node::Item::Group |
+                        scenario_node::Item::Group(_) |
                         scenario_node::Item::Scene(_) => { // ope:grp, sel:grp,scn
                             ope_type = Operation::AddNeighbor;
                         },
@@ -155,7 +155,7 @@ pub mod tree_manipulate{
                 // ope:scn /////////////////////////////////////
                 scenario_node::Item::Scene(_) => {
                     match *sel_sno.get_node().value.borrow() { // sel
-                        scenario_node::Item::Group => { // ope:scn, sel:grp
+                        scenario_node::Item::Group(_) => { // ope:scn, sel:grp
                             ope_type = Operation::AddChild;
                         },
                         scenario_node::Item::Scene(_) => { // ope:scn, sel:scn
@@ -182,7 +182,7 @@ pub mod tree_manipulate{
                 scenario_node::Item::Page(_) |
                 scenario_node::Item::Pmat(_) => {
                     match *sel_sno.get_node().value.borrow() { // sel
-                        scenario_node::Item::Group => {
+                        scenario_node::Item::Group(_) => {
                             return;
                         },
                         scenario_node::Item::Scene(_) => {
@@ -207,7 +207,7 @@ pub mod tree_manipulate{
                 scenario_node::Item::Mat(_) |
                 scenario_node::Item::Ovimg(_) => {
                     match *sel_sno.get_node().value.borrow() { // sel
-                        scenario_node::Item::Group => {
+                        scenario_node::Item::Group(_) => {
                             return;
                         },
                         scenario_node::Item::Scene(_) => {
@@ -330,9 +330,27 @@ pub mod tree_manipulate{
         }
     }
     // gen_id /////////////////////////////////////////////////
+    static ID_COUNT: AtomicI32 = AtomicI32::new(1000);
     pub fn gen_id() -> i32 {
-        static COUNT: AtomicI32 = AtomicI32::new(1000);
-        COUNT.fetch_add(1, Ordering::SeqCst)
+        ID_COUNT.fetch_add(1, Ordering::SeqCst)
+    }
+    // fix_loaded_ids //////////////////////////////////////////
+    /// ids are used as the keys of translation files, so they must be unique
+    /// across sessions. This moves the counter of gen_id after the loaded ids,
+    /// and gives new ids to the duplicated nodes (made by the older versions).
+    pub fn fix_loaded_ids(root: &Rc<ScenarioNode>){
+        let mut vec = vec![root.clone()];
+        let mut max_id = 0;
+        while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+            max_id = max_id.max(sn.id.get()); }
+        ID_COUNT.fetch_max(max_id + 1, Ordering::SeqCst);
+
+        let mut ids = std::collections::HashSet::new();
+        let mut vec = vec![root.clone()];
+        while let Some(sn) = ScenarioNode::traverse(&mut vec) {
+            if !ids.insert(sn.id.get()) {
+                sn.id.set(gen_id()); }
+        }
     }
     // append_neighbors ////////////////////////////////////////
     pub fn append_neighbors(model: &ListStore, sn: Rc<ScenarioNode>, seq: i32){
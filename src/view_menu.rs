@@ -1,4 +1,6 @@
 pub mod view_actions{
+    use std::rc::Rc;
+
     use gtk::SingleSelection;
     use gtk::TreeListRow;
     use gtk::gio::SimpleAction;
@@ -20,6 +22,7 @@ pub mod view_actions{
     use crate::isv2_mediator::Isv2Mediator;
     use crate::isv2_parameter::Isv2Parameter;
     use crate::scenario_node::Item;
+    use crate::scenario_node::ScenarioNode;
     use crate::scenario_node_object::ScenarioNodeObject;
     use crate::sno_list::selection_to_sno;
     use crate::scenario_node_attribute_box::ScenarioNodeAttributeBox;
@@ -36,6 +39,7 @@ pub mod view_actions{
     pub const ACT_CLOSE_ALL_PAGE   : &str = "view_close_all_page";
     pub const ACT_CLOSE_ALL_SCENE  : &str = "view_close_all_scene";
     pub const ACT_TOGGLE_BGIMG     : &str = "view_toggle_bgimg";
+    pub const ACT_NEXT_LANGUAGE    : &str = "view_next_language";
 
     pub const ACT_TREE_NODE_SEL : &str = "tree_node_sel";
     #[derive(Debug, Clone, Copy)]
@@ -203,6 +207,29 @@ pub mod view_actions{
                 n-= 1; }
         }
     }
+    // select_node /////////////////////////////////////////
+    /// selects the row of sn, expanding the rows of the nodes containing it
+    pub fn select_node(sel: &SingleSelection, sn: &Rc<ScenarioNode>) -> bool{
+        let mut containers = Vec::new();
+        let mut p = ScenarioNode::get_container(sn);
+        while let Some(c) = p {
+            p = ScenarioNode::get_container(&c);
+            containers.push(c);
+        }
+        let mut n = 0;
+        while n < sel.n_items() {
+            let row = sel.item(n).unwrap().downcast::<TreeListRow>().expect("row");
+            let sno = row.item().and_downcast::<ScenarioNodeObject>().expect("sno is expd");
+            let node = sno.get_node();
+            if Rc::ptr_eq(&node, sn) {
+                sel.set_selected(n);
+                return true; }
+            if containers.iter().any(|c| Rc::ptr_eq(c, &node)) {
+                row.set_expanded(true); }
+            n+= 1;
+        }
+        false
+    }
     // act_close_all_page //////////////////////////////////
     pub fn act_close_all_page(sel : SingleSelection) -> SimpleAction{
         let act = SimpleAction::new(ACT_CLOSE_ALL_PAGE, None);
@@ -259,5 +286,24 @@ pub mod view_actions{
         });
         act
     }
+    // act_next_language ///////////////////////////////////
+    /// switches the editing language to the next one of the project languages
+    pub fn act_next_language(param : Isv2Parameter, mediator: Isv2Mediator, selection: SingleSelection) -> SimpleAction{
+        let act = SimpleAction::new(ACT_NEXT_LANGUAGE, None);
+        act.connect_activate(move|_act, _val|{
+            let codes: Vec<String> = param.languages().into_iter().map(|l| l.code).collect();
+            if codes.is_empty() {
+                println!("(act_next_language) no language is set in preferences");
+                return; }
+            let cur  = param.property::<String>("language");
+            let next = match codes.iter().position(|c| *c == cur) {
+                Some(i) => codes[(i + 1) % codes.len()].clone(),
+                None    => codes[0].clone(),
+            };
+            param.set_property("language", next);
+            mediator.emit_by_name::<()>("sno-selected", &[&selection]);
+        });
+        act
+    }
 
 }